@@ -54,7 +54,6 @@ fn counter(parent: ui::CommonRef, aux: &mut Aux) -> View<AppAux, i32> {
         view.get_mut(label)
             .unwrap()
             .set_text(format!("Count: {}", count));
-        layout::update_layout(view);
     });
 
     view.set_state(|_| {});
@@ -0,0 +1,523 @@
+//! Debug overlay, drawn on top of the application.
+//!
+//! Enabled through [`AppOptions::debug_overlay`](super::AppOptions::debug_overlay), the overlay is cycled between hidden,
+//! shown and picking by pressing its [`DebugChord`](DebugChord).
+//!
+//! While shown, it outlines every widget and layout node, shades layout margins, highlights the focused and hovered widgets,
+//! and lists frame timings along with the [statistics](crate::ui::stats) of the last frame.
+//! While picking, clicking selects the topmost widget under the cursor (instead of interacting with it) and lists its properties.
+
+use {
+    crate::{
+        prelude::*,
+        ui::{self, snapshot::fmt_rect},
+    },
+    reclutch::display::{self as gfx, GraphicsDisplay},
+    std::time::{Duration, Instant},
+};
+
+/// Key press which cycles the debug overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugChord {
+    pub key: ui::KeyInput,
+    pub modifiers: ui::KeyModifiers,
+}
+
+impl DebugChord {
+    #[inline]
+    pub fn matches(&self, key: &ui::KeyEvent) -> bool {
+        key.is(self.key) && key.modifiers == self.modifiers
+    }
+}
+
+impl Default for DebugChord {
+    /// Ctrl+Shift+D.
+    fn default() -> Self {
+        DebugChord {
+            key: ui::KeyInput::D,
+            modifiers: ui::KeyModifiers {
+                shift: true,
+                ctrl: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Hidden,
+    Shown,
+    Picking,
+}
+
+const FONT_SIZE: f32 = 12.;
+const LINE_HEIGHT: f32 = 15.;
+const PANEL_PADDING: f32 = 6.;
+const PANEL_MARGIN: f32 = 8.;
+
+#[inline]
+fn rgba(r: u8, g: u8, b: u8, a: f32) -> gfx::Color {
+    gfx::Color::new(r as f32 / 255., g as f32 / 255., b as f32 / 255., a)
+}
+
+fn stroke(out: &mut gfx::DisplayListBuilder, rect: gfx::Rect, thickness: f32, color: gfx::Color) {
+    out.push_rectangle(
+        rect,
+        gfx::GraphicsDisplayPaint::Stroke(gfx::GraphicsDisplayStroke {
+            thickness,
+            color: color.into(),
+            ..Default::default()
+        }),
+        None,
+    );
+}
+
+fn fill(out: &mut gfx::DisplayListBuilder, rect: gfx::Rect, color: gfx::Color) {
+    out.push_rectangle(
+        rect,
+        gfx::GraphicsDisplayPaint::Fill(gfx::StyleColor::Color(color)),
+        None,
+    );
+}
+
+fn is_drawn(v: ui::Visibility) -> bool {
+    v != ui::Visibility::None && v != ui::Visibility::Invisible
+}
+
+/// Topmost widget under a point.
+struct Hit {
+    id: u64,
+    type_name: &'static str,
+    rect: gfx::Rect,
+}
+
+fn widget_at<T: 'static>(widget: &dyn WidgetChildren<T>, point: gfx::Point) -> Option<Hit> {
+    let v = widget.visible();
+    if !is_drawn(v) {
+        return None;
+    }
+
+    if v != ui::Visibility::NoChildren {
+        // later children are drawn on top.
+        let mut hit = None;
+        widget.for_each_child_rev(&mut |child| {
+            if hit.is_none() {
+                hit = widget_at(child, point);
+            }
+        });
+        if hit.is_some() {
+            return hit;
+        }
+    }
+
+    let rect = widget.bounds();
+    if v != ui::Visibility::NoSelf && rect.contains(point) {
+        Some(Hit {
+            id: widget.common().read(|x| x.id()),
+            type_name: widget.type_name(),
+            rect,
+        })
+    } else {
+        None
+    }
+}
+
+fn find<T: 'static>(
+    widget: &dyn WidgetChildren<T>,
+    id: u64,
+) -> Option<(&'static str, ui::CommonRef)> {
+    if widget.common().read(|x| x.id()) == id {
+        return Some((widget.type_name(), widget.common().clone()));
+    }
+
+    let mut found = None;
+    widget.for_each_child(&mut |child| {
+        if found.is_none() {
+            found = find(child, id);
+        }
+    });
+    found
+}
+
+fn outline_layout(
+    out: &mut gfx::DisplayListBuilder,
+    node: &ui::layout::DynamicNode,
+    origin: gfx::Vector,
+) {
+    let node_rect = node.rect();
+    stroke(
+        out,
+        gfx::Rect::new(node_rect.origin + origin, node_rect.size),
+        1.,
+        rgba(46, 204, 113, 0.8),
+    );
+    for (item, margins) in node.items_with_margins() {
+        let rect = item.rect();
+        let rect = gfx::Rect::new(rect.origin + origin, rect.size);
+        if margins != ui::layout::SideMargins::zero() {
+            fill(out, rect.outer_rect(margins), rgba(230, 126, 34, 0.2));
+        }
+        if let ui::layout::Item::Layout(node) = item {
+            outline_layout(out, node, origin);
+        }
+    }
+}
+
+fn outline_tree<T: 'static>(out: &mut gfx::DisplayListBuilder, widget: &dyn WidgetChildren<T>) {
+    let v = widget.visible();
+    if !is_drawn(v) {
+        return;
+    }
+
+    widget.common().read(|x| {
+        if v != ui::Visibility::NoSelf {
+            stroke(out, x.absolute_rect(), 1., rgba(52, 152, 219, 0.6));
+        }
+        if let Some(node) = x.layout() {
+            // layouts position their items relative to the widget.
+            outline_layout(out, node, x.absolute_position().to_vector());
+        }
+    });
+
+    if v != ui::Visibility::NoChildren {
+        widget.for_each_child(&mut |child| outline_tree(out, child));
+    }
+}
+
+fn describe(type_name: &str, common: &ui::Common) -> String {
+    let mut out = format!("{} @{}", ui::query::short_type_name(type_name), common.id());
+    if let Some(name) = common.name() {
+        out.push_str(" #");
+        out.push_str(name);
+    }
+    for tag in common.tags() {
+        out.push_str(" .");
+        out.push_str(tag);
+    }
+    out
+}
+
+fn properties<T: 'static>(
+    type_name: &str,
+    common: &ui::CommonRef,
+    aux: &ui::Aux<T>,
+) -> Vec<String> {
+    let focused = aux.has_focus(common);
+    common.read(|x| {
+        let mut lines = vec![
+            describe(type_name, x),
+            type_name.to_string(),
+            format!(
+                "rect {} abs {}",
+                fmt_rect(x.rect()),
+                fmt_rect(x.absolute_rect())
+            ),
+            format!(
+                "visible {:?}, layout mode {:?}, updates {}",
+                x.visible(),
+                x.layout_mode(),
+                x.updates()
+            ),
+            format!(
+                "focused {}, attached {}, cursor icon {:?}",
+                focused,
+                x.is_attached(),
+                x.cursor_icon()
+            ),
+        ];
+        if let Some(parent) = x.parent() {
+            lines.push(format!("parent @{}", parent.read(|x| x.id())));
+        }
+        if let Some(node) = x.layout() {
+            lines.push(format!(
+                "layout {} {}, {} items",
+                ui::query::short_type_name(node.type_name()),
+                fmt_rect(node.rect()),
+                node.items().len()
+            ));
+        }
+        if let Ok(components) = x.component_type_names() {
+            if !components.is_empty() {
+                let components: Vec<_> = components
+                    .into_iter()
+                    .map(ui::query::short_type_name)
+                    .collect();
+                lines.push(format!("components {}", components.join(", ")));
+            }
+        }
+        lines
+    })
+}
+
+fn load_font(display: &mut dyn GraphicsDisplay) -> Option<(gfx::ResourceReference, gfx::FontInfo)> {
+    let info = gfx::FontInfo::from_name(
+        "Consolas", // Windows
+        &[
+            "Menlo",            // MacOS
+            "Monaco",           // MacOS
+            "DejaVu Sans Mono", // Linux
+            "Liberation Mono",  // Linux
+            "Noto Sans Mono",   // Linux
+        ],
+        None,
+    )
+    .ok()?;
+    let reference = display
+        .new_resource(gfx::ResourceDescriptor::Font(gfx::ResourceData::Data(
+            gfx::SharedData::RefCount(std::sync::Arc::new(info.data()?)),
+        )))
+        .ok()?;
+    Some((reference, info))
+}
+
+pub(super) struct DebugOverlay {
+    chord: DebugChord,
+    mode: Mode,
+    selected: Option<u64>,
+    /// `None` until the font is first needed, then `Some(None)` if it couldn't be loaded.
+    font: Option<Option<(gfx::ResourceReference, gfx::FontInfo)>>,
+    cmds: gfx::CommandGroup,
+    /// Whether the overlay was drawn in the last frame.
+    drawn: bool,
+
+    last_frame: Option<Instant>,
+    frame_interval: Duration,
+    pending_update_time: Duration,
+    update_time: Duration,
+    draw_time: Duration,
+    stats: ui::stats::FrameStats,
+}
+
+impl DebugOverlay {
+    pub fn new(chord: DebugChord) -> Self {
+        DebugOverlay {
+            chord,
+            mode: Mode::Hidden,
+            selected: None,
+            font: None,
+            cmds: gfx::CommandGroup::new(),
+            drawn: false,
+
+            last_frame: None,
+            frame_interval: Duration::default(),
+            pending_update_time: Duration::default(),
+            update_time: Duration::default(),
+            draw_time: Duration::default(),
+            stats: Default::default(),
+        }
+    }
+
+    /// Returns `true` if the entire frame must be redrawn, as the overlay is (or was) drawn over it.
+    #[inline]
+    pub fn needs_redraw(&self) -> bool {
+        self.mode != Mode::Hidden || self.drawn
+    }
+
+    /// Cycles the overlay if `key` is the chord, in which case it shouldn't reach the application.
+    pub fn handle_key(&mut self, key: &ui::KeyEvent) -> bool {
+        if !self.chord.matches(key) {
+            return false;
+        }
+
+        if !key.repeat {
+            self.mode = match self.mode {
+                Mode::Hidden => Mode::Shown,
+                Mode::Shown => Mode::Picking,
+                Mode::Picking => {
+                    self.selected = None;
+                    Mode::Hidden
+                }
+            };
+        }
+        true
+    }
+
+    /// While picking, selects the widget under a mouse press, returning `true` if the input shouldn't reach the application.
+    pub fn handle_mouse<T: 'static>(
+        &mut self,
+        root: &dyn WidgetChildren<T>,
+        input: &ui::InputEvent,
+    ) -> bool {
+        if self.mode != Mode::Picking {
+            return false;
+        }
+
+        match input {
+            ui::InputEvent::MousePress(ui::MouseButton::Left, pos) => {
+                self.selected = widget_at(root, *pos).map(|x| x.id);
+                true
+            }
+            ui::InputEvent::MousePress(..) | ui::InputEvent::MouseRelease(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Accounts for time spent updating, which is reported along with the next frame.
+    #[inline]
+    pub fn record_update(&mut self, time: Duration) {
+        self.pending_update_time += time;
+    }
+
+    /// Draws the overlay, on top of everything drawn so far.
+    pub fn draw<T: 'static>(
+        &mut self,
+        root: &dyn WidgetChildren<T>,
+        display: &mut dyn GraphicsDisplay,
+        aux: &ui::Aux<T>,
+        cursor: gfx::Point,
+    ) {
+        let visible = self.mode != Mode::Hidden;
+        if !visible && !self.drawn {
+            return;
+        }
+        self.drawn = visible;
+
+        let cmds = if visible {
+            self.build(root, display, aux, cursor)
+        } else {
+            Vec::new()
+        };
+        self.cmds.repaint();
+        self.cmds
+            .push(display, &cmds, gfx::ZOrder(std::i32::MAX - 1), false, None);
+    }
+
+    /// Records the timings and statistics of a frame which was just submitted.
+    pub fn finish_frame<T: 'static>(&mut self, aux: &ui::Aux<T>, draw_time: Duration) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            self.frame_interval = now - last;
+        }
+        self.update_time = std::mem::take(&mut self.pending_update_time);
+        self.draw_time = draw_time;
+        self.stats = aux.stats.take();
+    }
+
+    fn build<T: 'static>(
+        &mut self,
+        root: &dyn WidgetChildren<T>,
+        display: &mut dyn GraphicsDisplay,
+        aux: &ui::Aux<T>,
+        cursor: gfx::Point,
+    ) -> Vec<gfx::DisplayCommand> {
+        let mut out = gfx::DisplayListBuilder::new();
+
+        outline_tree(&mut out, root);
+
+        if let Some(focus) = &aux.focus_widget {
+            stroke(
+                &mut out,
+                focus.read(|x| x.absolute_rect()),
+                2.,
+                rgba(241, 196, 15, 1.),
+            );
+        }
+
+        let hovered = widget_at(root, cursor);
+        if let Some(hit) = &hovered {
+            fill(&mut out, hit.rect, rgba(52, 152, 219, 0.2));
+        }
+
+        let selected = self.selected.and_then(|id| find(root, id));
+        if selected.is_none() {
+            self.selected = None;
+        }
+        if let Some((_, common)) = &selected {
+            stroke(
+                &mut out,
+                common.read(|x| x.absolute_rect()),
+                2.,
+                rgba(231, 76, 60, 1.),
+            );
+        }
+
+        let ms = |x: Duration| x.as_secs_f64() * 1000.;
+        let mut lines = vec![
+            format!(
+                "frame {:.1} ms, update {:.2} ms, draw {:.2} ms",
+                ms(self.frame_interval),
+                ms(self.update_time),
+                ms(self.draw_time)
+            ),
+            format!(
+                "widgets: {} updated, {} drawn, {} repainted",
+                self.stats.widgets_updated, self.stats.widgets_drawn, self.stats.widgets_repainted
+            ),
+            format!(
+                "events: {} emitted, {} input",
+                self.stats.events_emitted, self.stats.input_events
+            ),
+        ];
+        if self.mode == Mode::Picking {
+            lines.push("picking: click a widget to select it".to_string());
+        }
+        if let Some(hit) = &hovered {
+            if let Some((_, common)) = find(root, hit.id) {
+                lines.push(format!(
+                    "hover: {}",
+                    common.read(|x| describe(hit.type_name, x))
+                ));
+            }
+        }
+        if let Some((type_name, common)) = &selected {
+            lines.push(String::new());
+            lines.extend(properties(type_name, common, aux));
+        }
+
+        self.push_panel(&mut out, display, root.bounds(), lines);
+
+        out.build()
+    }
+
+    /// Lists `lines` in a panel at the top-right corner of `bounds`.
+    fn push_panel(
+        &mut self,
+        out: &mut gfx::DisplayListBuilder,
+        display: &mut dyn GraphicsDisplay,
+        bounds: gfx::Rect,
+        lines: Vec<String>,
+    ) {
+        let (font, font_info) = match self.font.get_or_insert_with(|| load_font(display)) {
+            Some(font) => font.clone(),
+            None => return,
+        };
+
+        let items: Vec<_> = lines
+            .into_iter()
+            .map(|line| gfx::TextDisplayItem {
+                text: gfx::DisplayText::Simple(line),
+                font,
+                font_info: font_info.clone(),
+                size: FONT_SIZE,
+                bottom_left: Default::default(),
+                color: gfx::StyleColor::Color(rgba(255, 255, 255, 1.)),
+            })
+            .collect();
+        let width = items
+            .iter()
+            .filter_map(|x| x.bounds().ok())
+            .map(|x| x.size.width)
+            .fold(0., f32::max);
+
+        let panel = gfx::Rect::new(
+            gfx::Point::new(
+                bounds.max_x() - width - PANEL_PADDING * 2. - PANEL_MARGIN,
+                bounds.min_y() + PANEL_MARGIN,
+            ),
+            gfx::Size::new(
+                width + PANEL_PADDING * 2.,
+                items.len() as f32 * LINE_HEIGHT + PANEL_PADDING * 2.,
+            ),
+        );
+        fill(out, panel, rgba(0, 0, 0, 0.75));
+
+        for (i, mut item) in items.into_iter().enumerate() {
+            item.set_top_left(gfx::Point::new(
+                panel.min_x() + PANEL_PADDING,
+                panel.min_y() + PANEL_PADDING + i as f32 * LINE_HEIGHT,
+            ));
+            out.push_text(item, None);
+        }
+    }
+}
@@ -0,0 +1,578 @@
+mod debug;
+mod render;
+
+pub use {debug::DebugChord, render::RenderMode};
+
+use {
+    crate::{prelude::*, theme, ui},
+    glutin::event::{self as winit_event, Event, WindowEvent},
+    reclutch::display::{self as gfx, GraphicsDisplay},
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("{0}")]
+    ContextError(#[from] glutin::ContextError),
+    #[error("{0}")]
+    CreationError(#[from] glutin::CreationError),
+    #[error("{0}")]
+    SkiaError(#[from] reclutch::error::SkiaError),
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+}
+
+type RootReadWrites<T, U> = (ui::Write<T>, ui::Write<AppAux<U>>);
+
+pub struct Root<T: 'static, W: ui::WidgetChildren<AppData<T>>> {
+    child: W,
+
+    common: ui::CommonRef,
+    listeners: ui::ListenerList<RootReadWrites<Self, T>>,
+    components: ui::ComponentList<Self>,
+}
+
+impl<T: 'static, W: ui::WidgetChildren<AppData<T>>> ui::Element for Root<T, W> {
+    type Aux = AppData<T>;
+
+    #[inline]
+    fn common(&self) -> &ui::CommonRef {
+        &self.common
+    }
+
+    #[inline]
+    fn update_components(&mut self, aux: &mut AppAux<T>) {
+        ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    fn update(&mut self, aux: &mut AppAux<T>) {
+        ui::dispatch_list::<RootReadWrites<Self, T>, _>((self, aux), |(x, _)| &mut x.listeners);
+    }
+
+    #[inline]
+    fn draw(&mut self, display: &mut dyn gfx::GraphicsDisplay, aux: &mut AppAux<T>) {
+        ui::draw(
+            self,
+            |_, aux| {
+                vec![gfx::DisplayCommand::Clear(
+                    aux.theme.color(theme::colors::BACKGROUND),
+                )]
+            },
+            display,
+            aux,
+            None,
+        )
+    }
+}
+
+impl<T: 'static, W: ui::WidgetChildren<AppData<T>>> Root<T, W> {
+    pub fn new(
+        new: impl FnOnce(ui::CommonRef, &mut AppAux<T>) -> W,
+        common: ui::CommonRef,
+        aux: &mut AppAux<T>,
+    ) -> Self {
+        let focus_listener = crate::kit::focus_handler(aux, &common, |_, _, _| {}, None);
+
+        Root {
+            child: new(common.clone(), aux),
+
+            common,
+            listeners: ui::ListenerList::new(vec![focus_listener]),
+            components: ui::ComponentList::new().and_push(crate::kit::InteractionState::new(
+                aux,
+                crate::kit::interaction_forwarder(None),
+                None,
+                None,
+            )),
+        }
+    }
+}
+
+impl<T: 'static, W: ui::WidgetChildren<AppData<T>>> ui::WidgetChildren<AppData<T>> for Root<T, W> {
+    crate::children![for <AppData<T>>; child];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppData<T> {
+    pub data: T,
+    cursor: gfx::Point,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppOptions {
+    pub window_title: String,
+    pub window_size: gfx::Size,
+    pub render_mode: RenderMode,
+    /// If set, pressing the chord cycles the debug overlay (see [`DebugChord`](DebugChord)).
+    pub debug_overlay: Option<DebugChord>,
+    /// If set, raw input is recorded to a file at this path as the application runs (see [`ui::record`](ui::record)).
+    pub record_input: Option<std::path::PathBuf>,
+    /// If set, the recording is replayed into the window in real time, starting once the event loop starts.
+    pub replay_input: Option<ui::record::Recording>,
+}
+
+impl Default for AppOptions {
+    fn default() -> Self {
+        AppOptions {
+            window_title: "Otway UI".into(),
+            window_size: gfx::Size::new(960.0, 540.0),
+            render_mode: Default::default(),
+            debug_overlay: None,
+            record_input: None,
+            replay_input: None,
+        }
+    }
+}
+
+pub struct WindowResizeEvent {
+    pub physical: gfx::Size,
+    pub logical: gfx::Size,
+}
+
+/// Runs the application until the window is closed.
+///
+/// Unless [`RenderMode::Synchronous`](RenderMode::Synchronous) is set in `options`, display commands are rendered on a separate thread.
+/// What crosses over to that thread (the OpenGL context, frames and resource descriptors) is asserted to be `Send` at compile time.
+pub fn run<T: 'static, W: ui::WidgetChildren<AppData<T>>>(
+    new: impl FnOnce(ui::CommonRef, &mut AppAux<T>) -> W,
+    aux: T,
+    theme: impl FnOnce(&mut dyn gfx::GraphicsDisplay) -> Box<dyn theme::Theme<AppData<T>>>,
+    mut options: AppOptions,
+) -> Result<(), AppError> {
+    let el = glutin::event_loop::EventLoop::with_user_event();
+    // `EventLoopProxy` isn't `Sync` on every platform.
+    let el_proxy = std::sync::Mutex::new(el.create_proxy());
+
+    let wb = glutin::window::WindowBuilder::new()
+        .with_title(options.window_title.clone())
+        .with_inner_size(glutin::dpi::PhysicalSize::new(
+            options.window_size.width,
+            options.window_size.height,
+        ));
+    let ctxt = glutin::ContextBuilder::new()
+        .with_vsync(true)
+        .build_windowed(wb, &el)?;
+    // the context is made current by the renderer, on whichever thread it runs, while the window stays on this thread.
+    let (ctxt, window) = unsafe { ctxt.split() };
+    let mut scale_factor = window.scale_factor();
+    let mut display = render::new_display(
+        ctxt,
+        (
+            options.window_size.width as _,
+            options.window_size.height as _,
+        ),
+        options.render_mode,
+    )?;
+    let central_widget = ui::CommonRef::new(None);
+    let mut aux = ui::Aux::new(
+        AppData {
+            data: aux,
+            cursor: Default::default(),
+        },
+        theme(&mut display),
+        central_widget.clone(),
+    );
+    crate::kit::register_systems(&mut aux).unwrap();
+    let wake = std::sync::Arc::new(move || {
+        let _ = el_proxy.lock().unwrap().send_event(());
+    });
+    aux.proxy.set_waker({
+        let wake = wake.clone();
+        move || wake()
+    });
+    aux.tasks.set_waker(move || wake());
+    let mut root = Root::new(new, central_widget, &mut aux);
+    root.set_layout_mode(ui::LayoutMode::Fill);
+    let mut key_mods = ui::KeyModifiers::default();
+    let mut pressed_keys = std::collections::HashSet::new();
+    // winit delivers the text of a key press as a separate `ReceivedCharacter` event, directly after the key input.
+    // The key press is held back until then so that its text can be attached.
+    let mut pending_key: Option<ui::KeyEvent> = None;
+    let mut ime_cursor_area = None;
+    // touches currently in contact, and the touch which is primary until all of them end.
+    let mut touches: Vec<u64> = Vec::new();
+    let mut primary_touch: Option<u64> = None;
+    let (mut cmds_a, mut cmds_b) = (gfx::CommandGroup::new(), gfx::CommandGroup::new());
+    let mut redraw_requested = false;
+    let mut debug_overlay = options.debug_overlay.map(debug::DebugOverlay::new);
+    let mut replayer = options
+        .replay_input
+        .take()
+        .map(ui::record::InputReplayer::new);
+
+    let initial_size = {
+        let logical = window.inner_size().to_logical::<f64>(scale_factor);
+        gfx::Size::new(logical.width as _, logical.height as _)
+    };
+    root.set_size(initial_size);
+    ui::layout::update_layout(&root);
+
+    if let Some(path) = &options.record_input {
+        aux.start_recording_to(std::fs::File::create(path)?)?;
+        // so that a replay starts at the same size.
+        aux.record_resize(initial_size);
+    }
+
+    let bg_color = aux.theme.color(theme::colors::BACKGROUND);
+
+    el.run(move |event, _window, control_flow| {
+        if *control_flow != glutin::event_loop::ControlFlow::Exit {
+            // sleep until the next timer elapses or replayed input is due, or until the next event if there are neither.
+            let replay_deadline = replayer.as_ref().and_then(|x| x.next_deadline());
+            let deadline = match (aux.next_deadline(), replay_deadline) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            *control_flow = match deadline {
+                Some(deadline) => glutin::event_loop::ControlFlow::WaitUntil(deadline),
+                None => glutin::event_loop::ControlFlow::Wait,
+            };
+        }
+
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(_),
+                ..
+            } => {}
+            _ => {
+                if let Some(key) = pending_key.take() {
+                    aux.dispatch_input(ui::InputEvent::KeyPress(key));
+                }
+            }
+        }
+
+        match event {
+            Event::NewEvents(_) => {
+                if let Some(replayer) = &mut replayer {
+                    for entry in replayer.due(std::time::Instant::now()) {
+                        match entry.input {
+                            ui::record::RecordedInput::Input(input) => {
+                                if let ui::InputEvent::MouseMove(point) = input {
+                                    aux.data.cursor = point;
+                                }
+                                aux.dispatch_input(input);
+                            }
+                            // the window reports the resize back through `WindowEvent::Resized`.
+                            ui::record::RecordedInput::Resize(size) => {
+                                window.set_inner_size(glutin::dpi::LogicalSize::new(
+                                    size.width as f64,
+                                    size.height as f64,
+                                ));
+                            }
+                        }
+                    }
+                }
+                aux.process_timers();
+                aux.run_tasks();
+            }
+            Event::UserEvent(()) => {
+                aux.process_proxied_events();
+            }
+            Event::MainEventsCleared => {
+                let overlay_visible = debug_overlay
+                    .as_ref()
+                    .map(|x| x.needs_redraw())
+                    .unwrap_or(false);
+                if overlay_visible || root.common().read(|x| x.is_dirty()) {
+                    redraw_requested = true;
+                    window.request_redraw();
+                }
+            }
+            Event::RedrawRequested(_) => {
+                // redraws requested by the platform (e.g. the window was exposed) can't be limited to a region.
+                if !redraw_requested
+                    || debug_overlay
+                        .as_ref()
+                        .map(|x| x.needs_redraw())
+                        .unwrap_or(false)
+                {
+                    aux.damage.invalidate_all();
+                }
+                redraw_requested = false;
+
+                let size = display.size();
+                if options.window_size.width != size.0 as f32
+                    || options.window_size.height != size.1 as f32
+                {
+                    display
+                        .resize((
+                            options.window_size.width as _,
+                            options.window_size.height as _,
+                        ))
+                        .expect("Display error when resizing");
+                }
+
+                cmds_a.push(
+                    &mut display,
+                    &[
+                        gfx::DisplayCommand::Save,
+                        gfx::DisplayCommand::Clear(bg_color),
+                        gfx::DisplayCommand::Scale(gfx::Vector::new(
+                            scale_factor as _,
+                            scale_factor as _,
+                        )),
+                    ],
+                    gfx::ZOrder(std::i32::MIN),
+                    false,
+                    None,
+                );
+
+                let draw_start = std::time::Instant::now();
+                ui::propagate_draw(&mut root, &mut display, &mut aux);
+                if let Some(overlay) = &mut debug_overlay {
+                    overlay.draw(&root, &mut display, &aux, aux.data.cursor);
+                }
+
+                cmds_b.push(
+                    &mut display,
+                    &[gfx::DisplayCommand::Restore],
+                    gfx::ZOrder(std::i32::MAX),
+                    false,
+                    None,
+                );
+
+                let damage = aux.damage.finish();
+                let frame = display.finish_frame(damage.clip().map(|x| {
+                    x.scale(scale_factor as f32, scale_factor as f32)
+                        .round_out()
+                }));
+                if damage != ui::damage::Damage::None {
+                    display.submit(frame);
+                }
+                if let Some(overlay) = &mut debug_overlay {
+                    overlay.finish_frame(&aux, draw_start.elapsed());
+                }
+            }
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => {
+                    *control_flow = glutin::event_loop::ControlFlow::Exit;
+                }
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor: new_scale_factor,
+                    ..
+                } => {
+                    scale_factor = new_scale_factor;
+                    let size = window.inner_size();
+                    options.window_size.width = size.width as _;
+                    options.window_size.height = size.height as _;
+
+                    cmds_a.repaint();
+                    cmds_b.repaint();
+                    aux.damage.invalidate_all();
+                    let size: glutin::dpi::LogicalSize<f64> = size.to_logical(scale_factor);
+                    let size = gfx::Size::new(size.width as _, size.height as _);
+                    root.set_size(size);
+                    aux.record_resize(size);
+                }
+                WindowEvent::Resized(size) => {
+                    options.window_size.width = size.width as _;
+                    options.window_size.height = size.height as _;
+
+                    aux.damage.invalidate_all();
+                    let size: glutin::dpi::LogicalSize<f64> = size.to_logical(scale_factor);
+                    let size = gfx::Size::new(size.width as _, size.height as _);
+                    root.set_size(size);
+                    aux.record_resize(size);
+                    aux.emit(
+                        &aux.id,
+                        WindowResizeEvent {
+                            physical: options.window_size,
+                            logical: size,
+                        },
+                    );
+                }
+                WindowEvent::ModifiersChanged(key_modifiers) => {
+                    key_mods.shift = key_modifiers.shift();
+                    key_mods.ctrl = key_modifiers.ctrl();
+                    key_mods.alt = key_modifiers.alt();
+                    key_mods.logo = key_modifiers.logo();
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    let position = position.to_logical::<f64>(scale_factor);
+                    let point = gfx::Point::new(position.x as _, position.y as _);
+                    aux.data.cursor = point;
+                    aux.dispatch_input(ui::InputEvent::MouseMove(point));
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let mouse_button = match button {
+                        winit_event::MouseButton::Left => ui::MouseButton::Left,
+                        winit_event::MouseButton::Middle => ui::MouseButton::Middle,
+                        winit_event::MouseButton::Right => ui::MouseButton::Right,
+                        winit_event::MouseButton::Other(x) => ui::MouseButton::Other(x),
+                    };
+
+                    let cursor = aux.data.cursor;
+                    let input = match state {
+                        winit_event::ElementState::Pressed => {
+                            ui::InputEvent::MousePress(mouse_button, cursor)
+                        }
+                        winit_event::ElementState::Released => {
+                            ui::InputEvent::MouseRelease(mouse_button, cursor)
+                        }
+                    };
+                    let picked = debug_overlay
+                        .as_mut()
+                        .map(|x| x.handle_mouse(&root, &input))
+                        .unwrap_or(false);
+                    if !picked {
+                        aux.dispatch_input(input);
+                    }
+                }
+                WindowEvent::KeyboardInput { input, .. } => {
+                    let key = ui::KeyEvent {
+                        scancode: input.scancode,
+                        key: input.virtual_keycode.map(Into::into),
+                        modifiers: key_mods,
+                        repeat: false,
+                        text: String::new(),
+                    };
+
+                    match input.state {
+                        winit_event::ElementState::Pressed => {
+                            let key = ui::KeyEvent {
+                                repeat: !pressed_keys.insert(input.scancode),
+                                ..key
+                            };
+                            let chord = debug_overlay
+                                .as_mut()
+                                .map(|x| x.handle_key(&key))
+                                .unwrap_or(false);
+                            if !chord {
+                                pending_key = Some(key);
+                            }
+                        }
+                        winit_event::ElementState::Released => {
+                            pressed_keys.remove(&input.scancode);
+                            aux.dispatch_input(ui::InputEvent::KeyRelease(key));
+                        }
+                    }
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let (lines, pixels) = match delta {
+                        winit_event::MouseScrollDelta::LineDelta(x, y) => {
+                            (gfx::Vector::new(x, y), gfx::Vector::zero())
+                        }
+                        winit_event::MouseScrollDelta::PixelDelta(delta) => (
+                            gfx::Vector::zero(),
+                            gfx::Vector::new(delta.x as _, delta.y as _),
+                        ),
+                    };
+
+                    let position = aux.data.cursor;
+                    aux.dispatch_input(ui::InputEvent::Scroll(ui::ScrollInput {
+                        lines,
+                        pixels,
+                        position,
+                    }));
+                }
+                WindowEvent::Touch(touch) => {
+                    let phase = match touch.phase {
+                        winit_event::TouchPhase::Started => {
+                            if touches.is_empty() {
+                                primary_touch = Some(touch.id);
+                            }
+                            touches.push(touch.id);
+                            ui::PointerPhase::Began
+                        }
+                        winit_event::TouchPhase::Moved => ui::PointerPhase::Moved,
+                        winit_event::TouchPhase::Ended => ui::PointerPhase::Ended,
+                        winit_event::TouchPhase::Cancelled => ui::PointerPhase::Cancelled,
+                    };
+                    let primary = primary_touch == Some(touch.id);
+                    if phase == ui::PointerPhase::Ended || phase == ui::PointerPhase::Cancelled {
+                        touches.retain(|&x| x != touch.id);
+                        // a touch which began after the primary touch never becomes primary mid-gesture.
+                        if touches.is_empty() {
+                            primary_touch = None;
+                        }
+                    }
+
+                    let (kind, tilt) = match touch.force {
+                        // only a stylus reports an altitude angle.
+                        Some(winit_event::Force::Calibrated {
+                            altitude_angle: Some(angle),
+                            ..
+                        }) => (ui::PointerKind::Pen, Some(angle as f32)),
+                        _ => (ui::PointerKind::Touch, None),
+                    };
+
+                    let position = touch.location.to_logical::<f64>(scale_factor);
+                    aux.dispatch_input(ui::InputEvent::Pointer(ui::PointerInput {
+                        id: touch.id,
+                        kind,
+                        phase,
+                        position: gfx::Point::new(position.x as _, position.y as _),
+                        pressure: touch.force.map(|x| x.normalized() as f32),
+                        tilt,
+                        primary,
+                    }));
+                }
+                WindowEvent::ReceivedCharacter(c) => {
+                    if let Some(mut key) = pending_key.take() {
+                        if !c.is_control() {
+                            key.text.push(c);
+                        }
+                        aux.dispatch_input(ui::InputEvent::KeyPress(key));
+                    }
+
+                    if !c.is_control() {
+                        aux.dispatch_input(ui::InputEvent::Text(c));
+                    }
+                }
+                WindowEvent::Focused(false) => {
+                    pressed_keys.clear();
+                    // the platform cancels any composition when the window loses focus.
+                    aux.dispatch_input(ui::InputEvent::Preedit(Default::default()));
+                }
+                _ => {}
+            },
+            _ => return,
+        }
+
+        let update_start = std::time::Instant::now();
+        aux.process_lifecycle();
+        ui::system::run_systems(&mut root, &mut aux).unwrap();
+        if let Some(overlay) = &mut debug_overlay {
+            overlay.record_update(update_start.elapsed());
+        }
+
+        // winit 0.22 reports no input method events; committed text is delivered through `ReceivedCharacter` (as
+        // `TextEvent`s) and compositions aren't reported at all, so only the candidate window placement needs to be
+        // forwarded.
+        if aux.ime_cursor_area != ime_cursor_area {
+            ime_cursor_area = aux.ime_cursor_area;
+            if let Some(area) = ime_cursor_area {
+                window.set_ime_position(glutin::dpi::LogicalPosition::new(
+                    area.origin.x as f64,
+                    area.max_y() as f64,
+                ));
+            }
+        }
+
+        let cursor = aux.data.cursor;
+        if ui::update_cursor_icon(&root, &mut aux, cursor) {
+            window.set_cursor_icon(cursor_icon(aux.cursor_icon));
+        }
+    });
+}
+
+fn cursor_icon(icon: ui::CursorIcon) -> glutin::window::CursorIcon {
+    use glutin::window::CursorIcon;
+    match icon {
+        ui::CursorIcon::Default => CursorIcon::Default,
+        ui::CursorIcon::Pointer => CursorIcon::Hand,
+        ui::CursorIcon::Text => CursorIcon::Text,
+        ui::CursorIcon::Crosshair => CursorIcon::Crosshair,
+        ui::CursorIcon::Move => CursorIcon::Move,
+        ui::CursorIcon::Grab => CursorIcon::Grab,
+        ui::CursorIcon::Grabbing => CursorIcon::Grabbing,
+        ui::CursorIcon::NotAllowed => CursorIcon::NotAllowed,
+        ui::CursorIcon::Wait => CursorIcon::Wait,
+        ui::CursorIcon::ResizeHorizontal => CursorIcon::EwResize,
+        ui::CursorIcon::ResizeVertical => CursorIcon::NsResize,
+        ui::CursorIcon::ResizeNwse => CursorIcon::NwseResize,
+        ui::CursorIcon::ResizeNesw => CursorIcon::NeswResize,
+    }
+}
+
+pub type AppAux<T> = ui::Aux<AppData<T>>;
@@ -0,0 +1,470 @@
+//! Rendering of display lists recorded on the UI thread.
+//!
+//! Widgets draw into a [`RecordingDisplay`](RecordingDisplay), which keeps the command groups they push and,
+//! at the end of each frame, snapshots them into an immutable [`Frame`](Frame).
+//! The frame is then handed to a [`Renderer`](Renderer), which owns the Skia display and the OpenGL context,
+//! either directly on the UI thread ([`RenderMode::Synchronous`](RenderMode::Synchronous)) or through a
+//! dedicated render thread ([`RenderMode::Threaded`](RenderMode::Threaded)).
+
+use {
+    glutin::{NotCurrent, PossiblyCurrent, RawContext},
+    reclutch::{
+        display::{self as gfx, GraphicsDisplay},
+        error::{DisplayError, ResourceError},
+    },
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{mpsc, Arc, Condvar, Mutex},
+        thread,
+    },
+};
+
+/// How frames are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderMode {
+    /// Frames are rendered and presented on the UI thread, blocking it until presentation is done.
+    Synchronous,
+    /// Frames are rendered on a separate thread.
+    ///
+    /// If the render thread falls behind, the UI thread doesn't wait for it; intermediate frames are skipped instead.
+    Threaded,
+}
+
+impl Default for RenderMode {
+    #[inline]
+    fn default() -> Self {
+        RenderMode::Threaded
+    }
+}
+
+#[derive(Clone)]
+struct FrameGroup {
+    id: u64,
+    z_order: gfx::ZOrder,
+    protected: Option<bool>,
+    always_alive: Option<bool>,
+    commands: Arc<[gfx::DisplayCommand]>,
+}
+
+/// Immutable snapshot of every command group to be presented in a frame.
+///
+/// Command groups which haven't changed since the previous frame share their commands with it.
+#[derive(Clone)]
+pub struct Frame {
+    groups: Vec<FrameGroup>,
+    /// Physical size of the window.
+    size: (u32, u32),
+    /// Physical region which changed since the last frame, or `None` if everything changed.
+    cull: Option<gfx::Rect>,
+}
+
+impl Frame {
+    /// Merges a frame which was skipped into `self`, so that the region it changed is still presented.
+    fn merge_skipped(&mut self, skipped: &Frame) {
+        self.cull = match (self.cull, skipped.cull) {
+            (Some(a), Some(b)) => Some(a.union(&b)),
+            _ => None,
+        };
+    }
+}
+
+struct RecordedGroup {
+    group: FrameGroup,
+    maintained: bool,
+}
+
+/// Display which records command groups into frames rather than rendering them.
+pub struct RecordingDisplay {
+    groups: HashMap<u64, RecordedGroup>,
+    next_id: u64,
+    size: (u32, u32),
+    backend: Backend,
+}
+
+impl RecordingDisplay {
+    fn new(size: (u32, u32), backend: Backend) -> Self {
+        RecordingDisplay {
+            groups: HashMap::new(),
+            next_id: 0,
+            size,
+            backend,
+        }
+    }
+
+    /// Finishes a frame, removing command groups which weren't maintained, and returns a snapshot of it.
+    pub fn finish_frame(&mut self, cull: Option<gfx::Rect>) -> Frame {
+        self.groups
+            .retain(|_, x| x.maintained || x.group.always_alive.unwrap_or(false));
+
+        let mut groups: Vec<_> = self
+            .groups
+            .values_mut()
+            .map(|x| {
+                x.maintained = false;
+                x.group.clone()
+            })
+            .collect();
+        groups.sort_by_key(|x| (x.z_order.0, x.id));
+
+        Frame {
+            groups,
+            size: self.size,
+            cull,
+        }
+    }
+
+    /// Presents a frame; in threaded mode, this returns immediately.
+    pub fn submit(&mut self, frame: Frame) {
+        match &mut self.backend {
+            Backend::Synchronous(renderer) => renderer.render(frame),
+            Backend::Threaded(thread) => thread.submit(frame),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        id: u64,
+        commands: &[gfx::DisplayCommand],
+        z_order: gfx::ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.insert(
+            id,
+            RecordedGroup {
+                group: FrameGroup {
+                    id,
+                    z_order,
+                    protected,
+                    always_alive,
+                    commands: commands.into(),
+                },
+                maintained: true,
+            },
+        );
+    }
+}
+
+impl GraphicsDisplay for RecordingDisplay {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+        self.size = size;
+        Ok(())
+    }
+
+    #[inline]
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: gfx::ResourceDescriptor,
+    ) -> Result<gfx::ResourceReference, ResourceError> {
+        match &mut self.backend {
+            Backend::Synchronous(renderer) => renderer.display.new_resource(descriptor),
+            Backend::Threaded(thread) => thread.new_resource(descriptor),
+        }
+    }
+
+    fn remove_resource(&mut self, reference: gfx::ResourceReference) {
+        match &mut self.backend {
+            Backend::Synchronous(renderer) => renderer.display.remove_resource(reference),
+            Backend::Threaded(thread) => thread.send(Message::RemoveResource(reference)),
+        }
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[gfx::DisplayCommand],
+        z_order: gfx::ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) -> Result<gfx::CommandGroupHandle, Box<dyn std::error::Error>> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert(id, commands, z_order, protected, always_alive);
+        Ok(gfx::CommandGroupHandle::new(id))
+    }
+
+    fn get_command_group(&self, handle: gfx::CommandGroupHandle) -> Option<&[gfx::DisplayCommand]> {
+        self.groups.get(&handle.id()).map(|x| &x.group.commands[..])
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: gfx::CommandGroupHandle,
+        commands: &[gfx::DisplayCommand],
+        z_order: gfx::ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.insert(handle.id(), commands, z_order, protected, always_alive);
+    }
+
+    fn maintain_command_group(&mut self, handle: gfx::CommandGroupHandle) {
+        if let Some(group) = self.groups.get_mut(&handle.id()) {
+            group.maintained = true;
+        }
+    }
+
+    fn remove_command_group(
+        &mut self,
+        handle: gfx::CommandGroupHandle,
+    ) -> Option<Vec<gfx::DisplayCommand>> {
+        self.groups
+            .remove(&handle.id())
+            .map(|x| x.group.commands.to_vec())
+    }
+
+    #[inline]
+    fn before_exit(&mut self) {}
+
+    /// Frames are finished through [`finish_frame`](RecordingDisplay::finish_frame) instead.
+    #[inline]
+    fn present(&mut self, _cull: Option<gfx::Rect>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+}
+
+/// Owner of the Skia display and OpenGL context, which renders and presents frames.
+pub struct Renderer {
+    display: gfx::skia::SkiaGraphicsDisplay,
+    context: RawContext<PossiblyCurrent>,
+    groups: HashMap<u64, (gfx::CommandGroupHandle, Arc<[gfx::DisplayCommand]>)>,
+    last_cull: Option<gfx::Rect>,
+}
+
+impl Renderer {
+    /// Makes `context` current on the calling thread and creates a Skia display for its default framebuffer.
+    pub fn new(context: RawContext<NotCurrent>, size: (u32, u32)) -> Result<Self, super::AppError> {
+        let context = unsafe { context.make_current().map_err(|(_, e)| e)? };
+        let display = gfx::skia::SkiaGraphicsDisplay::new_gl_framebuffer(
+            &gfx::skia::SkiaOpenGlFramebuffer {
+                framebuffer_id: 0,
+                size: (size.0 as _, size.1 as _),
+            },
+        )?;
+
+        Ok(Renderer {
+            display,
+            context,
+            groups: HashMap::new(),
+            last_cull: None,
+        })
+    }
+
+    pub fn render(&mut self, frame: Frame) {
+        if self.display.size() != frame.size {
+            self.context
+                .resize(glutin::dpi::PhysicalSize::new(frame.size.0, frame.size.1));
+            self.display
+                .resize(frame.size)
+                .expect("Display error when resizing");
+            // the new framebuffer holds nothing from previous frames.
+            self.last_cull = None;
+        }
+
+        let mut groups = HashMap::with_capacity(frame.groups.len());
+        for group in frame.groups {
+            let handle = match self.groups.remove(&group.id) {
+                Some((handle, commands)) if Arc::ptr_eq(&commands, &group.commands) => {
+                    self.display.maintain_command_group(handle);
+                    handle
+                }
+                Some((handle, _)) => {
+                    self.display.modify_command_group(
+                        handle,
+                        &group.commands,
+                        group.z_order,
+                        group.protected,
+                        group.always_alive,
+                    );
+                    handle
+                }
+                None => self
+                    .display
+                    .push_command_group(
+                        &group.commands,
+                        group.z_order,
+                        group.protected,
+                        group.always_alive,
+                    )
+                    .expect("Display error when pushing command group"),
+            };
+            groups.insert(group.id, (handle, group.commands));
+        }
+
+        for (handle, _) in self.groups.values() {
+            self.display.remove_command_group(*handle);
+        }
+        self.groups = groups;
+
+        // the back buffer holds the frame before the last one, so it must also be repaired where the last frame changed.
+        let cull = match (frame.cull, self.last_cull) {
+            (Some(a), Some(b)) => Some(a.union(&b)),
+            _ => None,
+        };
+        self.last_cull = frame.cull;
+
+        self.display.present(cull).unwrap();
+        self.context.swap_buffers().unwrap();
+    }
+}
+
+enum Backend {
+    Synchronous(Renderer),
+    Threaded(RenderThread),
+}
+
+enum Message {
+    NewResource(
+        gfx::ResourceDescriptor,
+        mpsc::Sender<Result<gfx::ResourceReference, ResourceError>>,
+    ),
+    RemoveResource(gfx::ResourceReference),
+}
+
+#[derive(Default)]
+struct Shared {
+    messages: VecDeque<Message>,
+    frame: Option<Frame>,
+    exit: bool,
+}
+
+// everything which crosses over to the render thread; this fails to compile should any of it stop being `Send`.
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send::<RawContext<NotCurrent>>();
+    // shared between the frames kept by the recording display and those held by the render thread.
+    assert_send_sync::<gfx::DisplayCommand>();
+    assert_send::<Frame>();
+    assert_send::<Message>();
+    assert_send::<super::AppError>();
+};
+
+struct RenderThread {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl RenderThread {
+    fn spawn(context: RawContext<NotCurrent>, size: (u32, u32)) -> Result<Self, super::AppError> {
+        let shared = Arc::new((Mutex::new(Shared::default()), Condvar::new()));
+        let (init_tx, init_rx) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name("otway-render".into())
+            .spawn({
+                let shared = Arc::clone(&shared);
+                move || {
+                    let mut renderer = match Renderer::new(context, size) {
+                        Ok(renderer) => {
+                            let _ = init_tx.send(Ok(()));
+                            renderer
+                        }
+                        Err(err) => {
+                            let _ = init_tx.send(Err(err));
+                            return;
+                        }
+                    };
+
+                    let (lock, cvar) = &*shared;
+                    loop {
+                        let (messages, frame) = {
+                            let mut shared = lock.lock().unwrap();
+                            while !shared.exit
+                                && shared.messages.is_empty()
+                                && shared.frame.is_none()
+                            {
+                                shared = cvar.wait(shared).unwrap();
+                            }
+                            if shared.exit {
+                                return;
+                            }
+                            (std::mem::take(&mut shared.messages), shared.frame.take())
+                        };
+
+                        for message in messages {
+                            match message {
+                                Message::NewResource(descriptor, reply) => {
+                                    let _ = reply.send(renderer.display.new_resource(descriptor));
+                                }
+                                Message::RemoveResource(reference) => {
+                                    renderer.display.remove_resource(reference)
+                                }
+                            }
+                        }
+
+                        if let Some(frame) = frame {
+                            renderer.render(frame);
+                        }
+                    }
+                }
+            })
+            .expect("Failed to spawn render thread");
+
+        init_rx
+            .recv()
+            .expect("Render thread exited during initialization")?;
+
+        Ok(RenderThread {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    fn send(&self, message: Message) {
+        let (lock, cvar) = &*self.shared;
+        lock.lock().unwrap().messages.push_back(message);
+        cvar.notify_one();
+    }
+
+    /// Replaces the pending frame; if the previous frame hasn't been picked up yet, it is skipped.
+    fn submit(&self, mut frame: Frame) {
+        let (lock, cvar) = &*self.shared;
+        let mut shared = lock.lock().unwrap();
+        if let Some(skipped) = shared.frame.take() {
+            frame.merge_skipped(&skipped);
+        }
+        shared.frame = Some(frame);
+        cvar.notify_one();
+    }
+
+    fn new_resource(
+        &self,
+        descriptor: gfx::ResourceDescriptor,
+    ) -> Result<gfx::ResourceReference, ResourceError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(Message::NewResource(descriptor, reply_tx));
+        reply_rx.recv().expect("Render thread exited")
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        {
+            let (lock, cvar) = &*self.shared;
+            lock.lock().unwrap().exit = true;
+            cvar.notify_one();
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Creates the recording display, along with a renderer for `context` according to `mode`.
+pub fn new_display(
+    context: RawContext<NotCurrent>,
+    size: (u32, u32),
+    mode: RenderMode,
+) -> Result<RecordingDisplay, super::AppError> {
+    let backend = match mode {
+        RenderMode::Synchronous => Backend::Synchronous(Renderer::new(context, size)?),
+        RenderMode::Threaded => Backend::Threaded(RenderThread::spawn(context, size)?),
+    };
+    Ok(RecordingDisplay::new(size, backend))
+}
@@ -0,0 +1,255 @@
+//! Runs a UI without a window or a graphics display.
+//!
+//! This is mostly useful for automated testing; input is synthesized through the methods of [`Headless`](Headless)
+//! and the resulting state can be inspected through [`root`](Headless::root) and [`aux`](Headless::aux).
+//!
+//! Timers and task delays run on a [`ManualClock`](ui::timer::ManualClock), so time only passes through [`advance`](Headless::advance).
+
+use {
+    crate::{prelude::*, theme, ui},
+    reclutch::display as gfx,
+    std::time::Duration,
+};
+
+/// Root widget of a headless UI, which simply wraps the top-level widget.
+pub struct Root<T: 'static, W: ui::WidgetChildren<T>> {
+    child: W,
+    common: ui::CommonRef,
+}
+
+impl<T: 'static, W: ui::WidgetChildren<T>> ui::Element for Root<T, W> {
+    type Aux = T;
+
+    #[inline]
+    fn common(&self) -> &ui::CommonRef {
+        &self.common
+    }
+}
+
+impl<T: 'static, W: ui::WidgetChildren<T>> ui::WidgetChildren<T> for Root<T, W> {
+    crate::children![for <T>; child];
+}
+
+/// Headless driver of a UI.
+///
+/// Every input method updates the UI immediately after emitting the event, as `app::run` does.
+pub struct Headless<T: 'static, W: ui::WidgetChildren<T>> {
+    root: Root<T, W>,
+    aux: ui::Aux<T>,
+    cursor: gfx::Point,
+    modifiers: ui::KeyModifiers,
+    clock: ui::timer::ManualClock,
+}
+
+impl<T: 'static, W: ui::WidgetChildren<T>> Headless<T, W> {
+    /// Creates the UI with `new` and lays it out to fill `size`.
+    ///
+    /// The systems of the toolkit are registered if the `kit` feature is enabled.
+    pub fn new(
+        new: impl FnOnce(ui::CommonRef, &mut ui::Aux<T>) -> W,
+        data: T,
+        theme: Box<dyn theme::Theme<T>>,
+        size: gfx::Size,
+    ) -> Self {
+        let central_widget = ui::CommonRef::new(None);
+        let clock = ui::timer::ManualClock::default();
+        let mut aux = ui::Aux::new(data, theme, central_widget.clone());
+        #[cfg(feature = "kit")]
+        crate::kit::register_systems(&mut aux).unwrap();
+        aux.timers.set_clock(clock.clone());
+        let root = Root {
+            child: new(central_widget.clone(), &mut aux),
+            common: central_widget,
+        };
+        root.set_layout_mode(ui::LayoutMode::Fill);
+        root.set_size(size);
+        ui::layout::update_layout(&root);
+
+        let mut headless = Headless {
+            root,
+            aux,
+            cursor: Default::default(),
+            modifiers: Default::default(),
+            clock,
+        };
+        headless.update();
+        headless
+    }
+
+    #[inline]
+    pub fn root(&self) -> &W {
+        &self.root.child
+    }
+
+    #[inline]
+    pub fn root_mut(&mut self) -> &mut W {
+        &mut self.root.child
+    }
+
+    #[inline]
+    pub fn aux(&self) -> &ui::Aux<T> {
+        &self.aux
+    }
+
+    #[inline]
+    pub fn aux_mut(&mut self) -> &mut ui::Aux<T> {
+        &mut self.aux
+    }
+
+    /// Returns the top-level widget along with `Aux`, e.g. to add widgets to the UI.
+    #[inline]
+    pub fn parts_mut(&mut self) -> (&mut W, &mut ui::Aux<T>) {
+        (&mut self.root.child, &mut self.aux)
+    }
+
+    /// Processes elapsed timers, proxied events, woken tasks and lifecycle changes, then runs the systems (which, by default,
+    /// propagate an update through the UI and update invalidated layouts).
+    pub fn update(&mut self) {
+        self.aux.process_proxied_events();
+        self.aux.process_timers();
+        self.aux.run_tasks();
+        self.aux.process_lifecycle();
+        ui::system::run_systems(&mut self.root, &mut self.aux).unwrap();
+        ui::update_cursor_icon(&self.root, &mut self.aux, self.cursor);
+    }
+
+    /// Emits an event from the queue ID of `Aux` (i.e. as global input), then updates.
+    pub fn emit<E: 'static>(&mut self, event: E) {
+        let id = self.aux.id;
+        self.aux.emit(&id, event);
+        self.update();
+    }
+
+    /// Passes raw input through the input filters and emits it, then updates.
+    pub fn input(&mut self, input: ui::InputEvent) {
+        self.aux.dispatch_input(input);
+        self.update();
+    }
+
+    /// Advances the clock by `duration`, then updates.
+    pub fn advance(&mut self, duration: Duration) {
+        self.clock.advance(duration);
+        self.update();
+    }
+
+    /// Returns the clock which drives the timers.
+    #[inline]
+    pub fn clock(&self) -> &ui::timer::ManualClock {
+        &self.clock
+    }
+
+    pub fn resize(&mut self, size: gfx::Size) {
+        self.aux.record_resize(size);
+        self.root.set_size(size);
+        self.update();
+    }
+
+    /// Returns the cursor icon requested by the widgets under the cursor.
+    #[inline]
+    pub fn cursor_icon(&self) -> ui::CursorIcon {
+        self.aux.cursor_icon
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> gfx::Point {
+        self.cursor
+    }
+
+    /// Changes the modifier keys attached to subsequent key events.
+    #[inline]
+    pub fn set_modifiers(&mut self, modifiers: ui::KeyModifiers) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn mouse_move(&mut self, position: gfx::Point) {
+        self.cursor = position;
+        self.input(ui::InputEvent::MouseMove(position));
+    }
+
+    pub fn mouse_press(&mut self, button: ui::MouseButton) {
+        self.input(ui::InputEvent::MousePress(button, self.cursor));
+    }
+
+    pub fn mouse_release(&mut self, button: ui::MouseButton) {
+        self.input(ui::InputEvent::MouseRelease(button, self.cursor));
+    }
+
+    /// Moves the cursor to `position`, then presses and releases the left mouse button.
+    pub fn click(&mut self, position: gfx::Point) {
+        self.mouse_move(position);
+        self.mouse_press(ui::MouseButton::Left);
+        self.mouse_release(ui::MouseButton::Left);
+    }
+
+    pub fn key_press(&mut self, key: ui::KeyInput) {
+        let key = ui::KeyEvent {
+            modifiers: self.modifiers,
+            ..ui::KeyEvent::new(key)
+        };
+        self.input(ui::InputEvent::KeyPress(key));
+    }
+
+    pub fn key_release(&mut self, key: ui::KeyInput) {
+        let key = ui::KeyEvent {
+            modifiers: self.modifiers,
+            ..ui::KeyEvent::new(key)
+        };
+        self.input(ui::InputEvent::KeyRelease(key));
+    }
+
+    /// Emits a `TextEvent` for each character of `text`.
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.input(ui::InputEvent::Text(c));
+        }
+    }
+
+    pub fn scroll(&mut self, lines: gfx::Vector) {
+        self.input(ui::InputEvent::Scroll(ui::ScrollInput {
+            lines,
+            pixels: gfx::Vector::zero(),
+            position: self.cursor,
+        }));
+    }
+
+    /// Replays a recorded input session.
+    ///
+    /// Entries are grouped by the frame they were recorded in; all the entries of a frame are applied before the UI updates
+    /// once, as they were when recorded. The clock is advanced between frames by the time that passed between them when
+    /// recorded, so timers and tasks elapse at the same points of the session.
+    pub fn replay(&mut self, recording: &ui::record::Recording) {
+        let entries = &recording.entries;
+        let mut time = Duration::default();
+        let mut start = 0;
+        while start < entries.len() {
+            let frame = entries[start].frame;
+            let end = entries[start..]
+                .iter()
+                .position(|x| x.frame != frame)
+                .map_or(entries.len(), |x| start + x);
+
+            if entries[start].time > time {
+                self.clock.advance(entries[start].time - time);
+                time = entries[start].time;
+            }
+
+            for entry in &entries[start..end] {
+                match &entry.input {
+                    ui::record::RecordedInput::Input(input) => {
+                        if let ui::InputEvent::MouseMove(position) = input {
+                            self.cursor = *position;
+                        }
+                        self.aux.dispatch_input(input.clone());
+                    }
+                    ui::record::RecordedInput::Resize(size) => {
+                        self.aux.record_resize(*size);
+                        self.root.set_size(*size);
+                    }
+                }
+            }
+            self.update();
+
+            start = end;
+        }
+    }
+}
@@ -18,14 +18,7 @@ impl<T: 'static> Button<T> {
     pub fn new(parent: ui::CommonRef, aux: &mut ui::Aux<T>) -> Self {
         let common = ui::CommonRef::new(parent);
 
-        let focus_listener = kit::focus_handler(
-            aux,
-            kit::focus_forwarder(),
-            kit::FocusConfig {
-                mouse_trigger: Default::default(),
-                interaction_handler: common.with(|x| x.id()),
-            },
-        );
+        let focus_listener = kit::focus_handler(aux, &common, kit::focus_forwarder(), None);
 
         Button {
             label: kit::Label::new(common.clone(), aux),
@@ -90,8 +83,12 @@ impl<T: 'static> ui::Element for Button<T> {
     }
 
     #[inline]
-    fn update(&mut self, aux: &mut ui::Aux<T>) {
+    fn update_components(&mut self, aux: &mut ui::Aux<T>) {
         ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    #[inline]
+    fn update(&mut self, aux: &mut ui::Aux<T>) {
         ui::dispatch_list::<kit::ReadWrite<Self>, _>((self, aux), |(x, _)| &mut x.listeners);
 
         ui::propagate_repaint(self);
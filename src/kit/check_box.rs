@@ -18,14 +18,7 @@ impl<T: 'static> CheckMarkBox<T> {
     pub fn new(parent: ui::CommonRef, aux: &mut ui::Aux<T>) -> Self {
         let common = ui::CommonRef::new(parent);
 
-        let focus_listener = kit::focus_handler(
-            aux,
-            kit::focus_forwarder(),
-            kit::FocusConfig {
-                interaction_handler: common.with(|x| x.id()),
-                mouse_trigger: Default::default(),
-            },
-        );
+        let focus_listener = kit::focus_handler(aux, &common, kit::focus_forwarder(), None);
 
         let mut cm = CheckMarkBox {
             checked: false,
@@ -78,8 +71,12 @@ impl<T: 'static> ui::Element for CheckMarkBox<T> {
     }
 
     #[inline]
-    fn update(&mut self, aux: &mut ui::Aux<T>) {
+    fn update_components(&mut self, aux: &mut ui::Aux<T>) {
         ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    #[inline]
+    fn update(&mut self, aux: &mut ui::Aux<T>) {
         ui::dispatch_list::<(ui::Write<Self>, ui::Write<ui::Aux<T>>), _>((self, aux), |(x, _)| {
             &mut x.listeners
         });
@@ -159,8 +156,12 @@ impl<T: 'static> ui::Element for CheckBox<T> {
     }
 
     #[inline]
-    fn update(&mut self, aux: &mut ui::Aux<T>) {
+    fn update_components(&mut self, aux: &mut ui::Aux<T>) {
         ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    #[inline]
+    fn update(&mut self, aux: &mut ui::Aux<T>) {
         ui::dispatch_list::<kit::ReadWrite<Self>, _>((self, aux), |(x, _)| &mut x.listeners);
 
         ui::propagate_repaint(self);
@@ -16,6 +16,7 @@ pub struct ComboListItem<T: 'static> {
 impl<T: 'static> ComboListItem<T> {
     pub fn new(parent: ui::CommonRef, aux: &mut ui::Aux<T>) -> Self {
         let common = ui::CommonRef::new(parent);
+        common.with(|x| x.set_cursor_icon(ui::CursorIcon::Pointer));
 
         ComboListItem {
             label: kit::Label::new(common.clone(), aux),
@@ -70,8 +71,12 @@ impl<T: 'static> ui::Element for ComboListItem<T> {
     }
 
     #[inline]
-    fn update(&mut self, aux: &mut ui::Aux<Self::Aux>) {
+    fn update_components(&mut self, aux: &mut ui::Aux<Self::Aux>) {
         ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    #[inline]
+    fn update(&mut self, aux: &mut ui::Aux<Self::Aux>) {
         ui::dispatch_list::<kit::ReadWrite<Self>, _>((self, aux), |(x, _)| &mut x.listeners);
     }
 
@@ -93,6 +98,8 @@ impl<T: 'static> ui::WidgetChildren<T> for ComboListItem<T> {
 pub struct ComboList<T: 'static> {
     combos: Vec<String>,
     items: Vec<ComboListItem<T>>,
+    selected: Option<usize>,
+    scroll: f32,
 
     painter: theme::Painter<Self>,
     common: ui::CommonRef,
@@ -104,22 +111,32 @@ impl<T: 'static> ComboList<T> {
     pub fn new(parent: ui::CommonRef, aux: &mut ui::Aux<T>) -> Self {
         let common = ui::CommonRef::new(parent);
 
-        let focus_listener = kit::focus_handler(
-            aux,
-            kit::focus_forwarder(),
-            kit::FocusConfig {
-                interaction_handler: common.with(|x| x.id()),
-                mouse_trigger: Default::default(),
-            },
-        );
+        let focus_listener = kit::focus_handler(aux, &common, kit::focus_forwarder(), None);
+
+        let scroll_listener = kit::scroll_handler(aux, &common, |obj: &mut Self, aux, input| {
+            let lines = kit::scroll_lines(
+                &mut obj.scroll,
+                &input,
+                aux.theme.standards().label_size,
+            );
+            if lines != 0 && !obj.items.is_empty() {
+                let last = obj.items.len() as i32 - 1;
+                let selected = obj.selected.map(|x| x as i32).unwrap_or(-1);
+                obj.set_selected(Some((selected + lines).max(0).min(last) as usize));
+            }
+
+            kit::scroll_forwarder()(obj, aux, input);
+        });
 
         ComboList {
             combos: Vec::new(),
             items: Vec::new(),
+            selected: None,
+            scroll: 0.,
 
             painter: theme::get_painter(aux.theme.as_ref(), theme::painters::COMBO_LIST),
             common,
-            listeners: ui::ListenerList::new(vec![focus_listener]),
+            listeners: ui::ListenerList::new(vec![focus_listener, scroll_listener]),
             components: ui::ComponentList::new().and_push(kit::InteractionState::new(
                 aux,
                 kit::interaction_forwarder(None),
@@ -139,6 +156,19 @@ impl<T: 'static> ComboList<T> {
         &self.combos
     }
 
+    /// Changes the highlighted item.
+    pub fn set_selected(&mut self, selected: Option<usize>) {
+        self.selected = selected;
+        for (i, item) in self.items.iter_mut().enumerate() {
+            item.set_selected(Some(i) == selected);
+        }
+    }
+
+    #[inline]
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
     fn update_items(&mut self, aux: &mut ui::Aux<T>) {
         let mut stack = ui::layout::VStack::new().into_node(None);
 
@@ -160,6 +190,8 @@ impl<T: 'static> ComboList<T> {
 
         self.set_layout(stack);
         ui::layout::update_layout(self);
+
+        self.set_selected(self.selected.filter(|&x| x < self.items.len()));
     }
 }
 
@@ -171,8 +203,12 @@ impl<T: 'static> ui::Element for ComboList<T> {
         &self.common
     }
 
-    fn update(&mut self, aux: &mut ui::Aux<Self::Aux>) {
+    #[inline]
+    fn update_components(&mut self, aux: &mut ui::Aux<Self::Aux>) {
         ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    fn update(&mut self, aux: &mut ui::Aux<Self::Aux>) {
         ui::dispatch_list::<kit::ReadWrite<Self>, _>((self, aux), |(x, _)| &mut x.listeners);
 
         ui::propagate_repaint(self);
@@ -203,6 +239,30 @@ impl<T: 'static> ui::WidgetChildren<T> for ComboList<T> {
             .map(|x| x as &mut dyn WidgetChildren<T>)
             .collect()
     }
+
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for item in &self.items {
+            f(item);
+        }
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for item in &mut self.items {
+            f(item);
+        }
+    }
+
+    fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for item in self.items.iter().rev() {
+            f(item);
+        }
+    }
+
+    fn for_each_child_mut_rev(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for item in self.items.iter_mut().rev() {
+            f(item);
+        }
+    }
 }
 
 pub struct ComboBox<T: 'static> {
@@ -210,6 +270,7 @@ pub struct ComboBox<T: 'static> {
     label: kit::Label<T>,
     list: Option<ComboList<T>>,
     selected: Option<usize>,
+    scroll: f32,
 
     painter: theme::Painter<Self>,
     common: ui::CommonRef,
@@ -220,25 +281,35 @@ pub struct ComboBox<T: 'static> {
 impl<T: 'static> ComboBox<T> {
     pub fn new(parent: ui::CommonRef, aux: &mut ui::Aux<T>) -> Self {
         let common = ui::CommonRef::new(parent);
+        common.with(|x| x.set_cursor_icon(ui::CursorIcon::Pointer));
 
-        let focus_listener = kit::focus_handler(
-            aux,
-            kit::focus_forwarder(),
-            kit::FocusConfig {
-                interaction_handler: common.with(|x| x.id()),
-                mouse_trigger: Default::default(),
-            },
-        );
+        let focus_listener = kit::focus_handler(aux, &common, kit::focus_forwarder(), None);
+
+        let scroll_listener = kit::scroll_handler(aux, &common, |obj: &mut Self, aux, input| {
+            let lines = kit::scroll_lines(
+                &mut obj.scroll,
+                &input,
+                aux.theme.standards().label_size,
+            );
+            if lines != 0 && !obj.combos.is_empty() {
+                let last = obj.combos.len() as i32 - 1;
+                let selected = obj.selected.map(|x| x as i32).unwrap_or(-1);
+                obj.set_selected((selected + lines).max(0).min(last) as usize);
+            }
+
+            kit::scroll_forwarder()(obj, aux, input);
+        });
 
         ComboBox {
             combos: Vec::new(),
             label: kit::Label::new(common.clone(), aux),
             list: None,
             selected: None,
+            scroll: 0.,
 
             painter: theme::get_painter(aux.theme.as_ref(), theme::painters::COMBO_BOX),
             common,
-            listeners: ui::ListenerList::new(vec![focus_listener]),
+            listeners: ui::ListenerList::new(vec![focus_listener, scroll_listener]),
             components: ui::ComponentList::new().and_push(kit::InteractionState::new(
                 aux,
                 |obj: &mut Self, aux, ev| {
@@ -343,8 +414,12 @@ impl<T: 'static> ui::Element for ComboBox<T> {
     }
 
     #[inline]
-    fn update(&mut self, aux: &mut ui::Aux<Self::Aux>) {
+    fn update_components(&mut self, aux: &mut ui::Aux<Self::Aux>) {
         ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
+    #[inline]
+    fn update(&mut self, aux: &mut ui::Aux<Self::Aux>) {
         ui::dispatch_list::<kit::ReadWrite<Self>, _>((self, aux), |(x, _)| &mut x.listeners);
 
         ui::propagate_repaint(self);
@@ -377,4 +452,32 @@ impl<T: 'static> ui::WidgetChildren<T> for ComboBox<T> {
             vec![&mut self.label]
         }
     }
+
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn ui::WidgetChildren<T>)) {
+        f(&self.label);
+        if let Some(list) = &self.list {
+            f(list);
+        }
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn ui::WidgetChildren<T>)) {
+        f(&mut self.label);
+        if let Some(list) = &mut self.list {
+            f(list);
+        }
+    }
+
+    fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn ui::WidgetChildren<T>)) {
+        if let Some(list) = &self.list {
+            f(list);
+        }
+        f(&self.label);
+    }
+
+    fn for_each_child_mut_rev(&mut self, f: &mut dyn FnMut(&mut dyn ui::WidgetChildren<T>)) {
+        if let Some(list) = &mut self.list {
+            f(list);
+        }
+        f(&mut self.label);
+    }
 }
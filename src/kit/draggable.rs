@@ -0,0 +1,108 @@
+use {
+    crate::{kit, prelude::*, ui},
+    reclutch::display as gfx,
+};
+
+/// The widget was moved by a given offset as it was dragged.
+#[repr(transparent)]
+pub struct DragEvent(pub gfx::Vector);
+
+/// Widget component which lets the user move any widget by dragging it with the left mouse button.
+///
+/// It is added to a widget at runtime through [`add_component`](crate::ui::ElementMixin::add_component), which registers
+/// the widget as a pointer target.
+/// A drag only begins if the press lands within the widget bounds and hasn't been consumed by another handler.
+/// Widget components update before the widget itself, so the press which begins a drag is consumed before the widget's
+/// own handlers (e.g. the [`InteractionState`](kit::InteractionState) of a button) see it.
+/// The widget emits a [`DragEvent`](DragEvent) whenever it is moved.
+pub struct Draggable<T: 'static> {
+    bounds: gfx::Rect,
+    accepts_input: bool,
+    cursor: Option<gfx::Point>,
+    offset: gfx::Vector,
+
+    listener: ui::Listener<(ui::Write<Self>, ui::Write<ui::Aux<T>>)>,
+}
+
+impl<T: 'static> Draggable<T> {
+    pub fn new(aux: &mut ui::Aux<T>) -> Self {
+        Draggable {
+            bounds: Default::default(),
+            accepts_input: false,
+            cursor: None,
+            offset: gfx::Vector::zero(),
+
+            listener: aux.listen(),
+        }
+    }
+
+    /// Registers the widget as a pointer target, so that mouse input is emitted from its ID.
+    ///
+    /// The press which begins a drag captures the mouse, so the drag follows it beyond the widget bounds.
+    fn register(&mut self, widget: &dyn WidgetChildren<T>, aux: &mut ui::Aux<T>) {
+        let id = widget.common().read(|x| x.id());
+        aux.spatial_index.insert(widget.common());
+
+        let listener = std::mem::replace(&mut self.listener, aux.listen());
+        self.listener = listener
+            .and_on(id, |(state, _), ev: &ui::MousePressEvent| {
+                if !state.accepts_input {
+                    return;
+                }
+
+                let bounds = state.bounds;
+                if let Some(&(_, pos)) =
+                    ev.0.with(|&(btn, pos)| btn == ui::MouseButton::Left && bounds.contains(pos))
+                {
+                    state.cursor = Some(pos);
+                }
+            })
+            .and_on(id, |(state, _), ev: &ui::MouseMoveEvent| {
+                if let Some(cursor) = state.cursor {
+                    let pos = *ev.0.get();
+                    state.offset += pos - cursor;
+                    state.cursor = Some(pos);
+                }
+            })
+            .and_on(id, |(state, _), ev: &ui::MouseReleaseEvent| {
+                if ev.0.get().0 == ui::MouseButton::Left {
+                    state.cursor = None;
+                }
+            });
+    }
+
+    /// Returns `true` if the widget is being dragged.
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.cursor.is_some()
+    }
+}
+
+impl<T: 'static> ui::WidgetComponent for Draggable<T> {
+    type Aux = T;
+
+    fn update(&mut self, widget: &mut dyn WidgetChildren<T>, aux: &mut ui::Aux<T>) {
+        self.bounds = widget.bounds();
+        self.accepts_input = !kit::invisible_to_input(widget.visible());
+        ui::dispatch((self, aux), |(x, _): (&mut Self, _)| &mut x.listener);
+
+        let offset = std::mem::replace(&mut self.offset, gfx::Vector::zero());
+        if offset != gfx::Vector::zero() {
+            widget.set_position(widget.position() + offset);
+            widget.emit(aux, DragEvent(offset));
+        }
+    }
+
+    #[inline]
+    fn on_attach(&mut self, widget: &mut dyn WidgetChildren<T>, aux: &mut ui::Aux<T>) {
+        self.register(widget, aux);
+    }
+
+    fn on_detach(&mut self, widget: &mut dyn WidgetChildren<T>, aux: &mut ui::Aux<T>) {
+        let id = widget.common().read(|x| x.id());
+        aux.spatial_index.remove(id);
+        self.listener.remove_id(id);
+        self.cursor = None;
+        self.offset = gfx::Vector::zero();
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{kit, prelude::*, ui};
+
+/// Widget component which marks a widget as able to receive focus through the mouse.
+///
+/// Marked widgets are found by [`FocusSystem`](FocusSystem). A widget is only focused once its
+/// [`InteractionState`](kit::InteractionState) has been pressed or released (as per `mouse_trigger`), so input which
+/// was masked out or consumed by another widget (e.g. one on top of it) doesn't move focus.
+pub struct Focusable<T: 'static> {
+    pub mouse_trigger: kit::FocusMouseTrigger,
+    triggered: bool,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: 'static> Focusable<T> {
+    pub fn new(mouse_trigger: kit::FocusMouseTrigger) -> Self {
+        Focusable {
+            mouse_trigger,
+            triggered: false,
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<T: 'static> ui::WidgetComponent for Focusable<T> {
+    type Aux = T;
+
+    #[inline]
+    fn update(&mut self, _widget: &mut dyn WidgetChildren<T>, _aux: &mut ui::Aux<T>) {}
+}
+
+/// Records that the widget of `common` was pressed or released, so that it is focused if its trigger matches.
+pub(crate) fn trigger<T: 'static>(common: &ui::CommonRef, trigger: kit::FocusMouseTrigger) {
+    common.with(|x| {
+        if let Ok(focusable) = x.component_mut::<Focusable<T>>() {
+            if focusable.mouse_trigger == trigger {
+                focusable.triggered = true;
+            }
+        }
+    });
+}
+
+/// System which gives focus to the [`Focusable`](Focusable) widget which was pressed or released (as per the trigger
+/// of the widget).
+///
+/// Registered as `"focus"` by [`register_systems`](kit::register_systems), after `"components"` (which dispatches the
+/// interaction states) and before `"update"`.
+pub struct FocusSystem<T: 'static> {
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: 'static> FocusSystem<T> {
+    #[inline]
+    pub fn new() -> Self {
+        FocusSystem {
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<T: 'static> Default for FocusSystem<T> {
+    #[inline]
+    fn default() -> Self {
+        FocusSystem::new()
+    }
+}
+
+impl<T: 'static> ui::system::System<T> for FocusSystem<T> {
+    fn run(&mut self, root: &mut dyn WidgetChildren<T>, aux: &mut ui::Aux<T>) {
+        let mut target = None;
+        ui::system::query::<T, Focusable<T>>(root, &mut |widget| {
+            let triggered = widget.common().with(|x| {
+                x.component_mut::<Focusable<T>>()
+                    .map(|x| std::mem::replace(&mut x.triggered, false))
+                    .unwrap_or(false)
+            });
+            if triggered {
+                target = Some(widget.common().clone());
+            }
+        });
+
+        if let Some(target) = target {
+            aux.grab_focus(target);
+        }
+    }
+}
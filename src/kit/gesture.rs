@@ -0,0 +1,176 @@
+use {
+    crate::{kit, prelude::*, ui},
+    reclutch::display as gfx,
+};
+
+/// The touch points within the widget moved together by a given offset.
+#[repr(transparent)]
+pub struct PanEvent(pub gfx::Vector);
+/// The touch points within the widget moved apart or closer together.
+pub struct PinchEvent {
+    /// Ratio of the new distance between the touch points to the previous distance.
+    pub scale: f32,
+    /// Center point of the touch points.
+    pub center: gfx::Point,
+}
+/// All the touch points of a gesture were lifted.
+pub struct GestureEndEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GestureEvent {
+    Pan(gfx::Vector),
+    Pinch { scale: f32, center: gfx::Point },
+    End,
+}
+
+/// Component which recognizes multi-touch pan and pinch gestures.
+///
+/// A touch is only tracked if it begins within the widget bounds. Panning is reported for any number of touches,
+/// using the center of the touches, whereas pinching requires at least two touches.
+pub struct GestureRecognizer<
+    T: 'static,
+    W: ui::WidgetChildren<T>,
+    F: FnMut(&mut W, &mut ui::Aux<T>, GestureEvent) + 'static,
+> {
+    touches: Vec<(u64, gfx::Point)>,
+
+    listener: ui::Listener<(ui::Write<W>, ui::Write<Self>, ui::Write<ui::Aux<T>>)>,
+    callback: F,
+
+    phantom: std::marker::PhantomData<(T, W)>,
+}
+
+impl<
+        T: 'static,
+        W: ui::WidgetChildren<T>,
+        F: FnMut(&mut W, &mut ui::Aux<T>, GestureEvent) + 'static,
+    > GestureRecognizer<T, W, F>
+{
+    pub fn new(aux: &mut ui::Aux<T>, callback: F) -> Self {
+        GestureRecognizer {
+            touches: Vec::new(),
+
+            listener: aux.listen(),
+            callback,
+
+            phantom: Default::default(),
+        }
+    }
+
+    /// Registers the widget as a pointer target, so that touches are emitted from its ID.
+    ///
+    /// Touches which begin within the widget are captured by it, so they're still tracked once they leave its bounds.
+    fn register(&mut self, obj: &W, aux: &mut ui::Aux<T>) {
+        let id = obj.common().read(|x| x.id());
+        aux.spatial_index.insert(obj.common());
+
+        let listener = std::mem::replace(&mut self.listener, aux.listen());
+        self.listener = listener.and_on(id, |(obj, state, aux), ev: &ui::PointerEvent| {
+            let input = *ev.0.get();
+            if input.kind == ui::PointerKind::Mouse || kit::invisible_to_input(obj.visible()) {
+                return;
+            }
+
+            match input.phase {
+                ui::PointerPhase::Began => {
+                    if obj.bounds().contains(input.position) {
+                        state.touches.push((input.id, input.position));
+                    }
+                }
+                ui::PointerPhase::Moved => state.track(obj, aux, input.id, input.position),
+                ui::PointerPhase::Ended | ui::PointerPhase::Cancelled => {
+                    let len = state.touches.len();
+                    state.touches.retain(|&(id, _)| id != input.id);
+                    if len != state.touches.len() && state.touches.is_empty() {
+                        (state.callback)(obj, aux, GestureEvent::End);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Returns the number of touches currently being tracked.
+    #[inline]
+    pub fn touch_count(&self) -> usize {
+        self.touches.len()
+    }
+
+    fn track(&mut self, obj: &mut W, aux: &mut ui::Aux<T>, id: u64, position: gfx::Point) {
+        let idx = if let Some(idx) = self.touches.iter().position(|&(x, _)| x == id) {
+            idx
+        } else {
+            return;
+        };
+
+        let (old_center, old_spread) = self.center_and_spread();
+        self.touches[idx].1 = position;
+        let (center, spread) = self.center_and_spread();
+
+        let delta = center - old_center;
+        if delta != gfx::Vector::zero() {
+            (self.callback)(obj, aux, GestureEvent::Pan(delta));
+        }
+
+        if self.touches.len() > 1 && old_spread > 0. && spread != old_spread {
+            (self.callback)(
+                obj,
+                aux,
+                GestureEvent::Pinch {
+                    scale: spread / old_spread,
+                    center,
+                },
+            );
+        }
+    }
+
+    /// Returns the center of the touches and the average distance of the touches from that center.
+    fn center_and_spread(&self) -> (gfx::Point, f32) {
+        let n = self.touches.len() as f32;
+        let center = self
+            .touches
+            .iter()
+            .fold(gfx::Vector::zero(), |acc, &(_, p)| acc + p.to_vector())
+            / n;
+        let spread = self
+            .touches
+            .iter()
+            .map(|&(_, p)| (p - center.to_point()).length())
+            .sum::<f32>()
+            / n;
+        (center.to_point(), spread)
+    }
+}
+
+impl<
+        T: 'static,
+        W: ui::WidgetChildren<T>,
+        F: FnMut(&mut W, &mut ui::Aux<T>, GestureEvent) + 'static,
+    > ui::Component for GestureRecognizer<T, W, F>
+{
+    type Type = T;
+    type Object = W;
+
+    fn update(&mut self, obj: &mut Self::Object, aux: &mut ui::Aux<Self::Type>) {
+        ui::dispatch((obj, self, aux), |x: (_, &mut Self, _)| &mut x.1.listener);
+    }
+
+    #[inline]
+    fn on_attach(&mut self, obj: &mut Self::Object, aux: &mut ui::Aux<Self::Type>) {
+        self.register(obj, aux);
+    }
+
+    fn on_detach(&mut self, obj: &mut Self::Object, aux: &mut ui::Aux<Self::Type>) {
+        let id = obj.common().read(|x| x.id());
+        aux.spatial_index.remove(id);
+        self.listener.remove_id(id);
+    }
+}
+
+pub fn gesture_forwarder<E: ui::Element<Aux = T>, T: 'static>(
+) -> impl Fn(&mut E, &mut ui::Aux<T>, GestureEvent) + Copy {
+    move |obj, aux, event| match event {
+        GestureEvent::Pan(delta) => obj.emit(aux, PanEvent(delta)),
+        GestureEvent::Pinch { scale, center } => obj.emit(aux, PinchEvent { scale, center }),
+        GestureEvent::End => obj.emit(aux, GestureEndEvent),
+    }
+}
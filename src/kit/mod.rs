@@ -6,10 +6,27 @@ use {
 pub mod button;
 pub mod check_box;
 pub mod combo_box;
+pub mod draggable;
+pub mod focus;
+pub mod gesture;
 pub mod label;
 pub mod text_box;
 
-pub use {button::*, check_box::*, combo_box::*, label::*, text_box::*};
+pub use {
+    button::*, check_box::*, combo_box::*, draggable::*, focus::*, gesture::*, label::*,
+    text_box::*,
+};
+
+/// Registers the systems of the toolkit in `aux`; currently only the [`FocusSystem`](FocusSystem), as `"focus"`.
+pub fn register_systems<T: 'static>(aux: &mut ui::Aux<T>) -> Result<(), ui::system::SystemError> {
+    aux.systems.add(
+        "focus",
+        FocusSystem::new(),
+        ui::system::SystemOrder::new()
+            .after("components")
+            .before("update"),
+    )
+}
 
 /// The widget was pressed.
 #[repr(transparent)]
@@ -28,11 +45,17 @@ pub struct FocusGainedEvent;
 pub struct FocusLostEvent;
 
 #[repr(transparent)]
-pub struct KeyPressEvent(pub ui::KeyInput);
+pub struct KeyPressEvent(pub ui::KeyEvent);
 #[repr(transparent)]
-pub struct KeyReleaseEvent(pub ui::KeyInput);
+pub struct KeyReleaseEvent(pub ui::KeyEvent);
 #[repr(transparent)]
 pub struct TextEvent(pub char);
+#[repr(transparent)]
+pub struct PreeditEvent(pub ui::Preedit);
+#[repr(transparent)]
+pub struct ScrollEvent(pub ui::ScrollInput);
+#[repr(transparent)]
+pub struct CommitEvent(pub String);
 
 /// Standard set of listener read/writes: `&mut Widget` and `&mut Aux`.
 pub type ReadWrite<E> = (ui::Write<E>, ui::Write<ui::Aux<<E as ui::Element>::Aux>>);
@@ -77,80 +100,136 @@ impl<
             pressed: false,
             hovered: false,
 
-            listener: aux
-                .listen::<(ui::Write<W>, ui::Write<Self>, ui::Write<ui::Aux<T>>)>()
-                .and_on(aux.id, |(obj, state, aux), ev: &ui::MousePressEvent| {
-                    if !state.mask.press {
-                        return;
-                    }
-                    let v = obj.visible();
-                    if !state.ignore_vis && invisible_to_input(v) {
-                        return;
-                    }
+            listener: aux.listen(),
+            callback,
+            mask: mask.into().unwrap_or_default(),
+            ignore_vis: ignore_visibility.into().unwrap_or(false),
 
-                    let bounds = obj.bounds();
-                    if let Some(&(_, pos)) = ev
-                        .0
-                        .with(|&(btn, pos)| btn == ui::MouseButton::Left && bounds.contains(pos))
-                    {
-                        state.pressed = true;
-                        (state.callback)(obj, aux, InteractionEvent::Press(pos));
-                    }
-                })
-                .and_on(
-                    aux.id,
-                    move |(obj, state, aux), ev: &ui::MouseReleaseEvent| {
-                        if !state.mask.release {
-                            return;
-                        }
-                        let v = obj.visible();
-                        if !state.ignore_vis && invisible_to_input(v) {
-                            return;
-                        }
+            phantom: Default::default(),
+        }
+    }
+
+    /// Registers the widget as a pointer target, so that pointer input is also emitted from its ID.
+    fn register(&mut self, obj: &W, aux: &mut ui::Aux<T>) {
+        let id = obj.common().read(|x| x.id());
+        aux.spatial_index.insert(obj.common());
 
-                        // FIXME: release applies when pressed, not when mouse is in bounds
+        let listener = std::mem::replace(&mut self.listener, aux.listen());
+        self.listener = listener
+            .and_on(id, |(obj, state, aux), ev: &ui::MousePressEvent| {
+                if !state.mask.press || !state.accepts_input(obj) {
+                    return;
+                }
+
+                let bounds = obj.bounds();
+                if let Some(&(_, pos)) =
+                    ev.0.with(|&(btn, pos)| btn == ui::MouseButton::Left && bounds.contains(pos))
+                {
+                    state.press(obj, aux, pos);
+                }
+            })
+            .and_on(id, move |(obj, state, aux), ev: &ui::MouseReleaseEvent| {
+                if !state.mask.release || !state.accepts_input(obj) {
+                    return;
+                }
+
+                // FIXME: release applies when pressed, not when mouse is in bounds
 
-                        let bounds = obj.bounds();
-                        if let Some(&(_, pos)) = ev.0.with(|&(btn, pos)| {
-                            btn == ui::MouseButton::Left && bounds.contains(pos)
-                        }) {
-                            state.pressed = false;
-                            (state.callback)(obj, aux, InteractionEvent::Release(pos));
+                let bounds = obj.bounds();
+                if let Some(&(_, pos)) =
+                    ev.0.with(|&(btn, pos)| btn == ui::MouseButton::Left && bounds.contains(pos))
+                {
+                    state.release(obj, aux, pos);
+                }
+            })
+            .and_on(id, move |(obj, state, aux), ev: &ui::MouseMoveEvent| {
+                if !state.mask.hover || (!state.ignore_vis && invisible_to_input(obj.visible())) {
+                    return;
+                }
+
+                let bounds = obj.bounds();
+                if let Some(&pos) = ev.0.with(|&pos| bounds.contains(pos)) {
+                    state.hover(obj, aux, pos, true);
+                } else {
+                    state.hover(obj, aux, *ev.0.get(), false);
+                }
+            })
+            .and_on(id, move |(obj, state, aux), ev: &ui::PointerEvent| {
+                // a single touch or stylus contact behaves like the left mouse button.
+                let input = *ev.0.get();
+                if input.kind == ui::PointerKind::Mouse
+                    || !input.primary
+                    || !state.accepts_input(obj)
+                {
+                    return;
+                }
+
+                let bounds = obj.bounds();
+                let pos = input.position;
+                match input.phase {
+                    ui::PointerPhase::Began => {
+                        if state.mask.hover {
+                            state.hover(obj, aux, pos, bounds.contains(pos));
+                        }
+                        if state.mask.press && ev.0.with(|x| bounds.contains(x.position)).is_some()
+                        {
+                            state.press(obj, aux, pos);
                         }
-                    },
-                )
-                .and_on(aux.id, move |(obj, state, aux), ev: &ui::MouseMoveEvent| {
-                    if !state.mask.hover {
-                        return;
                     }
-                    let v = obj.visible();
-                    if !state.ignore_vis && invisible_to_input(v) {
-                        return;
+                    ui::PointerPhase::Moved => {
+                        if state.mask.hover {
+                            state.hover(obj, aux, pos, bounds.contains(pos));
+                        }
                     }
-
-                    let bounds = obj.bounds();
-                    let was_hovered = state.hovered;
-                    let pos = if let Some(&pos) = ev.0.with(|&pos| bounds.contains(pos)) {
-                        state.hovered = true;
-                        pos
-                    } else {
-                        state.hovered = false;
-                        ev.0.get().clone()
-                    };
-
-                    if was_hovered != state.hovered {
-                        if was_hovered {
-                            (state.callback)(obj, aux, InteractionEvent::EndHover(pos));
-                        } else {
-                            (state.callback)(obj, aux, InteractionEvent::BeginHover(pos));
+                    ui::PointerPhase::Ended => {
+                        if state.mask.release
+                            && state.pressed
+                            && ev.0.with(|x| bounds.contains(x.position)).is_some()
+                        {
+                            state.release(obj, aux, pos);
+                        }
+                        state.pressed = false;
+                        if state.mask.hover {
+                            // there is no hovering without contact.
+                            state.hover(obj, aux, pos, false);
                         }
                     }
-                }),
-            callback,
-            mask: mask.into().unwrap_or_default(),
-            ignore_vis: ignore_visibility.into().unwrap_or(false),
+                    ui::PointerPhase::Cancelled => {
+                        state.pressed = false;
+                        if state.mask.hover {
+                            state.hover(obj, aux, pos, false);
+                        }
+                    }
+                }
+            });
+    }
 
-            phantom: Default::default(),
+    fn accepts_input(&self, obj: &W) -> bool {
+        self.ignore_vis || !invisible_to_input(obj.visible())
+    }
+
+    fn press(&mut self, obj: &mut W, aux: &mut ui::Aux<T>, pos: gfx::Point) {
+        self.pressed = true;
+        focus::trigger::<T>(obj.common(), FocusMouseTrigger::Press);
+        (self.callback)(obj, aux, InteractionEvent::Press(pos));
+    }
+
+    fn release(&mut self, obj: &mut W, aux: &mut ui::Aux<T>, pos: gfx::Point) {
+        self.pressed = false;
+        focus::trigger::<T>(obj.common(), FocusMouseTrigger::Release);
+        (self.callback)(obj, aux, InteractionEvent::Release(pos));
+    }
+
+    fn hover(&mut self, obj: &mut W, aux: &mut ui::Aux<T>, pos: gfx::Point, hovered: bool) {
+        let was_hovered = self.hovered;
+        self.hovered = hovered;
+
+        if was_hovered != self.hovered {
+            if was_hovered {
+                (self.callback)(obj, aux, InteractionEvent::EndHover(pos));
+            } else {
+                (self.callback)(obj, aux, InteractionEvent::BeginHover(pos));
+            }
         }
     }
 }
@@ -167,6 +246,17 @@ impl<
     fn update(&mut self, obj: &mut Self::Object, aux: &mut ui::Aux<Self::Type>) {
         ui::dispatch((obj, self, aux), |x: (_, &mut Self, _)| &mut x.1.listener);
     }
+
+    #[inline]
+    fn on_attach(&mut self, obj: &mut Self::Object, aux: &mut ui::Aux<Self::Type>) {
+        self.register(obj, aux);
+    }
+
+    fn on_detach(&mut self, obj: &mut Self::Object, aux: &mut ui::Aux<Self::Type>) {
+        let id = obj.common().read(|x| x.id());
+        aux.spatial_index.remove(id);
+        self.listener.remove_id(id);
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -233,36 +323,21 @@ impl Default for FocusMouseTrigger {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct FocusConfig {
-    pub mouse_trigger: FocusMouseTrigger,
-    /// The event ID of the widget which emits interaction events.
-    /// This should almost always be the same ID as the the widget which is attaching the `focus_handler`.
-    pub interaction_handler: u64,
-}
-
+/// Makes the widget of `common` [`Focusable`](Focusable) and returns a listener which reports its focus changes
+/// to `callback`.
+///
+/// The focus itself is moved by [`FocusSystem`](FocusSystem); `mouse_trigger` defaults to a press.
 pub fn focus_handler<T, W: ui::WidgetChildren<T>>(
     aux: &mut ui::Aux<T>,
+    common: &ui::CommonRef,
     callback: impl Fn(&mut W, &mut ui::Aux<T>, FocusEvent) + Copy + 'static,
-    focus_config: FocusConfig,
+    mouse_trigger: impl Into<Option<FocusMouseTrigger>>,
 ) -> ui::Listener<(ui::Write<W>, ui::Write<ui::Aux<T>>)> {
+    let focusable = Focusable::<T>::new(mouse_trigger.into().unwrap_or_default());
+    // a widget which is already focusable keeps its trigger.
+    let _ = common.with(|x| x.add_component(focusable));
+
     aux.listen::<(ui::Write<W>, ui::Write<ui::Aux<T>>)>()
-        .and_on(
-            focus_config.interaction_handler,
-            move |(obj, aux), _: &PressEvent| {
-                if focus_config.mouse_trigger == FocusMouseTrigger::Press {
-                    aux.grab_focus(obj.common().clone());
-                }
-            },
-        )
-        .and_on(
-            focus_config.interaction_handler,
-            move |(obj, aux), _: &ReleaseEvent| {
-                if focus_config.mouse_trigger == FocusMouseTrigger::Release {
-                    aux.grab_focus(obj.common().clone());
-                }
-            },
-        )
         .and_on(aux.id, move |(obj, aux), evt: &ui::FocusChangedEvent| {
             if evt
                 .old_focus
@@ -295,43 +370,73 @@ pub fn focus_forwarder<E: ui::Element<Aux = T>, T: 'static>(
 }
 
 pub enum KeyboardEvent {
-    KeyPress(ui::KeyInput),
-    KeyRelease(ui::KeyInput),
+    KeyPress(ui::KeyEvent),
+    KeyRelease(ui::KeyEvent),
     Text(char),
+    Preedit(ui::Preedit),
+    Commit(String),
+}
+
+#[inline]
+fn has_keyboard_input<T, W: ui::WidgetChildren<T>>(obj: &W, aux: &ui::Aux<T>) -> bool {
+    aux.has_focus(obj.common())
 }
 
+/// Creates a listener which handles keyboard input while the widget of `common` has focus.
+///
+/// Keyboard input is emitted from the ID of the focused widget (see [`Aux::dispatch_input`](ui::Aux::dispatch_input)).
 pub fn keyboard_handler<T, W: ui::WidgetChildren<T>>(
     aux: &mut ui::Aux<T>,
+    common: &ui::CommonRef,
     callback: impl Fn(&mut W, &mut ui::Aux<T>, KeyboardEvent) + Copy + 'static,
 ) -> ui::Listener<(ui::Write<W>, ui::Write<ui::Aux<T>>)> {
+    let id = common.read(|x| x.id());
     aux.listen::<(ui::Write<W>, ui::Write<ui::Aux<T>>)>()
-        .and_on(aux.id, move |(obj, aux), event: &ui::KeyPressEvent| {
+        .and_on(id, move |(obj, aux), event: &ui::KeyPressEvent| {
             if invisible_to_input(obj.visible()) {
                 return;
             }
 
-            if let Some(e) = event.0.with(|_| aux.has_focus(obj.common())) {
-                callback(obj, aux, KeyboardEvent::KeyPress(*e));
+            if let Some(e) = event.0.with(|_| has_keyboard_input(obj, aux)) {
+                callback(obj, aux, KeyboardEvent::KeyPress(e.clone()));
             }
         })
-        .and_on(aux.id, move |(obj, aux), event: &ui::KeyReleaseEvent| {
+        .and_on(id, move |(obj, aux), event: &ui::KeyReleaseEvent| {
             if invisible_to_input(obj.visible()) {
                 return;
             }
 
-            if let Some(e) = event.0.with(|_| aux.has_focus(obj.common())) {
-                callback(obj, aux, KeyboardEvent::KeyRelease(*e));
+            if let Some(e) = event.0.with(|_| has_keyboard_input(obj, aux)) {
+                callback(obj, aux, KeyboardEvent::KeyRelease(e.clone()));
             }
         })
-        .and_on(aux.id, move |(obj, aux), event: &ui::TextEvent| {
+        .and_on(id, move |(obj, aux), event: &ui::TextEvent| {
             if invisible_to_input(obj.visible()) {
                 return;
             }
 
-            if let Some(e) = event.0.with(|_| aux.has_focus(obj.common())) {
+            if let Some(e) = event.0.with(|_| has_keyboard_input(obj, aux)) {
                 callback(obj, aux, KeyboardEvent::Text(*e));
             }
         })
+        .and_on(id, move |(obj, aux), event: &ui::PreeditEvent| {
+            if invisible_to_input(obj.visible()) {
+                return;
+            }
+
+            if let Some(e) = event.0.with(|_| has_keyboard_input(obj, aux)) {
+                callback(obj, aux, KeyboardEvent::Preedit(e.clone()));
+            }
+        })
+        .and_on(id, move |(obj, aux), event: &ui::CommitEvent| {
+            if invisible_to_input(obj.visible()) {
+                return;
+            }
+
+            if let Some(e) = event.0.with(|_| has_keyboard_input(obj, aux)) {
+                callback(obj, aux, KeyboardEvent::Commit(e.clone()));
+            }
+        })
 }
 
 pub fn keyboard_forwarder<E: ui::Element<Aux = T>, T: 'static>(
@@ -340,9 +445,51 @@ pub fn keyboard_forwarder<E: ui::Element<Aux = T>, T: 'static>(
         KeyboardEvent::KeyPress(x) => obj.emit(aux, KeyPressEvent(x)),
         KeyboardEvent::KeyRelease(x) => obj.emit(aux, KeyReleaseEvent(x)),
         KeyboardEvent::Text(x) => obj.emit(aux, TextEvent(x)),
+        KeyboardEvent::Preedit(x) => obj.emit(aux, PreeditEvent(x)),
+        KeyboardEvent::Commit(x) => obj.emit(aux, CommitEvent(x)),
     }
 }
 
+/// Creates a listener which handles scrolling while the cursor is within the bounds of the widget of `common`.
+///
+/// The widget is registered in the [spatial index](ui::spatial), since scrolling is emitted from the IDs of the widgets
+/// under the cursor.
+/// The scroll event is consumed, hence only the innermost widget under the cursor with a scroll handler will receive it.
+pub fn scroll_handler<T, W: ui::WidgetChildren<T>>(
+    aux: &mut ui::Aux<T>,
+    common: &ui::CommonRef,
+    callback: impl Fn(&mut W, &mut ui::Aux<T>, ui::ScrollInput) + Copy + 'static,
+) -> ui::Listener<(ui::Write<W>, ui::Write<ui::Aux<T>>)> {
+    let id = common.read(|x| x.id());
+    aux.spatial_index.insert(common);
+    aux.listen::<(ui::Write<W>, ui::Write<ui::Aux<T>>)>()
+        .and_on(id, move |(obj, aux), event: &ui::ScrollEvent| {
+            if invisible_to_input(obj.visible()) {
+                return;
+            }
+
+            let bounds = obj.bounds();
+            if let Some(&input) = event.0.with(|x| bounds.contains(x.position)) {
+                callback(obj, aux, input);
+            }
+        })
+}
+
+pub fn scroll_forwarder<E: ui::Element<Aux = T>, T: 'static>(
+) -> impl Fn(&mut E, &mut ui::Aux<T>, ui::ScrollInput) + Copy {
+    move |obj, aux, input| obj.emit(aux, ScrollEvent(input))
+}
+
+/// Accumulates vertical scrolling into `accumulator` and returns the whole number of lines scrolled towards the user (downwards).
+///
+/// The remaining fraction is kept in `accumulator`, so that precise scrolling (e.g. trackpads) eventually adds up to whole lines.
+pub fn scroll_lines(accumulator: &mut f32, input: &ui::ScrollInput, line_height: f32) -> i32 {
+    *accumulator -= input.line_delta(line_height).y;
+    let lines = accumulator.trunc();
+    *accumulator -= lines;
+    lines as i32
+}
+
 pub fn invisible_to_input(v: ui::Visibility) -> bool {
     v == ui::Visibility::NoSelf || v == ui::Visibility::Invisible || v == ui::Visibility::None
 }
@@ -1,8 +1,15 @@
 use {
     crate::{kit, prelude::*, theme, ui},
     reclutch::display as gfx,
+    std::time::Duration,
 };
 
+/// Time for which the caret is shown or hidden while blinking.
+const CARET_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+struct CaretBlinkEvent;
+
 /// Widget which can accept various forms of string-based user input.
 ///
 /// This widget shouldn't be used on its own. It is deliberately rendered as only the text and cursor.
@@ -17,6 +24,9 @@ pub struct TextBox<T: 'static> {
     censor: Option<Box<dyn FnMut(&str) -> String>>,
     multi_line: bool,
     cursor: usize,
+    preedit: ui::Preedit,
+    caret_visible: bool,
+    caret_timer: Option<ui::timer::TimerHandle>,
 
     painter: theme::Painter<Self>,
     common: ui::CommonRef,
@@ -27,42 +37,74 @@ pub struct TextBox<T: 'static> {
 impl<T: 'static> TextBox<T> {
     pub fn new(parent: ui::CommonRef, aux: &mut ui::Aux<T>) -> Self {
         let common = ui::CommonRef::new(parent);
+        common.with(|x| x.set_cursor_icon(ui::CursorIcon::Text));
 
         let focus_listener = kit::focus_handler(
             aux,
-            kit::focus_forwarder(),
-            kit::FocusConfig {
-                mouse_trigger: Default::default(),
-                interaction_handler: common.with(|x| x.id()),
+            &common,
+            |obj: &mut Self, aux, event| {
+                match event {
+                    kit::FocusEvent::Gained => obj.restart_caret_blink(aux),
+                    kit::FocusEvent::Lost => {
+                        obj.preedit = Default::default();
+                        obj.update_label();
+                        obj.stop_caret_blink(aux);
+                        aux.set_ime_cursor_area(None);
+                    }
+                }
+
+                kit::focus_forwarder()(obj, aux, event);
             },
+            None,
         );
 
-        let keyboard_listener = kit::keyboard_handler(aux, |obj: &mut Self, aux, event| {
-            let mut text = obj.text().to_string();
-            match event {
-                kit::KeyboardEvent::Text(c) => {
-                    text.insert(obj.cursor, c);
-                    obj.cursor += 1;
-                }
-                kit::KeyboardEvent::KeyPress(key) => match key {
-                    ui::KeyInput::Back if obj.cursor > 0 => {
-                        obj.cursor -= 1;
-                        text.remove(obj.cursor);
+        let keyboard_listener =
+            kit::keyboard_handler(aux, &common, |obj: &mut Self, aux, event| {
+                let mut text = obj.text().to_string();
+                match &event {
+                    kit::KeyboardEvent::Text(c) => {
+                        text.insert(obj.cursor, *c);
+                        obj.cursor += c.len_utf8();
                     }
-                    ui::KeyInput::Left if obj.cursor > 0 => {
-                        obj.cursor -= 1;
+                    kit::KeyboardEvent::Preedit(preedit) => {
+                        obj.preedit = preedit.clone();
                     }
-                    ui::KeyInput::Right if obj.cursor < text.len() => {
-                        obj.cursor += 1;
+                    kit::KeyboardEvent::Commit(commit) => {
+                        text.insert_str(obj.cursor, commit);
+                        obj.cursor += commit.len();
+                        obj.preedit = Default::default();
                     }
+                    kit::KeyboardEvent::KeyPress(key) => match key.key {
+                        Some(ui::KeyInput::Back) if obj.cursor > 0 => {
+                            obj.cursor = prev_char_boundary(&text, obj.cursor);
+                            text.remove(obj.cursor);
+                        }
+                        Some(ui::KeyInput::Left) if obj.cursor > 0 => {
+                            obj.cursor = prev_char_boundary(&text, obj.cursor);
+                        }
+                        Some(ui::KeyInput::Right) if obj.cursor < text.len() => {
+                            obj.cursor = next_char_boundary(&text, obj.cursor);
+                        }
+                        _ => {}
+                    },
                     _ => {}
-                },
-                _ => {}
-            }
-            obj.set_text(text);
+                }
+                obj.set_text(text);
 
-            kit::keyboard_forwarder()(obj, aux, event);
-        });
+                if !matches!(event, kit::KeyboardEvent::KeyRelease(_)) {
+                    obj.restart_caret_blink(aux);
+                }
+
+                kit::keyboard_forwarder()(obj, aux, event);
+            });
+
+        let blink_listener = aux.listen::<kit::ReadWrite<Self>>().and_on(
+            common.read(|x| x.id()),
+            |(obj, _), _: &CaretBlinkEvent| {
+                obj.caret_visible = !obj.caret_visible;
+                obj.repaint();
+            },
+        );
 
         TextBox {
             text_label: kit::Label::new(common.clone(), aux),
@@ -72,10 +114,17 @@ impl<T: 'static> TextBox<T> {
             censor: None,
             multi_line: false,
             cursor: 0,
+            preedit: Default::default(),
+            caret_visible: true,
+            caret_timer: None,
 
             painter: theme::get_painter(aux.theme.as_ref(), theme::painters::TEXT_BOX),
             common,
-            listeners: ui::ListenerList::new(vec![focus_listener, keyboard_listener]),
+            listeners: ui::ListenerList::new(vec![
+                focus_listener,
+                keyboard_listener,
+                blink_listener,
+            ]),
             components: ui::ComponentList::new().and_push(
                 kit::InteractionState::<T, Self, _>::new(
                     aux,
@@ -91,6 +140,7 @@ impl<T: 'static> TextBox<T> {
         self.text = text.to_string();
         self.cursor = self.cursor.min(self.text.len());
         self.update_label();
+        self.repaint();
     }
 
     #[inline]
@@ -158,21 +208,92 @@ impl<T: 'static> TextBox<T> {
         self.multi_line
     }
 
+    /// Changes the cursor position, as a byte index into the text.
     #[inline]
     pub fn set_cursor(&mut self, cursor: usize) {
         self.cursor = cursor;
+        self.repaint();
     }
 
+    /// Returns the cursor position, as a byte index into the text.
     #[inline]
     pub fn cursor(&self) -> usize {
         self.cursor
     }
 
+    /// Returns `true` if the caret is in the visible phase of blinking.
+    #[inline]
+    pub fn caret_visible(&self) -> bool {
+        self.caret_visible
+    }
+
+    /// Returns the input method composition which is currently being displayed at the cursor.
+    #[inline]
+    pub fn preedit(&self) -> &ui::Preedit {
+        &self.preedit
+    }
+
+    /// Returns the text as it is displayed; that is, with the input method composition inserted at the cursor.
+    pub fn display_text(&self) -> String {
+        let mut text = self.text.clone();
+        text.insert_str(self.cursor, &self.preedit.text);
+        text
+    }
+
+    /// Returns the caret position, as a byte index into the [displayed text](TextBox::display_text).
+    ///
+    /// During a composition, the caret is placed at the end of the range selected by the input method
+    /// (see [`Preedit::cursor`](ui::Preedit::cursor)), or before the composition if there is no such range.
+    /// Otherwise, the caret is at the cursor.
+    pub fn caret(&self) -> usize {
+        let offset = self
+            .preedit
+            .cursor
+            .map(|(_, end)| end.min(self.preedit.text.len()))
+            .unwrap_or(0);
+        self.cursor + offset
+    }
+
+    /// Shows the caret and restarts its blinking, so that the caret stays visible while typing.
+    fn restart_caret_blink(&mut self, aux: &mut ui::Aux<T>) {
+        self.stop_caret_blink(aux);
+        let timer = aux.set_interval(
+            &self.common.read(|x| x.id()),
+            CARET_BLINK_INTERVAL,
+            CaretBlinkEvent,
+        );
+        // otherwise the timer would keep firing should the text box be dropped while focused.
+        aux.timers.set_owner(timer, &self.common);
+        self.caret_timer = Some(timer);
+    }
+
+    fn stop_caret_blink(&mut self, aux: &mut ui::Aux<T>) {
+        if let Some(timer) = self.caret_timer.take() {
+            aux.cancel_timer(timer);
+        }
+        self.caret_visible = true;
+        self.repaint();
+    }
+
+    /// Returns the absolute rectangle of the caret.
+    ///
+    /// Themes which don't provide [`CARET_X`](theme::metrics::CARET_X) place the caret at the end of the text.
+    fn caret_rect(&mut self) -> gfx::Rect {
+        let label = self.text_label.bounds().size;
+        let x = theme::metrics(self, theme::metrics::CARET_X, |x| &mut x.painter)
+            .unwrap_or(label.width);
+        let origin = self.bounds().origin;
+        gfx::Rect::new(
+            gfx::Point::new(origin.x + x, origin.y),
+            gfx::Size::new(1., label.height),
+        )
+    }
+
     fn update_label(&mut self) {
-        let mut text = if self.text.is_empty() {
+        let mut text = if self.text.is_empty() && self.preedit.is_empty() {
             self.placeholder.clone()
         } else {
-            self.text.clone()
+            self.display_text()
         };
 
         if let Some(censor) = &mut self.censor {
@@ -200,6 +321,11 @@ impl<T: 'static> ui::Element for TextBox<T> {
         &self.common
     }
 
+    #[inline]
+    fn update_components(&mut self, aux: &mut ui::Aux<T>) {
+        ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
+    }
+
     fn update(&mut self, aux: &mut ui::Aux<T>) {
         self.text_label
             .set_color(aux.theme.color(if self.text.is_empty() {
@@ -208,9 +334,13 @@ impl<T: 'static> ui::Element for TextBox<T> {
                 theme::colors::FOREGROUND
             }));
 
-        ui::dispatch_components(self, aux, |x| &mut x.components).unwrap();
         ui::dispatch_list::<kit::ReadWrite<Self>, _>((self, aux), |(x, _)| &mut x.listeners);
 
+        if aux.has_focus(self.common()) {
+            let caret = self.caret_rect();
+            aux.set_ime_cursor_area(caret);
+        }
+
         ui::propagate_repaint(self);
     }
 
@@ -229,6 +359,22 @@ impl<T: 'static> ui::WidgetChildren<T> for TextBox<T> {
     crate::children![for <T>; text_label];
 }
 
+fn prev_char_boundary(text: &str, index: usize) -> usize {
+    text[..index]
+        .char_indices()
+        .next_back()
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn next_char_boundary(text: &str, index: usize) -> usize {
+    text[index..]
+        .chars()
+        .next()
+        .map(|c| index + c.len_utf8())
+        .unwrap_or(index)
+}
+
 /// Censor function for [`TextBox`](TextBox), appropriate for password fields.
 #[inline]
 pub fn password_censor(s: &str) -> String {
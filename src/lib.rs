@@ -14,9 +14,11 @@
 //!     - `theme::flat`; An implementation of the theme interface for a simple, dark, flat-style theme. Feature `themes` required.
 //! - `kit`; Toolkit of widgets. Feature `kit` required.
 //! - `app`; Application helper utility. Quick one-liner way to load a window and run a UI using Glutin/Winit and Skia, however offers minimal control in return.
+//! - `headless`; Runs a UI without a window, driven by synthesized input. Useful for testing.
 
 #[cfg(feature = "app")]
 pub mod app;
+pub mod headless;
 #[cfg(feature = "kit")]
 pub mod kit;
 pub mod theme;
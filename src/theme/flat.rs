@@ -93,8 +93,6 @@ impl<T: 'static> Theme<T> for FlatTheme {
             }),
             painters::TEXT_BOX => Box::new(TextBoxPainter {
                 theme: Rc::clone(&self.0),
-                count: 0,
-                last_cur: std::usize::MAX,
             }),
             painters::CHECK_MARK_BOX => Box::new(CheckMarkBoxPainter {
                 _theme: Rc::clone(&self.0),
@@ -240,8 +238,25 @@ impl<T: 'static> TypedPainter<T> for LabelPainter {
 
 struct TextBoxPainter {
     theme: Rc<Inner>,
-    count: usize,
-    last_cur: usize,
+}
+
+impl TextBoxPainter {
+    /// Returns the size of the first `index` bytes of `text`.
+    fn text_size(&self, text: &str, index: usize) -> gfx::Size {
+        let text_item = gfx::TextDisplayItem {
+            text: text.to_string().into(),
+            font: self.theme.fonts.ui_regular.0,
+            font_info: self.theme.fonts.ui_regular.1.clone(),
+            size: self.theme.font_sizes.ui,
+            bottom_left: Default::default(),
+            color: gfx::StyleColor::Color(Default::default()),
+        };
+        text_item
+            .limited_bounds(text[..index].chars().count())
+            .unwrap()
+            .size
+            .round()
+    }
 }
 
 impl<T: 'static> TypedPainter<T> for TextBoxPainter {
@@ -256,40 +271,56 @@ impl<T: 'static> TypedPainter<T> for TextBoxPainter {
             return Default::default();
         }
 
-        self.count += 1;
+        let text = obj.display_text();
+        let text_size = |index: usize| self.text_size(&text, index);
 
-        if self.last_cur != obj.cursor() {
-            self.count = 0;
-        }
+        let pos = obj.bounds().origin;
+        let cur = text_size(obj.cursor());
+        let caret = text_size(obj.caret());
 
-        self.last_cur = obj.cursor();
+        let mut out = gfx::DisplayListBuilder::new();
 
-        if self.count > 60 {
-            self.count = 0;
-            return Default::default();
-        } else if self.count > 30 {
-            return Default::default();
-        }
+        // the composition is underlined, and the range selected by the input method within it more heavily.
+        let preedit = obj.preedit();
+        if !preedit.is_empty() {
+            let end = text_size(obj.cursor() + preedit.text.len());
+            out.push_line(
+                gfx::Point::new(pos.x + cur.width, pos.y + cur.height),
+                gfx::Point::new(pos.x + end.width, pos.y + cur.height),
+                gfx::GraphicsDisplayStroke {
+                    thickness: 1.,
+                    color: aux.theme.color(colors::FOREGROUND).into(),
+                    ..Default::default()
+                },
+                None,
+            );
 
-        let mut out = gfx::DisplayListBuilder::new();
+            if let Some((start, end)) = preedit.cursor {
+                let len = preedit.text.len();
+                if start < end && end <= len {
+                    let start = text_size(obj.cursor() + start);
+                    let end = text_size(obj.cursor() + end);
+                    out.push_line(
+                        gfx::Point::new(pos.x + start.width, pos.y + cur.height),
+                        gfx::Point::new(pos.x + end.width, pos.y + cur.height),
+                        gfx::GraphicsDisplayStroke {
+                            thickness: 2.,
+                            color: aux.theme.color(colors::FOREGROUND).into(),
+                            ..Default::default()
+                        },
+                        None,
+                    );
+                }
+            }
+        }
 
-        let cur = gfx::TextDisplayItem {
-            text: obj.text().into(),
-            font: self.theme.fonts.ui_regular.0,
-            font_info: self.theme.fonts.ui_regular.1.clone(),
-            size: self.theme.font_sizes.ui,
-            bottom_left: Default::default(),
-            color: gfx::StyleColor::Color(Default::default()),
+        if !obj.caret_visible() {
+            return out.build();
         }
-        .limited_bounds(obj.cursor())
-        .unwrap()
-        .size
-        .round();
 
-        let pos = obj.bounds().origin;
         out.push_line(
-            gfx::Point::new(pos.x + cur.width, pos.y),
-            gfx::Point::new(pos.x + cur.width, pos.y + cur.height),
+            gfx::Point::new(pos.x + caret.width, pos.y),
+            gfx::Point::new(pos.x + caret.width, pos.y + cur.height),
             gfx::GraphicsDisplayStroke {
                 thickness: 1.,
                 color: aux.theme.color(colors::FOREGROUND).into(),
@@ -304,6 +335,13 @@ impl<T: 'static> TypedPainter<T> for TextBoxPainter {
     fn size_hint(&mut self, _obj: &mut kit::TextBox<T>) -> gfx::Size {
         Default::default()
     }
+
+    fn metrics(&self, obj: &kit::TextBox<T>, metric: &'static str) -> Option<f32> {
+        match metric {
+            metrics::CARET_X => Some(self.text_size(&obj.display_text(), obj.caret()).width),
+            _ => None,
+        }
+    }
 }
 
 fn check_mark(r: gfx::Rect) -> gfx::VectorPath {
@@ -153,6 +153,8 @@ pub mod metrics {
     pub const PADDING_X: &str = "padding_x";
     pub const PADDING_Y: &str = "padding_y";
     pub const CHECK_MARK_SPACING: &str = "spacing";
+    /// Horizontal offset of the caret of a text box from the origin of the text box.
+    pub const CARET_X: &str = "caret_x";
 }
 
 pub mod colors {
@@ -0,0 +1,150 @@
+//! Dirty tracking, used to redraw only when and where the UI has changed.
+//!
+//! Every `Common` has a [`DirtyFlag`](DirtyFlag) which is marked when the widget is repainted, and which
+//! bubbles up to the flags of its ancestors. Hence, a clean root implies that nothing needs to be redrawn.
+//!
+//! While drawing, [`DamageTracker`](DamageTracker) (stored in [`Aux`](super::Aux)) accumulates the
+//! rectangles of the widgets which were repainted, moved, or stopped being drawn.
+
+use {
+    reclutch::display as gfx,
+    std::{cell::Cell, collections::HashMap, rc::Rc},
+};
+
+/// Flag marking that a widget or any of its descendants needs to be redrawn.
+///
+/// `Common` keeps a second tree of these flags for layouts which need to be updated.
+#[derive(Debug)]
+pub struct DirtyFlag {
+    dirty: Cell<bool>,
+    parent: Option<Rc<DirtyFlag>>,
+}
+
+impl DirtyFlag {
+    /// Creates a new flag, initially dirty, as a child of `parent` (which is marked as well).
+    pub fn new(parent: Option<Rc<DirtyFlag>>) -> Self {
+        let flag = DirtyFlag {
+            dirty: Cell::new(true),
+            parent,
+        };
+        flag.mark_parent();
+        flag
+    }
+
+    /// Marks this flag and all its ancestors as dirty.
+    pub fn mark(&self) {
+        self.dirty.set(true);
+        self.mark_parent();
+    }
+
+    /// Marks the ancestors as dirty, without marking this flag.
+    pub fn mark_parent(&self) {
+        let mut parent = self.parent.as_ref();
+        while let Some(flag) = parent {
+            flag.dirty.set(true);
+            parent = flag.parent.as_ref();
+        }
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        self.dirty.set(false);
+    }
+
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+}
+
+/// Area of the window which changed during a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Damage {
+    /// Nothing changed.
+    None,
+    /// Only the area within the rectangle changed.
+    Region(gfx::Rect),
+    /// The entire window needs to be redrawn.
+    Full,
+}
+
+impl Damage {
+    /// Returns the union of both damaged areas.
+    pub fn union(self, other: Damage) -> Damage {
+        match (self, other) {
+            (Damage::Full, _) | (_, Damage::Full) => Damage::Full,
+            (Damage::None, x) | (x, Damage::None) => x,
+            (Damage::Region(a), Damage::Region(b)) => Damage::Region(a.union(&b)),
+        }
+    }
+
+    /// Returns the rectangle to clip presentation to, or `None` if nothing should be clipped.
+    #[inline]
+    pub fn clip(self) -> Option<gfx::Rect> {
+        match self {
+            Damage::Region(rect) => Some(rect),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Damage {
+    #[inline]
+    fn default() -> Self {
+        Damage::None
+    }
+}
+
+/// Accumulates the damaged area of a frame from the widgets being drawn.
+#[derive(Debug, Default)]
+pub struct DamageTracker {
+    previous: HashMap<u64, gfx::Rect>,
+    current: HashMap<u64, gfx::Rect>,
+    damage: Damage,
+}
+
+impl DamageTracker {
+    /// Records that widget `id` was drawn within `rect` (in absolute coordinates).
+    ///
+    /// Returns `true` if the widget must repaint; that is, if `repaint` is `true` or the widget moved since the last frame.
+    /// In that case, both the old and new rectangles are damaged.
+    pub fn record(&mut self, id: u64, rect: gfx::Rect, repaint: bool) -> bool {
+        let previous = self.previous.get(&id).copied();
+        let repaint = repaint || previous != Some(rect);
+        if repaint {
+            self.add(rect);
+            if let Some(previous) = previous {
+                self.add(previous);
+            }
+        }
+        self.current.insert(id, rect);
+        repaint
+    }
+
+    /// Marks `rect` as damaged.
+    #[inline]
+    pub fn add(&mut self, rect: gfx::Rect) {
+        self.damage = self.damage.union(Damage::Region(rect));
+    }
+
+    /// Marks the entire window as damaged (e.g. after a resize).
+    #[inline]
+    pub fn invalidate_all(&mut self) {
+        self.damage = Damage::Full;
+    }
+
+    /// Finishes a frame, returning the damaged area.
+    ///
+    /// Widgets which were drawn in the previous frame but not in this one (because they were hidden or dropped)
+    /// damage the rectangle they were last drawn in.
+    pub fn finish(&mut self) -> Damage {
+        for (id, rect) in &self.previous {
+            if !self.current.contains_key(id) {
+                self.damage = self.damage.union(Damage::Region(*rect));
+            }
+        }
+
+        self.previous = std::mem::take(&mut self.current);
+        std::mem::take(&mut self.damage)
+    }
+}
@@ -0,0 +1,90 @@
+//! Prioritized chain of filters which intercept raw input before it reaches widgets.
+//!
+//! Filters are registered through [`Aux::add_filter`](super::Aux::add_filter) and run by
+//! [`Aux::dispatch_input`](super::Aux::dispatch_input).
+
+use {
+    super::{Aux, ConsumableEvent, InputEvent},
+    std::{cell::RefCell, rc::Rc},
+};
+
+type Filter<T> = Rc<RefCell<dyn FnMut(&mut Aux<T>, &mut ConsumableEvent<InputEvent>)>>;
+
+/// Handle to a registered filter, used to remove it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FilterHandle(u64);
+
+/// Input filters, ordered by descending priority.
+///
+/// A filter which dispatches input from within itself doesn't see that input, as it's still running.
+pub struct EventFilters<T: 'static> {
+    filters: Vec<(i32, FilterHandle, Filter<T>)>,
+    next_handle: u64,
+}
+
+impl<T: 'static> EventFilters<T> {
+    pub fn new() -> Self {
+        EventFilters {
+            filters: Vec::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// Registers a filter which runs before any filter of a lower `priority`,
+    /// and after the filters of the same priority which were added before it.
+    pub fn add(
+        &mut self,
+        priority: i32,
+        filter: impl FnMut(&mut Aux<T>, &mut ConsumableEvent<InputEvent>) + 'static,
+    ) -> FilterHandle {
+        let handle = FilterHandle(self.next_handle);
+        self.next_handle += 1;
+
+        let idx = self
+            .filters
+            .iter()
+            .position(|&(x, _, _)| x < priority)
+            .unwrap_or(self.filters.len());
+        self.filters
+            .insert(idx, (priority, handle, Rc::new(RefCell::new(filter))));
+
+        handle
+    }
+
+    /// Removes a filter, returning `false` if it had already been removed.
+    pub fn remove(&mut self, handle: FilterHandle) -> bool {
+        let len = self.filters.len();
+        self.filters.retain(|&(_, x, _)| x != handle);
+        len != self.filters.len()
+    }
+
+    #[inline]
+    pub fn contains(&self, handle: FilterHandle) -> bool {
+        self.filters.iter().any(|&(_, x, _)| x == handle)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Returns the filters in the order they run, so that they can run while `self` is modified.
+    pub(crate) fn snapshot(&self) -> Vec<(FilterHandle, Filter<T>)> {
+        self.filters
+            .iter()
+            .map(|(_, handle, filter)| (*handle, Rc::clone(filter)))
+            .collect()
+    }
+}
+
+impl<T: 'static> Default for EventFilters<T> {
+    #[inline]
+    fn default() -> Self {
+        EventFilters::new()
+    }
+}
@@ -81,6 +81,20 @@ impl layout::Layout for HStack {
             .collect()
     }
 
+    fn margins(&self, id: &u64) -> layout::SideMargins {
+        self.entries
+            .get(id)
+            .map(|entry| {
+                layout::SideMargins::new(
+                    0.0,
+                    entry.config.right_margin,
+                    0.0,
+                    entry.config.left_margin,
+                )
+            })
+            .unwrap_or_else(layout::SideMargins::zero)
+    }
+
     fn min_size(&self) -> gfx::Size {
         let mut width = 0.0;
         let mut height = 0.0;
@@ -33,7 +33,7 @@ impl Item {
 
     pub fn rect(&self) -> gfx::Rect {
         match self {
-            Item::Widget(w) => w.with(|x| x.rect()),
+            Item::Widget(w) => w.read(|x| x.rect()),
             Item::Layout(l) => l.0.rect(),
         }
     }
@@ -72,6 +72,12 @@ pub trait Layout: 'static {
     fn len(&self) -> usize;
     fn items(&self) -> Vec<(&Item, &Self::Id)>;
 
+    /// Returns the margins which the layout places around an item.
+    #[inline]
+    fn margins(&self, _id: &Self::Id) -> SideMargins {
+        SideMargins::zero()
+    }
+
     fn min_size(&self) -> gfx::Size;
     fn update(&mut self, bounds: gfx::Rect);
 
@@ -87,7 +93,7 @@ pub trait Layout: 'static {
 /// Returns a boolean indicating whether an item should be subject to layout.
 pub fn should_layout(item: &Item) -> bool {
     if let Item::Widget(c) = item {
-        let v = c.with(|x| x.visible());
+        let v = c.read(|x| x.visible());
         v != ui::Visibility::NoLayout && v != ui::Visibility::None
     } else {
         true
@@ -101,6 +107,8 @@ pub(crate) trait DynNode: as_any::AsAny {
     fn set_rect(&mut self, rect: gfx::Rect);
     fn rect(&self) -> gfx::Rect;
     fn set_size(&mut self, size: Option<gfx::Size>);
+    fn layout_type_name(&self) -> &'static str;
+    fn items(&self) -> Vec<(&Item, SideMargins)>;
 }
 
 #[derive(Debug, Clone)]
@@ -195,7 +203,7 @@ impl<L: Layout> DynNode for Node<L> {
         let mut removal = Vec::new();
         for (item, id) in self.layout.items().clone() {
             if let Item::Widget(widget) = item {
-                if widget.with(|x| x.is_marked_for_detach()) {
+                if widget.read(|x| x.is_marked_for_detach()) {
                     // the layout is wrongly keeping the widget alive
                     removal.push(id.clone());
                 }
@@ -220,6 +228,19 @@ impl<L: Layout> DynNode for Node<L> {
     fn rect(&self) -> gfx::Rect {
         self.rect
     }
+
+    #[inline]
+    fn layout_type_name(&self) -> &'static str {
+        std::any::type_name::<L>()
+    }
+
+    fn items(&self) -> Vec<(&Item, SideMargins)> {
+        self.layout
+            .items()
+            .into_iter()
+            .map(|(x, id)| (x, self.layout.margins(id)))
+            .collect()
+    }
 }
 
 impl Downcast for dyn DynNode {}
@@ -230,6 +251,29 @@ impl DynamicNode {
     pub fn cast_mut<L: Layout>(&mut self) -> Option<&mut Node<L>> {
         self.0.as_mut().downcast_mut::<Node<L>>()
     }
+
+    /// Returns the full type name of the layout (e.g. `VStack`), as given by `std::any::type_name`.
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.0.layout_type_name()
+    }
+
+    #[inline]
+    pub fn rect(&self) -> gfx::Rect {
+        self.0.rect()
+    }
+
+    /// Returns the items of the layout, in the order given by [`Layout::items`](Layout::items).
+    #[inline]
+    pub fn items(&self) -> Vec<&Item> {
+        self.0.items().into_iter().map(|(x, _)| x).collect()
+    }
+
+    /// Returns the items of the layout along with their [margins](Layout::margins).
+    #[inline]
+    pub fn items_with_margins(&self) -> Vec<(&Item, SideMargins)> {
+        self.0.items()
+    }
 }
 
 pub fn update_direct_layout(common: &ui::CommonRef) {
@@ -254,15 +298,11 @@ fn update_layout_impl<T: 'static>(widget: &dyn WidgetChildren<T>) {
         }
     });
 
-    for child in widget.children() {
-        update_layout(child);
-    }
+    widget.for_each_child(&mut |child| update_layout(child));
 }
 
 fn resize_layout<T: 'static>(widget: &dyn WidgetChildren<T>) {
-    for child in widget.children() {
-        resize_layout(child);
-    }
+    widget.for_each_child(&mut |child| resize_layout(child));
 
     widget.common().with(|x| {
         if let Some(DynamicNode(layout)) = &mut x.layout {
@@ -272,6 +312,57 @@ fn resize_layout<T: 'static>(widget: &dyn WidgetChildren<T>) {
     });
 }
 
+/// Updates the layouts which were invalidated since the last pass, within the tree of `widget`.
+///
+/// Invalidated layouts are first resized bottom-up; if that changes the size of their widget, the layout of the parent is
+/// invalidated as well. They are then updated top-down. Subtrees without any invalidated layout aren't visited.
+pub fn update_dirty_layouts<T: 'static>(widget: &dyn WidgetChildren<T>) {
+    resize_dirty_layouts(widget);
+    update_dirty_layouts_impl(widget);
+}
+
+fn resize_dirty_layouts<T: 'static>(widget: &dyn WidgetChildren<T>) {
+    if !widget.common().read(|x| x.is_layout_dirty()) {
+        return;
+    }
+
+    widget.for_each_child(&mut |child| resize_dirty_layouts(child));
+
+    widget.common().with(|x| {
+        if x.needs_layout.get() {
+            if let Some(DynamicNode(layout)) = &mut x.layout {
+                layout.process_detachments();
+                layout.resize();
+            }
+            x.update_layout_size();
+        }
+    });
+}
+
+fn update_dirty_layouts_impl<T: 'static>(widget: &dyn WidgetChildren<T>) {
+    let dirty = widget.common().with(|x| {
+        if !x.is_layout_dirty() {
+            return false;
+        }
+
+        if x.needs_layout.replace(false) {
+            if let Some(DynamicNode(layout)) = &mut x.layout {
+                layout.update();
+            }
+            // resizing the children invalidates this layout again, although it has just been updated.
+            x.needs_layout.set(false);
+        }
+        true
+    });
+
+    if dirty {
+        widget.for_each_child(&mut |child| update_dirty_layouts_impl(child));
+
+        // children resized by the layouts above have been visited, so the whole subtree is up to date.
+        widget.common().read(|x| x.layout_dirty.clear());
+    }
+}
+
 pub type SideMargins = reclutch::euclid::SideOffsets2D<f32, reclutch::euclid::UnknownUnit>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
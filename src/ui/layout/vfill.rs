@@ -102,6 +102,13 @@ impl layout::Layout for VFill {
             .collect()
     }
 
+    fn margins(&self, id: &u64) -> layout::SideMargins {
+        self.entries
+            .get(id)
+            .map(|entry| entry.config.margins)
+            .unwrap_or_else(layout::SideMargins::zero)
+    }
+
     fn min_size(&self) -> gfx::Size {
         let mut width = 0.0;
         let mut height = 0.0;
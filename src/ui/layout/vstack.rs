@@ -82,6 +82,20 @@ impl layout::Layout for VStack {
             .collect()
     }
 
+    fn margins(&self, id: &u64) -> layout::SideMargins {
+        self.entries
+            .get(id)
+            .map(|entry| {
+                layout::SideMargins::new(
+                    entry.config.top_margin,
+                    0.0,
+                    entry.config.bottom_margin,
+                    0.0,
+                )
+            })
+            .unwrap_or_else(layout::SideMargins::zero)
+    }
+
     fn min_size(&self) -> gfx::Size {
         let mut width = 0.0;
         let mut height = 0.0;
@@ -0,0 +1,211 @@
+//! Attachment lifecycle of widgets.
+//!
+//! A widget is attached while it's connected to a root widget (one without a parent) through its parents, and neither it
+//! nor any of its parents have been marked for detachment or dropped.
+//!
+//! [`Aux::process_lifecycle`](super::Aux::process_lifecycle) emits an [`AttachedEvent`](AttachedEvent) or
+//! [`DetachedEvent`](DetachedEvent) from the ID of a widget whenever this changes. Once a widget has been reported as
+//! detached (or has been dropped), [`Listener`](super::Listener)s remove their handlers for its ID.
+
+use {
+    super::Common,
+    std::{
+        cell::{Cell, RefCell},
+        collections::{HashMap, HashSet},
+        rc::{Rc, Weak},
+    },
+};
+
+/// Emitted from the ID of a widget once it has been attached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachedEvent;
+
+/// Emitted from the ID of a widget once it has been detached.
+///
+/// Handlers for the ID of the widget are removed after this has been dispatched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetachedEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The widget hasn't been attached yet.
+    Pending,
+    Attached,
+    Detached,
+}
+
+struct Entry {
+    common: Weak<RefCell<Common>>,
+    state: State,
+}
+
+/// Attachment state of the widgets of a tree.
+///
+/// Widgets register themselves in the registry of their parent when created, so a tree shares the registry of its root,
+/// which is owned by the [`Aux`](super::Aux) of the tree.
+#[derive(Default)]
+pub struct Lifecycle {
+    entries: RefCell<HashMap<u64, Entry>>,
+    /// Incremented whenever a widget is created, marked for detachment or dropped.
+    changes: Cell<u64>,
+    processed: Cell<Option<u64>>,
+    /// Incremented whenever widgets are reported as detached.
+    detachments: Cell<u64>,
+}
+
+impl Lifecycle {
+    pub(crate) fn register(&self, common: &Rc<RefCell<Common>>) {
+        let id = common.borrow().id;
+        self.entries.borrow_mut().insert(
+            id,
+            Entry {
+                common: Rc::downgrade(common),
+                state: State::Pending,
+            },
+        );
+        self.invalidate();
+    }
+
+    /// Marks the structure of the widget tree as changed, so that the next lifecycle pass checks every widget.
+    #[inline]
+    pub(crate) fn invalidate(&self) {
+        self.changes.set(self.changes.get().wrapping_add(1));
+    }
+
+    #[inline]
+    pub(crate) fn detachments(&self) -> u64 {
+        self.detachments.get()
+    }
+
+    /// Returns `true` if `id` belongs to a registered widget which hasn't been reported as detached.
+    pub(crate) fn is_widget(&self, id: u64) -> bool {
+        self.entries
+            .borrow()
+            .get(&id)
+            .map(|x| x.state != State::Detached)
+            .unwrap_or(false)
+    }
+
+    /// Emits lifecycle events for the widgets whose attachment changed since the last pass.
+    ///
+    /// Returns `true` if any widgets were detached or dropped.
+    pub(crate) fn process(&self, mut emit: impl FnMut(u64, bool)) -> bool {
+        let changes = self.changes.get();
+        if self.processed.replace(Some(changes)) == Some(changes) {
+            return false;
+        }
+
+        let mut entries: Vec<_> = self
+            .entries
+            .borrow()
+            .iter()
+            .map(|(&id, entry)| (id, entry.common.upgrade(), entry.state))
+            .collect();
+        // parents are created before their children, so they're attached first.
+        entries.sort_by_key(|&(id, _, _)| id);
+
+        let mut removed = Vec::new();
+        let mut transitions = Vec::new();
+        for (id, common, state) in &entries {
+            let attached = match common {
+                // if the widget is being mutated, its attachment can't have changed.
+                Some(common) => common
+                    .try_borrow()
+                    .map(|x| x.is_attached())
+                    .unwrap_or(*state == State::Attached),
+                None => {
+                    removed.push((*id, *state));
+                    continue;
+                }
+            };
+
+            match (state, attached) {
+                (State::Pending, true) => transitions.push((*id, State::Attached)),
+                (State::Attached, false) => transitions.push((*id, State::Detached)),
+                _ => {}
+            }
+        }
+        // the strong references are dropped before touching the entries, as that may drop widgets.
+        drop(entries);
+
+        let detached = !removed.is_empty()
+            || transitions
+                .iter()
+                .any(|&(_, state)| state == State::Detached);
+
+        {
+            let mut entries = self.entries.borrow_mut();
+            for &(id, _) in &removed {
+                entries.remove(&id);
+            }
+            for &(id, state) in &transitions {
+                if let Some(entry) = entries.get_mut(&id) {
+                    entry.state = state;
+                }
+            }
+        }
+
+        for (id, state) in transitions {
+            emit(id, state == State::Attached);
+        }
+        for (id, state) in removed {
+            if state == State::Attached {
+                emit(id, false);
+            }
+        }
+
+        if detached {
+            self.detachments.set(self.detachments.get().wrapping_add(1));
+        }
+        detached
+    }
+
+    /// Returns the number of widgets which are alive.
+    pub fn live_widgets(&self) -> usize {
+        self.entries
+            .borrow()
+            .values()
+            .filter(|x| x.common.strong_count() > 0)
+            .count()
+    }
+}
+
+/// Snapshot of the live widgets, used to check that widgets created since have been released.
+///
+/// ```ignore
+/// let check = LeakCheck::new(&aux.lifecycle);
+/// // add and remove some widgets, then update...
+/// assert!(check.leaked().is_empty());
+/// ```
+pub struct LeakCheck {
+    lifecycle: Rc<Lifecycle>,
+    existing: HashSet<u64>,
+}
+
+impl LeakCheck {
+    pub fn new(lifecycle: &Rc<Lifecycle>) -> Self {
+        LeakCheck {
+            lifecycle: Rc::clone(lifecycle),
+            existing: lifecycle.entries.borrow().keys().copied().collect(),
+        }
+    }
+
+    /// Returns the IDs of the widgets created since the snapshot which are still alive despite not being attached,
+    /// as of the last lifecycle pass.
+    pub fn leaked(&self) -> Vec<u64> {
+        let mut leaked: Vec<_> = self
+            .lifecycle
+            .entries
+            .borrow()
+            .iter()
+            .filter(|(id, entry)| {
+                !self.existing.contains(id)
+                    && entry.state != State::Attached
+                    && entry.common.strong_count() > 0
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        leaked.sort_unstable();
+        leaked
+    }
+}
@@ -1,11 +1,25 @@
+pub mod damage;
+pub mod filter;
 pub mod layout;
+pub mod lifecycle;
+pub mod proxy;
+pub mod query;
+pub mod record;
+pub mod resource;
+pub mod snapshot;
+pub mod spatial;
+pub mod stats;
+pub mod system;
+pub mod task;
+pub mod timer;
+pub mod trace;
 pub mod view;
 
 use {
     crate::theme::Theme,
     reclutch::display as gfx,
     std::{
-        cell::Cell,
+        cell::{Cell, RefCell},
         collections::HashMap,
         ops::{Deref, DerefMut},
         rc::{Rc, Weak},
@@ -28,22 +42,106 @@ pub struct Aux<T: 'static> {
     pub central_widget: CommonRef,
     /// Current widget that has focus.
     pub focus_widget: Option<CommonRef>,
+    /// Absolute rectangle of the text caret which is receiving input method composition.
+    ///
+    /// Applications use this to place the input method candidate window.
+    pub ime_cursor_area: Option<gfx::Rect>,
+    /// Cursor icon requested by the widget under the cursor.
+    ///
+    /// This is resolved by [`update_cursor_icon`](update_cursor_icon).
+    pub cursor_icon: CursorIcon,
+    /// Scheduled timers.
+    pub timers: timer::Timers,
+    /// Receiver of events queued from other threads.
+    pub proxy: proxy::ProxyReceiver,
+    /// Executor of spawned tasks.
+    pub tasks: task::Executor,
+    /// Filters which intercept raw input.
+    pub filters: filter::EventFilters<T>,
+    grabs: Vec<InputGrab>,
+    /// Damaged area of the frame being drawn.
+    pub damage: damage::DamageTracker,
+    /// Widgets which pointer input is routed to.
+    pub spatial_index: spatial::SpatialIndex,
+    /// Systems which run over the widget tree every frame.
+    pub systems: system::Systems<T>,
+    /// Global resources, keyed by type.
+    pub resources: resource::Resources,
+    /// Log of emitted events.
+    pub trace: trace::EventTrace,
+    /// Counters of the work done by the UI.
+    pub stats: stats::Stats,
+    /// Recorder of raw input.
+    pub recorder: record::InputRecorder,
+    /// Attachment state of the widgets in the tree of `central_widget`.
+    pub lifecycle: Rc<lifecycle::Lifecycle>,
 }
 
 impl<T: 'static> Aux<T> {
+    /// Creates a new `Aux` with a unique queue ID and no focus.
+    ///
+    /// The default systems are registered (see [`Systems::new`](system::Systems::new)).
+    pub fn new(data: T, theme: Box<dyn Theme<T>>, central_widget: CommonRef) -> Self {
+        let lifecycle = central_widget.read(|x| Rc::clone(&x.lifecycle));
+        Aux {
+            data,
+            theme,
+            id: uniq::id::next(),
+            queue: Default::default(),
+            central_widget,
+            focus_widget: None,
+            ime_cursor_area: None,
+            cursor_icon: Default::default(),
+            timers: Default::default(),
+            proxy: Default::default(),
+            tasks: Default::default(),
+            filters: Default::default(),
+            grabs: Vec::new(),
+            damage: Default::default(),
+            spatial_index: Default::default(),
+            systems: Default::default(),
+            resources: Default::default(),
+            trace: Default::default(),
+            stats: Default::default(),
+            recorder: Default::default(),
+            lifecycle,
+        }
+    }
+
     /// Creates a new [`Listener`](Listener).
     #[inline]
     pub fn listen<U: uniq::Packable>(&self) -> Listener<U> {
-        Listener(Some(self.queue.listen()), Vec::new())
+        Listener {
+            listener: Some(self.queue.listen()),
+            lates: Vec::new(),
+            handlers: HashMap::new(),
+            detachments: self.lifecycle.detachments(),
+            lifecycle: Rc::clone(&self.lifecycle),
+        }
     }
 
     #[inline]
     pub fn emit<E: 'static>(&self, id: &impl Id, e: E) {
-        self.queue.emit(id.id(), e);
+        self.emitter().emit(id.id(), e);
+    }
+
+    /// Returns an [`Emitter`](Emitter) for the global queue.
+    #[inline]
+    pub fn emitter(&self) -> Emitter {
+        Emitter::new(&self.queue, &self.trace, &self.stats)
     }
 
+    /// Changes the focused widget.
+    ///
+    /// While an input grab is held, focus can only move within the grabbing subtree (or be cleared).
     pub fn grab_focus(&mut self, focus: impl Into<Option<CommonRef>>) {
         let mut focus = focus.into();
+        if let Some(focus) = &focus {
+            if !self.accepts_input(focus) {
+                return;
+            }
+        }
+
         if self.focus_widget != focus {
             std::mem::swap(&mut self.focus_widget, &mut focus);
             self.emit(
@@ -60,18 +158,463 @@ impl<T: 'static> Aux<T> {
     pub fn has_focus(&self, common: &CommonRef) -> bool {
         self.focus_widget.as_ref() == Some(common)
     }
+
+    /// Grabs input for the subtree of `common`, on top of any existing grab.
+    ///
+    /// Until the grab is released (through [`pop_grab`](Aux::pop_grab) or by detaching the widget),
+    /// [`dispatch_input`](Aux::dispatch_input) only emits input from the IDs of widgets within the subtree (and not from
+    /// the queue ID of `self`), and focus can't leave the subtree.
+    /// If the focused widget is outside the subtree, focus is cleared.
+    ///
+    /// If `dismiss_on_outside` is `true`, a [`GrabDismissEvent`](GrabDismissEvent) is emitted to `common` when
+    /// a press lands outside its bounds; the grab is not released automatically.
+    pub fn push_grab(&mut self, common: &CommonRef, dismiss_on_outside: bool) {
+        self.prune_grabs();
+        self.grabs.push(InputGrab {
+            common: common.downgrade(),
+            dismiss_on_outside,
+        });
+
+        if let Some(focus) = self.focus_widget.clone() {
+            if !is_descendant(&focus, common) {
+                self.grab_focus(None);
+            }
+        }
+    }
+
+    /// Releases the input grab held by `common`, returning `false` if it held no grab.
+    ///
+    /// Grabs which were pushed after it are kept.
+    pub fn pop_grab(&mut self, common: &CommonRef) -> bool {
+        self.prune_grabs();
+        if let Some(idx) = self
+            .grabs
+            .iter()
+            .rposition(|x| x.common.ptr_eq(&Rc::downgrade(common.get_rc())))
+        {
+            self.grabs.remove(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the widget holding the innermost input grab.
+    pub fn input_grab(&self) -> Option<CommonRef> {
+        self.grabs
+            .iter()
+            .rev()
+            .filter_map(|x| x.common.upgrade())
+            .find(|x| is_attached(x.get_rc()))
+    }
+
+    /// Returns `true` if input can be delivered to `common`; that is, if there's no input grab or
+    /// if `common` is within the subtree holding the innermost grab.
+    pub fn accepts_input(&self, common: &CommonRef) -> bool {
+        self.input_grab()
+            .map(|grab| is_descendant(common, &grab))
+            .unwrap_or(true)
+    }
+
+    fn prune_grabs(&mut self) {
+        self.grabs.retain(|x| {
+            x.common
+                .upgrade()
+                .map(|x| is_attached(x.get_rc()))
+                .unwrap_or(false)
+        });
+    }
+
+    /// Reports the absolute rectangle of the caret which is receiving text input.
+    ///
+    /// Pass `None` when no widget is accepting text input.
+    #[inline]
+    pub fn set_ime_cursor_area(&mut self, area: impl Into<Option<gfx::Rect>>) {
+        self.ime_cursor_area = area.into();
+    }
+
+    /// Schedules `event` to be emitted to `id` once `delay` has elapsed.
+    #[inline]
+    pub fn set_timeout<E: 'static>(
+        &mut self,
+        id: &impl Id,
+        delay: std::time::Duration,
+        event: E,
+    ) -> timer::TimerHandle {
+        self.timers.set_timeout(id.id(), delay, event)
+    }
+
+    /// Schedules `event` to be emitted to `id` every `interval`, until cancelled.
+    #[inline]
+    pub fn set_interval<E: Clone + 'static>(
+        &mut self,
+        id: &impl Id,
+        interval: std::time::Duration,
+        event: E,
+    ) -> timer::TimerHandle {
+        self.timers.set_interval(id.id(), interval, event)
+    }
+
+    /// Cancels a timer, returning `false` if it had already finished or been cancelled.
+    #[inline]
+    pub fn cancel_timer(&mut self, handle: timer::TimerHandle) -> bool {
+        self.timers.cancel(handle)
+    }
+
+    /// Registers an input filter, returning a handle to remove it with.
+    ///
+    /// Filters with a higher `priority` run first; filters of equal priority run in the order they were added.
+    ///
+    /// A filter may dispatch input itself (through [`dispatch_input`](Aux::dispatch_input)); that input passes through the
+    /// other filters, but not through the filter dispatching it, which is still running.
+    #[inline]
+    pub fn add_filter(
+        &mut self,
+        priority: i32,
+        filter: impl FnMut(&mut Aux<T>, &mut ConsumableEvent<InputEvent>) + 'static,
+    ) -> filter::FilterHandle {
+        self.filters.add(priority, filter)
+    }
+
+    /// Removes an input filter, returning `false` if it had already been removed.
+    #[inline]
+    pub fn remove_filter(&mut self, handle: filter::FilterHandle) -> bool {
+        self.filters.remove(handle)
+    }
+
+    /// Passes raw input through the input filters, then emits it from the queue ID of `self`.
+    ///
+    /// Filters can inspect the input, rewrite it with [`ConsumableEvent::replace`](ConsumableEvent::replace),
+    /// or consume it, in which case no further filters run and the input isn't emitted.
+    /// Returns `true` if the input was emitted.
+    ///
+    /// While [recording](Aux::start_recording), the input is recorded before any filter runs.
+    pub fn dispatch_input(&mut self, input: InputEvent) -> bool {
+        self.stats.record(|x| x.input_events += 1);
+        if self.recorder.is_recording() {
+            self.recorder.record(
+                self.timers.now(),
+                self.trace.frame(),
+                record::RecordedInput::Input(input.clone()),
+            );
+        }
+        let mut input = ConsumableEvent::new(input);
+        for (handle, filter) in self.filters.snapshot() {
+            // the filter may have been removed by a filter which ran before it.
+            if !self.filters.contains(handle) {
+                continue;
+            }
+
+            // the filter is already borrowed if it dispatched this input itself, in which case it's skipped.
+            if let Ok(mut filter) = filter.try_borrow_mut() {
+                (&mut *filter)(self, &mut input);
+            }
+
+            if input.is_consumed() {
+                return false;
+            }
+        }
+
+        self.prune_grabs();
+        let press = match input.get() {
+            InputEvent::MousePress(_, pos) => Some(*pos),
+            InputEvent::Pointer(x) if x.phase == PointerPhase::Began => Some(x.position),
+            _ => None,
+        };
+        if let (Some(pos), Some(grab)) = (press, self.grabs.last()) {
+            if grab.dismiss_on_outside {
+                let grab = grab.common.upgrade().unwrap();
+                let (id, rect) = grab.read(|x| (x.id(), x.absolute_rect()));
+                if !rect.contains(pos) {
+                    self.emit(&id, GrabDismissEvent { position: pos });
+                }
+            }
+        }
+
+        // input is emitted from the IDs of the widgets it concerns, sharing the consumption state, and from the queue ID of
+        // `self` unless an input grab is held, so that during a grab only the grabbing subtree receives input.
+        let grab = self.input_grab();
+        let mut ids = Vec::new();
+        if grab.is_none() {
+            ids.push(self.id);
+        }
+        let routed = match input.get() {
+            InputEvent::MousePress(_, pos) => self.spatial_index.route(None, *pos, true, false),
+            InputEvent::MouseRelease(_, pos) => self.spatial_index.route(None, *pos, false, true),
+            InputEvent::MouseMove(pos) => self.spatial_index.route(None, *pos, false, false),
+            InputEvent::Pointer(x) => self.spatial_index.route(
+                Some(x.id),
+                x.position,
+                x.phase == PointerPhase::Began,
+                x.phase == PointerPhase::Ended || x.phase == PointerPhase::Cancelled,
+            ),
+            InputEvent::Scroll(x) => self.spatial_index.query(x.position),
+            InputEvent::KeyPress(_)
+            | InputEvent::KeyRelease(_)
+            | InputEvent::Text(_)
+            | InputEvent::Preedit(_)
+            | InputEvent::Commit(_) => self
+                .focus_widget
+                .iter()
+                .chain(&grab)
+                .map(|x| x.read(|x| x.id()))
+                .collect(),
+        };
+        for id in routed {
+            let within_grab = match (&grab, self.spatial_index.get(id)) {
+                (Some(grab), Some(common)) => is_descendant(&common, grab),
+                _ => true,
+            };
+            if within_grab && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        input.get().clone().emit_to(&self.emitter(), &ids);
+        true
+    }
+
+    /// Returns a new proxy which can queue events from any thread.
+    #[inline]
+    pub fn proxy(&self) -> proxy::EventProxy {
+        self.proxy.proxy()
+    }
+
+    /// Emits the events queued through proxies, in the order they were queued.
+    ///
+    /// This should be called before propagating an update.
+    #[inline]
+    pub fn process_proxied_events(&mut self) -> usize {
+        self.proxy.process(&self.emitter())
+    }
+
+    /// Emits the events of elapsed timers.
+    ///
+    /// This should be called before propagating an update.
+    #[inline]
+    pub fn process_timers(&mut self) -> usize {
+        let emitter = Emitter::new(&self.queue, &self.trace, &self.stats);
+        self.timers.process(&emitter)
+    }
+
+    /// Emits lifecycle events for the widgets which were attached or detached since the last call.
+    ///
+    /// If widgets were detached, focus is cleared if the focused widget was among them, and their input grabs are released.
+    ///
+    /// This should be called before propagating an update.
+    pub fn process_lifecycle(&mut self) {
+        let lifecycle = Rc::clone(&self.lifecycle);
+        let detached = lifecycle.process(|id, attached| {
+            if attached {
+                self.emit(&id, lifecycle::AttachedEvent);
+            } else {
+                self.emit(&id, lifecycle::DetachedEvent);
+            }
+        });
+        if !detached {
+            return;
+        }
+
+        let focus_detached = self
+            .focus_widget
+            .as_ref()
+            .map(|x| !is_attached(x.get_rc()))
+            .unwrap_or(false);
+        if focus_detached {
+            self.grab_focus(None);
+        }
+        self.prune_grabs();
+    }
+
+    /// Starts recording raw input, discarding any recording in progress.
+    ///
+    /// See [`record`](record) for details.
+    #[inline]
+    pub fn start_recording(&mut self) {
+        self.recorder.start(self.timers.now());
+    }
+
+    /// Starts recording raw input, additionally writing each entry to `writer` as it's recorded.
+    #[inline]
+    pub fn start_recording_to(
+        &mut self,
+        writer: impl std::io::Write + 'static,
+    ) -> std::io::Result<()> {
+        self.recorder.start_to(self.timers.now(), writer)
+    }
+
+    /// Stops recording raw input and returns the recording, if one was in progress.
+    ///
+    /// Should writing to the writer given to [`start_recording_to`](Aux::start_recording_to) have failed, the error is
+    /// returned instead.
+    pub fn stop_recording(&mut self) -> Option<Result<record::Recording, record::RecordingError>> {
+        let (recording, error) = self.recorder.stop()?;
+        Some(match error {
+            Some(error) => Err(error.into()),
+            None => Ok(recording),
+        })
+    }
+
+    /// Records that the UI was resized to `size`, if recording.
+    ///
+    /// Resizes don't pass through [`dispatch_input`](Aux::dispatch_input), so the application reports them here.
+    pub fn record_resize(&mut self, size: gfx::Size) {
+        if self.recorder.is_recording() {
+            self.recorder.record(
+                self.timers.now(),
+                self.trace.frame(),
+                record::RecordedInput::Resize(size),
+            );
+        }
+    }
+
+    /// Inserts a global resource, returning the resource of the same type which it replaced.
+    #[inline]
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) -> Option<R> {
+        self.resources.insert(resource)
+    }
+
+    /// Removes a global resource and returns it.
+    #[inline]
+    pub fn remove_resource<R: 'static>(&mut self) -> Result<R, resource::ResourceError> {
+        self.resources.remove()
+    }
+
+    #[inline]
+    pub fn resource<R: 'static>(&self) -> Result<&R, resource::ResourceError> {
+        self.resources.get()
+    }
+
+    #[inline]
+    pub fn resource_mut<R: 'static>(&mut self) -> Result<&mut R, resource::ResourceError> {
+        self.resources.get_mut()
+    }
+
+    #[inline]
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains::<R>()
+    }
+
+    /// Mutably accesses a global resource alongside the rest of `self`.
+    ///
+    /// While `f` runs, the resource is inaccessible through `self`.
+    /// Should `f` insert a resource of the same type, the resource which was being accessed is dropped.
+    pub fn with_resource<R: 'static, O>(
+        &mut self,
+        f: impl FnOnce(&mut R, &mut Self) -> O,
+    ) -> Result<O, resource::ResourceError> {
+        let mut resource = self.resources.take::<R>()?;
+        let o = f(&mut resource, self);
+        self.resources.put_back(resource);
+        Ok(o)
+    }
+
+    /// Spawns a task owned by `owner`, which is cancelled once `owner` is dropped or marked for detachment.
+    ///
+    /// The output is emitted as a [`TaskOutputEvent`](task::TaskOutputEvent) from the ID of `owner`,
+    /// and a panic is emitted as a [`TaskPanicEvent`](task::TaskPanicEvent).
+    #[inline]
+    pub fn spawn<O: 'static>(
+        &mut self,
+        owner: &CommonRef,
+        future: impl std::future::Future<Output = O> + 'static,
+    ) -> task::TaskHandle {
+        self.tasks.spawn(owner, future)
+    }
+
+    /// Cancels a task, returning `false` if it had already completed or been cancelled.
+    #[inline]
+    pub fn cancel_task(&mut self, handle: task::TaskHandle) -> bool {
+        self.tasks.cancel(handle)
+    }
+
+    /// Returns a future which completes once `duration` has elapsed on the clock of the timers.
+    #[inline]
+    pub fn delay(&self, duration: std::time::Duration) -> task::Delay {
+        self.tasks.delay(self.timers.clock(), duration)
+    }
+
+    /// Polls the tasks which have been woken.
+    ///
+    /// This should be called before propagating an update.
+    #[inline]
+    pub fn run_tasks(&mut self) -> usize {
+        let now = self.timers.now();
+        let emitter = Emitter::new(&self.queue, &self.trace, &self.stats);
+        self.tasks.run(&emitter, now)
+    }
+
+    /// Returns the next instant at which timers or tasks need processing.
+    ///
+    /// If a task is ready to be polled, this is the current time.
+    pub fn next_deadline(&self) -> Option<std::time::Instant> {
+        if self.tasks.has_ready() {
+            return Some(self.timers.now());
+        }
+
+        match (self.timers.next_deadline(), self.tasks.next_deadline()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Emits events on the global queue, counting them in the [`stats`](stats) and recording them in the [`trace`](trace).
+///
+/// Every event the UI emits goes through an emitter, including input, timer, task, proxied and lifecycle events.
+#[derive(Clone, Copy)]
+pub struct Emitter<'a> {
+    queue: &'a uniq::rc::Queue,
+    trace: &'a trace::EventTrace,
+    stats: &'a stats::Stats,
+}
+
+impl<'a> Emitter<'a> {
+    #[inline]
+    pub fn new(
+        queue: &'a uniq::rc::Queue,
+        trace: &'a trace::EventTrace,
+        stats: &'a stats::Stats,
+    ) -> Self {
+        Emitter {
+            queue,
+            trace,
+            stats,
+        }
+    }
+
+    #[inline]
+    pub fn emit<E: 'static>(&self, id: u64, event: E) {
+        self.stats.record(|x| x.events_emitted += 1);
+        self.trace.record(id, std::any::type_name::<E>());
+        self.queue.emit(id, event);
+    }
 }
 
 pub type Read<T> = uniq::Read<T>;
 pub type Write<T> = uniq::Write<T>;
 
+type HandlerRemover<T> = Box<dyn Fn(&mut uniq::rc::EventListener<T>)>;
+
+/// Handlers added to a [`Listener`](Listener) for a single ID.
+struct Handlers<T: uniq::Packable> {
+    /// Whether the ID belonged to a widget when the handlers were added, in which case they're removed once it's detached.
+    widget: bool,
+    removers: Vec<(std::any::TypeId, HandlerRemover<T>)>,
+}
+
 /// Listener compatible with the [`dispatch`](dispatch) function.
 ///
 /// Created via [`listen`](Aux::listen).
-pub struct Listener<T: uniq::Packable>(
-    Option<uniq::rc::EventListener<T>>,
-    Vec<Box<dyn FnOnce(&mut Self)>>,
-);
+///
+/// Handlers for the ID of a widget are removed once the widget has been detached (see [`lifecycle`](lifecycle)).
+pub struct Listener<T: uniq::Packable> {
+    listener: Option<uniq::rc::EventListener<T>>,
+    lates: Vec<Box<dyn FnOnce(&mut Self)>>,
+    handlers: HashMap<u64, Handlers<T>>,
+    detachments: u64,
+    lifecycle: Rc<lifecycle::Lifecycle>,
+}
 
 impl<T: uniq::Packable> Listener<T> {
     /// Adds a handler to `self` and returns `Self`.
@@ -88,7 +631,7 @@ impl<T: uniq::Packable> Listener<T> {
     where
         T: uniq::Unpackable<'a, Unpacked = P>,
     {
-        self.0.as_mut().unwrap().on(id, handler);
+        self.on(id, handler);
         self
     }
 
@@ -106,7 +649,9 @@ impl<T: uniq::Packable> Listener<T> {
     where
         T: uniq::Unpackable<'a, Unpacked = P>,
     {
-        self.0.as_mut().unwrap().on(id, handler)
+        let key = self.listener.as_mut().unwrap().on(id, handler);
+        self.track::<E>(id);
+        key
     }
 
     /// Similar to [`on`](Listener::on), however the listener is added after processing of events is finished.
@@ -119,7 +664,7 @@ impl<T: uniq::Packable> Listener<T> {
     where
         T: uniq::Unpackable<'a, Unpacked = P>,
     {
-        self.1.push(Box::new(move |x| {
+        self.lates.push(Box::new(move |x| {
             x.on(id, handler);
         }));
         (id, std::any::TypeId::of::<E>())
@@ -127,20 +672,85 @@ impl<T: uniq::Packable> Listener<T> {
 
     /// Removes a handler which matches a specific `id` and event type.
     pub fn remove<E: 'static>(&mut self, id: u64) -> bool {
-        self.0.as_mut().unwrap().remove::<E>(id)
+        let ty = std::any::TypeId::of::<E>();
+        if let Some(handlers) = self.handlers.get_mut(&id) {
+            handlers.removers.retain(|(x, _)| *x != ty);
+            if handlers.removers.is_empty() {
+                self.handlers.remove(&id);
+            }
+        }
+        self.listener.as_mut().unwrap().remove::<E>(id)
     }
 
     /// Similar to [`remove`](Listener::remove), however the listener is removed after processing of events is finished.
     /// Serves a similar purpose to [`late_on`](Listener::late_on).
     pub fn late_remove<E: 'static>(&mut self, id: u64) {
-        self.1.push(Box::new(move |x| {
+        self.lates.push(Box::new(move |x| {
             x.remove::<E>(id);
         }));
     }
 
+    /// Removes every handler for `id`, returning how many were removed.
+    pub fn remove_id(&mut self, id: u64) -> usize {
+        let listener = self.listener.as_mut().unwrap();
+        self.handlers
+            .remove(&id)
+            .map(|handlers| {
+                for (_, remove) in &handlers.removers {
+                    remove(listener);
+                }
+                handlers.removers.len()
+            })
+            .unwrap_or(0)
+    }
+
     /// Returns `true` if there is a handler handling `id` and event type `E`.
     pub fn contains<E: 'static>(&self, id: u64) -> bool {
-        self.0.as_ref().unwrap().contains::<E>(id)
+        self.listener.as_ref().unwrap().contains::<E>(id)
+    }
+
+    /// Returns the IDs which are being handled, in no particular order.
+    ///
+    /// This can be used to check that handlers aren't leaking.
+    pub fn handled_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.handlers.keys().copied()
+    }
+
+    fn track<E: 'static>(&mut self, id: u64) {
+        let ty = std::any::TypeId::of::<E>();
+        let handlers = self.handlers.entry(id).or_insert_with(|| Handlers {
+            widget: self.lifecycle.is_widget(id),
+            removers: Vec::new(),
+        });
+        handlers.removers.retain(|(x, _)| *x != ty);
+        handlers.removers.push((
+            ty,
+            Box::new(move |x| {
+                x.remove::<E>(id);
+            }),
+        ));
+    }
+
+    /// Removes the handlers for the widgets which have been detached since this was last called.
+    fn remove_detached(&mut self) {
+        let detachments = self.lifecycle.detachments();
+        if self.detachments == detachments {
+            return;
+        }
+        self.detachments = detachments;
+
+        let listener = self.listener.as_mut().unwrap();
+        let lifecycle = &self.lifecycle;
+        self.handlers.retain(|&id, handlers| {
+            if handlers.widget && !lifecycle.is_widget(id) {
+                for (_, remove) in &handlers.removers {
+                    remove(listener);
+                }
+                false
+            } else {
+                true
+            }
+        });
     }
 }
 
@@ -170,11 +780,12 @@ where
         let packed = T::pack(it);
         let mut ls = l(T::unpack(packed)).0.take().unwrap();
         for l in &mut ls {
-            l.0.as_mut().unwrap().dispatch_packed(packed);
-            let lates = l.1.drain(..).collect::<Vec<_>>();
+            l.listener.as_mut().unwrap().dispatch_packed(packed);
+            let lates = l.lates.drain(..).collect::<Vec<_>>();
             for late in lates {
                 late(l);
             }
+            l.remove_detached();
         }
         l(T::unpack(packed)).0 = Some(ls);
     }
@@ -187,17 +798,34 @@ pub fn dispatch<'a, T: for<'b> uniq::Unpackable<'b> + 'static>(
 ) {
     unsafe {
         let packed = T::pack(it);
-        let mut ll = l(T::unpack(packed)).0.take().unwrap();
+        let mut ll = l(T::unpack(packed)).listener.take().unwrap();
         ll.dispatch_packed(packed);
-        l(T::unpack(packed)).0 = Some(ll);
+        l(T::unpack(packed)).listener = Some(ll);
         let mut lates = Vec::new();
-        std::mem::swap(&mut lates, &mut l(T::unpack(packed)).1);
+        std::mem::swap(&mut lates, &mut l(T::unpack(packed)).lates);
         for late in lates {
             late(l(T::unpack(packed)));
         }
+        // handlers for detached widgets are kept until now, so that they receive `DetachedEvent`.
+        l(T::unpack(packed)).remove_detached();
     }
 }
 
+/// Dispatches the events of a listener whose handlers take the resource `R` as a `Write<R>` parameter.
+///
+/// The resource is taken out of `aux` while dispatching (see [`Aux::with_resource`](Aux::with_resource)), so that it
+/// can be borrowed alongside `aux`. For other parameters (e.g. `Read<R>`), call [`dispatch`](dispatch) from
+/// `with_resource` in the same way.
+pub fn dispatch_with_resource<O: 'static, T: 'static, R: 'static>(
+    obj: &mut O,
+    aux: &mut Aux<T>,
+    l: impl Fn(&mut O) -> &mut Listener<(Write<O>, Write<Aux<T>>, Write<R>)>,
+) -> Result<(), resource::ResourceError> {
+    aux.with_resource(|resource: &mut R, aux| {
+        dispatch((obj, aux, resource), |(x, _, _)| l(x));
+    })
+}
+
 pub fn dispatch_components<W: WidgetChildren<T>, T: 'static>(
     o: &mut W,
     aux: &mut Aux<T>,
@@ -207,8 +835,15 @@ pub fn dispatch_components<W: WidgetChildren<T>, T: 'static>(
         .components
         .take()
         .ok_or(ComponentError::UpdateInProgress)?;
-    for c in components.values_mut() {
-        c.dispatch(o, aux);
+    for mut c in std::mem::take(&mut f(o).removed) {
+        c.dispatch_detach(o, aux);
+    }
+    for c in &mut components {
+        if !c.attached {
+            c.attached = true;
+            c.component.dispatch_attach(o, aux);
+        }
+        c.component.dispatch(o, aux);
     }
     f(o).components = Some(components);
     Ok(())
@@ -268,6 +903,27 @@ impl<T> ConsumableEvent<T> {
     pub fn get(&self) -> &T {
         &self.0.data
     }
+
+    /// Marks the event as consumed without inspecting it.
+    #[inline]
+    pub fn consume(&self) {
+        self.0.marker.set(false);
+    }
+
+    #[inline]
+    pub fn is_consumed(&self) -> bool {
+        !self.0.marker.get()
+    }
+
+    /// Replaces the event data, keeping the consumption state.
+    ///
+    /// Existing clones of this event are not affected.
+    pub fn replace(&mut self, val: T) {
+        self.0 = Rc::new(ConsumableEventInner {
+            marker: Cell::new(self.0.marker.get()),
+            data: val,
+        });
+    }
 }
 
 impl<T> Clone for ConsumableEvent<T> {
@@ -283,11 +939,223 @@ pub struct MouseReleaseEvent(pub ConsumableEvent<(MouseButton, gfx::Point)>);
 /// The mouse/cursor was moved.
 pub struct MouseMoveEvent(pub ConsumableEvent<gfx::Point>);
 /// A keyboard key was pressed down.
-pub struct KeyPressEvent(pub ConsumableEvent<KeyInput>);
+pub struct KeyPressEvent(pub ConsumableEvent<KeyEvent>);
 /// A keyboard key was released. Always paired with a prior `KeyPressEvent`.
-pub struct KeyReleaseEvent(pub ConsumableEvent<KeyInput>);
+pub struct KeyReleaseEvent(pub ConsumableEvent<KeyEvent>);
+/// The mouse wheel or trackpad was scrolled.
+///
+/// This should be consumed by the innermost widget under the cursor which handles scrolling.
+/// Since children are updated before their parents, unconsumed scroll events bubble outward.
+pub struct ScrollEvent(pub ConsumableEvent<ScrollInput>);
+/// A touch or stylus pointer made contact, moved or lifted.
+pub struct PointerEvent(pub ConsumableEvent<PointerInput>);
 /// Printable character was typed. Related to string input.
 pub struct TextEvent(pub ConsumableEvent<char>);
+/// The input method composition (pre-edit) text changed.
+///
+/// An empty composition text means the composition was cancelled.
+///
+/// Note that `app` only emits empty compositions (when the window loses focus), since winit 0.22 has no
+/// input method events; non-empty compositions only come from other sources of input (e.g. [`headless`](crate::headless)).
+pub struct PreeditEvent(pub ConsumableEvent<Preedit>);
+/// The input method finished a composition and committed the resulting text.
+///
+/// Like [`PreeditEvent`](PreeditEvent), this is never emitted by `app`; there, committed text arrives as
+/// [`TextEvent`](TextEvent)s.
+pub struct CommitEvent(pub ConsumableEvent<String>);
+
+/// Raw input, before it is emitted as its corresponding event.
+///
+/// Input passes through the event filters of [`Aux`](Aux) (see [`dispatch_input`](Aux::dispatch_input)).
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    MousePress(MouseButton, gfx::Point),
+    MouseRelease(MouseButton, gfx::Point),
+    MouseMove(gfx::Point),
+    KeyPress(KeyEvent),
+    KeyRelease(KeyEvent),
+    Scroll(ScrollInput),
+    Pointer(PointerInput),
+    Text(char),
+    Preedit(Preedit),
+    Commit(String),
+}
+
+impl InputEvent {
+    /// Emits the corresponding event (e.g. `MousePressEvent` for `MousePress`) to `id`.
+    #[inline]
+    pub fn emit(self, emitter: &Emitter, id: u64) {
+        self.emit_to(emitter, &[id])
+    }
+
+    /// Emits the corresponding event to each of `ids`.
+    ///
+    /// The emitted events share their consumption state; consuming one consumes them all.
+    pub fn emit_to(self, emitter: &Emitter, ids: &[u64]) {
+        fn emit_all<E: 'static, V>(
+            emitter: &Emitter,
+            ids: &[u64],
+            val: V,
+            new: impl Fn(ConsumableEvent<V>) -> E,
+        ) {
+            let event = ConsumableEvent::new(val);
+            for &id in ids {
+                emitter.emit(id, new(event.clone()));
+            }
+        }
+
+        match self {
+            InputEvent::MousePress(button, pos) => {
+                emit_all(emitter, ids, (button, pos), MousePressEvent)
+            }
+            InputEvent::MouseRelease(button, pos) => {
+                emit_all(emitter, ids, (button, pos), MouseReleaseEvent)
+            }
+            InputEvent::MouseMove(pos) => emit_all(emitter, ids, pos, MouseMoveEvent),
+            InputEvent::KeyPress(key) => emit_all(emitter, ids, key, KeyPressEvent),
+            InputEvent::KeyRelease(key) => emit_all(emitter, ids, key, KeyReleaseEvent),
+            InputEvent::Scroll(input) => emit_all(emitter, ids, input, ScrollEvent),
+            InputEvent::Pointer(input) => emit_all(emitter, ids, input, PointerEvent),
+            InputEvent::Text(c) => emit_all(emitter, ids, c, TextEvent),
+            InputEvent::Preedit(preedit) => emit_all(emitter, ids, preedit, PreeditEvent),
+            InputEvent::Commit(text) => emit_all(emitter, ids, text, CommitEvent),
+        }
+    }
+}
+
+/// Amount scrolled by a mouse wheel or trackpad.
+///
+/// Positive values indicate scrolling away from the user (upwards) or to the right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollInput {
+    /// Amount scrolled in lines, reported by devices which scroll in discrete steps (e.g. mouse wheels).
+    pub lines: gfx::Vector,
+    /// Amount scrolled in logical pixels, reported by precise devices (e.g. trackpads).
+    pub pixels: gfx::Vector,
+    /// Cursor position at the time of scrolling.
+    pub position: gfx::Point,
+}
+
+impl ScrollInput {
+    /// Returns the total amount scrolled in lines, given the height of a line in pixels.
+    #[inline]
+    pub fn line_delta(&self, line_height: f32) -> gfx::Vector {
+        self.lines + self.pixels / line_height
+    }
+
+    /// Returns the total amount scrolled in pixels, given the height of a line in pixels.
+    #[inline]
+    pub fn pixel_delta(&self, line_height: f32) -> gfx::Vector {
+        self.pixels + self.lines * line_height
+    }
+}
+
+/// Device which is producing pointer input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PointerKind {
+    Mouse,
+    Touch,
+    Pen,
+}
+
+/// Stage of a single pointer contact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PointerPhase {
+    /// The pointer made contact.
+    Began,
+    /// The pointer moved while in contact.
+    Moved,
+    /// The pointer was lifted.
+    Ended,
+    /// The platform cancelled the contact (e.g. the window lost focus).
+    Cancelled,
+}
+
+/// State of a single pointer, identified by `id`, for a given pointer event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerInput {
+    /// Identifies the pointer for the duration of its contact (e.g. a finger).
+    pub id: u64,
+    pub kind: PointerKind,
+    pub phase: PointerPhase,
+    pub position: gfx::Point,
+    /// Normalized pressure, ranging from `0.0` to `1.0`, if the device reports it.
+    pub pressure: Option<f32>,
+    /// Angle of a stylus in radians, relative to the surface, if the device reports it.
+    /// `0` is parallel to the surface and `π/2` is perpendicular.
+    pub tilt: Option<f32>,
+    /// `true` if this pointer made contact while no other pointer was in contact.
+    /// It remains the only primary pointer until every pointer has been lifted, even if it is lifted first.
+    /// Single-pointer interactions should only respond to the primary pointer.
+    pub primary: bool,
+}
+
+/// In-progress input method composition.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Preedit {
+    /// The text being composed, which has not been committed yet.
+    pub text: String,
+    /// The byte range within `text` that the input method has selected, if any.
+    pub cursor: Option<(usize, usize)>,
+}
+
+impl Preedit {
+    /// Creates a composition with a given text and no selection.
+    pub fn new(text: impl ToString) -> Self {
+        Preedit {
+            text: text.to_string(),
+            cursor: None,
+        }
+    }
+
+    /// Returns `true` if there is no composition in progress.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+/// Full description of a single keyboard key press or release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    /// Platform-specific identifier of the physical key.
+    ///
+    /// This does not change with the keyboard layout, thus it is appropriate for things such as movement controls.
+    pub scancode: u32,
+    /// The logical key, if the platform could map the physical key to one.
+    ///
+    /// This is `None` for keys which have no [`KeyInput`](KeyInput) equivalent (e.g. many media and international keys).
+    pub key: Option<KeyInput>,
+    /// State of the modifier keys at the time of the event.
+    pub modifiers: KeyModifiers,
+    /// `true` if this press was generated by the key being held down.
+    ///
+    /// Always `false` for key releases.
+    pub repeat: bool,
+    /// Text produced by the key press, if any.
+    ///
+    /// This is empty for key releases and for keys which don't produce text.
+    pub text: String,
+}
+
+impl KeyEvent {
+    /// Creates a key event with no modifiers, text or scancode; handy for synthesizing input.
+    pub fn new(key: impl Into<Option<KeyInput>>) -> Self {
+        KeyEvent {
+            scancode: 0,
+            key: key.into(),
+            modifiers: Default::default(),
+            repeat: false,
+            text: String::new(),
+        }
+    }
+
+    /// Returns `true` if the logical key is `key`.
+    #[inline]
+    pub fn is(&self, key: KeyInput) -> bool {
+        self.key == Some(key)
+    }
+}
 
 /// Clickable button on a mouse.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -310,6 +1178,23 @@ macro_rules! keyboard_enum {
             $($v),*
         }
 
+        impl $name {
+            /// Returns the name of the variant (e.g. `"Key1"` or `"A"`).
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($name::$v => stringify!($v)),*
+                }
+            }
+
+            /// Inverse of [`name`](Self::name).
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($v) => Some($name::$v),)*
+                    _ => None,
+                }
+            }
+        }
+
         #[cfg(feature = "app")]
         impl From<$other> for $name {
             fn from(other: $other) -> $name {
@@ -528,16 +1413,29 @@ pub fn f3<T, P1, P2, P3, W: WidgetChildren<T>>(
 
 /// Helper type to store a counted reference to a `Common`, or in other words, a reference to the core of a widget type (not the widget type itself).
 ///
-/// The reference type provides `RefCell`-like semantics using `Cell`, reducing the overhead to only `Rc` instead of `Rc` + `RefCell`.
-/// It does this by `take` and `replace`, inserted around accessor closures.
+/// Access follows the rules of `RefCell`; any number of nested [`read`](CommonRef::read)s can be active at once,
+/// whereas [`with`](CommonRef::with) requires exclusive access.
+/// Where conflicting access is possible (e.g. when reaching a parent from a child), use the fallible
+/// [`try_with`](CommonRef::try_with) and [`try_read`](CommonRef::try_read).
 #[derive(Clone)]
-#[repr(transparent)]
-pub struct CommonRef(Rc<Cell<Option<Common>>>);
+pub struct CommonRef(Rc<RefCell<Common>>, Rc<SharedFlags>);
+
+/// The parts of a `Common` which are shared with its children.
+///
+/// These are kept alongside the `RefCell` rather than read from it, so that children can be created while the `Common`
+/// is borrowed (e.g. while the parent constructs them).
+struct SharedFlags {
+    dirty: Rc<damage::DirtyFlag>,
+    layout_dirty: Rc<damage::DirtyFlag>,
+    needs_layout: Rc<Cell<bool>>,
+    geometry: Rc<spatial::GeometryChanges>,
+    lifecycle: Rc<lifecycle::Lifecycle>,
+}
 
 impl CommonRef {
     /// Creates a new `CommonRef` as an implied child of a `parent`.
     pub fn new(parent: impl Into<Option<CommonRef>>) -> Self {
-        CommonRef(Rc::new(Cell::new(Some(Common::new(parent)))))
+        CommonRef::with_info(parent, None)
     }
 
     // Creates a new `CommonRef` as an implied child of a `parent` with some additional `info`.
@@ -545,39 +1443,106 @@ impl CommonRef {
         parent: impl Into<Option<CommonRef>>,
         info: impl Into<Option<Box<dyn std::any::Any>>>,
     ) -> Self {
-        CommonRef(Rc::new(Cell::new(Some(Common::with_info(parent, info)))))
+        let common = Common::with_info(parent, info);
+        let flags = Rc::new(SharedFlags {
+            dirty: Rc::clone(&common.dirty),
+            layout_dirty: Rc::clone(&common.layout_dirty),
+            needs_layout: Rc::clone(&common.needs_layout),
+            geometry: Rc::clone(&common.geometry),
+            lifecycle: Rc::clone(&common.lifecycle),
+        });
+        let common = Rc::new(RefCell::new(common));
+        flags.lifecycle.register(&common);
+        CommonRef(common, flags)
     }
 
     /// Mutably access the inner `Common` through a closure.
     /// The return value of the closure is forwarded to the caller.
     ///
     /// This can be used to extract certain values or mutate, or both.
+    ///
+    /// # Panics
+    /// Panics if the `Common` is already being accessed elsewhere. See [`try_with`](CommonRef::try_with).
     pub fn with<R>(&self, f: impl FnOnce(&mut Common) -> R) -> R {
+        match self.try_with(f) {
+            Ok(r) => r,
+            Err(err) => panic!("`CommonRef::with` failed: {}", err),
+        }
+    }
+
+    /// Mutably access the inner `Common` through a closure, failing if it is already being accessed elsewhere.
+    pub fn try_with<R>(&self, f: impl FnOnce(&mut Common) -> R) -> Result<R, CommonAccessError> {
         let mut common = self
             .0
-            .take()
-            .expect("`CommonRef::with` is already being invoked somewhere else");
-        let r = f(&mut common);
-        self.0.replace(Some(common));
-        r
+            .try_borrow_mut()
+            .map_err(|_| CommonAccessError::AlreadyBorrowed)?;
+        Ok(f(&mut common))
+    }
+
+    /// Immutably access the inner `Common` through a closure.
+    ///
+    /// Unlike [`with`](CommonRef::with), this can be nested within other reads of the same `Common`.
+    ///
+    /// # Panics
+    /// Panics if the `Common` is being mutably accessed. See [`try_read`](CommonRef::try_read).
+    pub fn read<R>(&self, f: impl FnOnce(&Common) -> R) -> R {
+        match self.try_read(f) {
+            Ok(r) => r,
+            Err(err) => panic!("`CommonRef::read` failed: {}", err),
+        }
+    }
+
+    /// Immutably access the inner `Common` through a closure, failing if it is being mutably accessed.
+    pub fn try_read<R>(&self, f: impl FnOnce(&Common) -> R) -> Result<R, CommonAccessError> {
+        let common = self
+            .0
+            .try_borrow()
+            .map_err(|_| CommonAccessError::AlreadyMutablyBorrowed)?;
+        Ok(f(&common))
     }
 
     /// Returns a reference to the ref-counted `Common`.
     #[inline]
-    pub fn get_rc(&self) -> &Rc<Cell<Option<Common>>> {
+    pub fn get_rc(&self) -> &Rc<RefCell<Common>> {
         &self.0
     }
+
+    /// Returns a reference which doesn't keep the `Common` alive.
+    #[inline]
+    pub(crate) fn downgrade(&self) -> WeakCommonRef {
+        WeakCommonRef(Rc::downgrade(&self.0), Rc::clone(&self.1))
+    }
+}
+
+/// Weak counterpart of [`CommonRef`](CommonRef).
+#[derive(Clone)]
+pub(crate) struct WeakCommonRef(Weak<RefCell<Common>>, Rc<SharedFlags>);
+
+impl WeakCommonRef {
+    #[inline]
+    pub(crate) fn upgrade(&self) -> Option<CommonRef> {
+        self.0.upgrade().map(|x| CommonRef(x, Rc::clone(&self.1)))
+    }
 }
 
 impl PartialEq for CommonRef {
     #[inline]
     fn eq(&self, other: &CommonRef) -> bool {
-        self.with(|x| x.id()) == other.with(|x| x.id())
+        Rc::ptr_eq(&self.0, &other.0)
     }
 }
 
 impl Eq for CommonRef {}
 
+/// Error returned when a `Common` can't be accessed because of a conflicting access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CommonAccessError {
+    #[error("The common is already being accessed elsewhere")]
+    AlreadyBorrowed,
+    #[error("The common is being mutably accessed elsewhere")]
+    AlreadyMutablyBorrowed,
+}
+
 /// Contains the interaction state for a single widget.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Interaction {
@@ -618,6 +1583,46 @@ impl Default for LayoutMode {
     }
 }
 
+/// A press landed outside the bounds of the widget holding the innermost input grab.
+///
+/// Emitted from the ID of the grabbing widget, if it requested dismissal when pushing the grab.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrabDismissEvent {
+    pub position: gfx::Point,
+}
+
+struct InputGrab {
+    common: WeakCommonRef,
+    dismiss_on_outside: bool,
+}
+
+/// Returns `true` if `common` is `ancestor` or one of its descendants.
+pub fn is_descendant(common: &CommonRef, ancestor: &CommonRef) -> bool {
+    let mut current = Some(common.clone());
+    while let Some(common) = current {
+        if Rc::ptr_eq(common.get_rc(), ancestor.get_rc()) {
+            return true;
+        }
+        current = common.read(|x| x.parent());
+    }
+    false
+}
+
+/// Returns `true` if the `Common` has been marked for detachment.
+///
+/// Unlike `CommonRef::read`, this doesn't panic if the `Common` is being mutably accessed elsewhere (in which case it is
+/// treated as attached).
+pub(crate) fn is_detached(common: &Rc<RefCell<Common>>) -> bool {
+    common
+        .try_borrow()
+        .map(|x| x.is_marked_for_detach())
+        .unwrap_or(false)
+}
+
+fn is_attached(common: &Rc<RefCell<Common>>) -> bool {
+    common.try_borrow().map(|x| x.is_attached()).unwrap_or(true)
+}
+
 pub struct FocusChangedEvent {
     pub old_focus: Option<CommonRef>,
     pub new_focus: Option<CommonRef>,
@@ -642,15 +1647,57 @@ impl Default for FocusMode {
     }
 }
 
+/// Appearance of the mouse cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CursorIcon {
+    /// The platform-dependent default cursor; usually an arrow.
+    Default,
+    /// Indicates a link or clickable element; usually a hand.
+    Pointer,
+    /// Indicates selectable or editable text; usually an I-beam.
+    Text,
+    Crosshair,
+    Move,
+    Grab,
+    Grabbing,
+    NotAllowed,
+    Wait,
+    /// Indicates a horizontally resizable edge.
+    ResizeHorizontal,
+    /// Indicates a vertically resizable edge.
+    ResizeVertical,
+    /// Indicates a resizable top-left or bottom-right corner.
+    ResizeNwse,
+    /// Indicates a resizable top-right or bottom-left corner.
+    ResizeNesw,
+}
+
+impl Default for CursorIcon {
+    #[inline]
+    fn default() -> Self {
+        CursorIcon::Default
+    }
+}
+
 pub trait Component: DispatchableComponent + 'static {
     type Type: 'static;
     type Object: Element<Aux = Self::Type>;
 
     fn update(&mut self, obj: &mut Self::Object, aux: &mut Aux<Self::Type>);
+
+    /// Invoked once the component has been added to the widget, before its first update.
+    #[inline]
+    fn on_attach(&mut self, _obj: &mut Self::Object, _aux: &mut Aux<Self::Type>) {}
+
+    /// Invoked once the component has been removed from the widget, if it had been attached.
+    #[inline]
+    fn on_detach(&mut self, _obj: &mut Self::Object, _aux: &mut Aux<Self::Type>) {}
 }
 
 pub trait DispatchableComponent: as_any::AsAny {
     fn dispatch(&mut self, obj: &mut dyn std::any::Any, aux: &mut dyn std::any::Any);
+    fn dispatch_attach(&mut self, obj: &mut dyn std::any::Any, aux: &mut dyn std::any::Any);
+    fn dispatch_detach(&mut self, obj: &mut dyn std::any::Any, aux: &mut dyn std::any::Any);
 }
 
 impl as_any::Downcast for dyn DispatchableComponent {}
@@ -659,13 +1706,73 @@ impl<O: WidgetChildren<T>, T: 'static, C: Component<Type = T, Object = O>> Dispa
     for C
 {
     fn dispatch(&mut self, obj: &mut dyn std::any::Any, aux: &mut dyn std::any::Any) {
-        self.update(
-            obj.downcast_mut::<O>()
-                .expect("dispatched components with incorrect object"),
-            aux.downcast_mut::<Aux<T>>()
-                .expect("dispatched components with incorrect auxiliary"),
-        )
+        let (obj, aux) = downcast_component_args::<O, T>(obj, aux);
+        self.update(obj, aux)
+    }
+
+    fn dispatch_attach(&mut self, obj: &mut dyn std::any::Any, aux: &mut dyn std::any::Any) {
+        let (obj, aux) = downcast_component_args::<O, T>(obj, aux);
+        self.on_attach(obj, aux)
+    }
+
+    fn dispatch_detach(&mut self, obj: &mut dyn std::any::Any, aux: &mut dyn std::any::Any) {
+        let (obj, aux) = downcast_component_args::<O, T>(obj, aux);
+        self.on_detach(obj, aux)
+    }
+}
+
+fn downcast_component_args<'a, O: 'static, T: 'static>(
+    obj: &'a mut dyn std::any::Any,
+    aux: &'a mut dyn std::any::Any,
+) -> (&'a mut O, &'a mut Aux<T>) {
+    (
+        obj.downcast_mut::<O>()
+            .expect("dispatched components with incorrect object"),
+        aux.downcast_mut::<Aux<T>>()
+            .expect("dispatched components with incorrect auxiliary"),
+    )
+}
+
+/// Component which works with any widget, rather than a specific widget type.
+///
+/// Widget components are added to the `Common` of a widget at runtime (see [`Common::add_component`](Common::add_component)),
+/// so behaviours can be attached to existing widgets without wrapping them. They are updated by
+/// [`propagate_components`](propagate_components), in the order they were added, before the components of the widget
+/// itself are dispatched; hence they see input first, and can consume it to override the handling of the widget.
+pub trait WidgetComponent: as_any::AsAny {
+    type Aux: 'static;
+
+    fn update(&mut self, widget: &mut dyn WidgetChildren<Self::Aux>, aux: &mut Aux<Self::Aux>);
+
+    /// Invoked once the component has been added to the widget, before its first update.
+    #[inline]
+    fn on_attach(
+        &mut self,
+        _widget: &mut dyn WidgetChildren<Self::Aux>,
+        _aux: &mut Aux<Self::Aux>,
+    ) {
     }
+
+    /// Invoked once the component has been removed from the widget, if it had been attached.
+    #[inline]
+    fn on_detach(
+        &mut self,
+        _widget: &mut dyn WidgetChildren<Self::Aux>,
+        _aux: &mut Aux<Self::Aux>,
+    ) {
+    }
+}
+
+impl<T: 'static> as_any::Downcast for dyn WidgetComponent<Aux = T> {}
+
+type BoxedWidgetComponent<T> = Box<dyn WidgetComponent<Aux = T>>;
+
+struct WidgetComponentEntry {
+    type_id: std::any::TypeId,
+    type_name: &'static str,
+    attached: bool,
+    /// A `BoxedWidgetComponent`, as `Common` isn't generic over the auxiliary type.
+    component: Box<dyn std::any::Any>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -674,30 +1781,42 @@ pub enum ComponentError {
     UpdateInProgress,
     #[error("Component type does not exist for this widget")]
     MissingComponent,
+    #[error("Component type already exists for this widget")]
+    DuplicateComponent,
+}
+
+struct ComponentEntry {
+    type_id: std::any::TypeId,
+    attached: bool,
+    component: Box<dyn DispatchableComponent>,
 }
 
+/// Ordered list of components, dispatched through [`dispatch_components`](dispatch_components).
+///
+/// Components are updated in the order of the list, and a widget can only have one component of each type.
 pub struct ComponentList<E: 'static + ?Sized + Element> {
-    components: Option<HashMap<std::any::TypeId, Box<dyn DispatchableComponent>>>,
+    components: Option<Vec<ComponentEntry>>,
+    /// Removed components which are yet to be detached.
+    removed: Vec<Box<dyn DispatchableComponent>>,
     _spooky: std::marker::PhantomData<E>,
 }
 
 impl<E: Element> ComponentList<E> {
     pub fn new() -> Self {
         ComponentList {
-            components: Some(HashMap::new()),
+            components: Some(Vec::new()),
+            removed: Vec::new(),
             _spooky: Default::default(),
         }
     }
 
+    /// Adds a component to the end of the list.
     pub fn push<C: Component<Object = E, Type = E::Aux>>(
         &mut self,
         component: C,
     ) -> Result<(), ComponentError> {
-        self.components
-            .as_mut()
-            .ok_or(ComponentError::UpdateInProgress)?
-            .insert(std::any::TypeId::of::<C>(), Box::new(component));
-        Ok(())
+        let len = self.len();
+        self.insert(len, component)
     }
 
     pub fn and_push<C: Component<Object = E, Type = E::Aux>>(mut self, component: C) -> Self {
@@ -705,14 +1824,71 @@ impl<E: Element> ComponentList<E> {
         self
     }
 
+    /// Adds a component at `index` in the list, shifting the components after it.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the length of the list.
+    pub fn insert<C: Component<Object = E, Type = E::Aux>>(
+        &mut self,
+        index: usize,
+        component: C,
+    ) -> Result<(), ComponentError> {
+        let type_id = std::any::TypeId::of::<C>();
+        let components = self
+            .components
+            .as_mut()
+            .ok_or(ComponentError::UpdateInProgress)?;
+        if components.iter().any(|x| x.type_id == type_id) {
+            return Err(ComponentError::DuplicateComponent);
+        }
+        components.insert(
+            index,
+            ComponentEntry {
+                type_id,
+                attached: false,
+                component: Box::new(component),
+            },
+        );
+        Ok(())
+    }
+
+    /// Removes a component.
+    ///
+    /// If the component has been attached, it is detached on the next dispatch, and then dropped.
+    pub fn remove<C: Component<Object = E, Type = E::Aux>>(
+        &mut self,
+    ) -> Result<(), ComponentError> {
+        let index = self.position::<C>()?;
+        let entry = self.components.as_mut().unwrap().remove(index);
+        if entry.attached {
+            self.removed.push(entry.component);
+        }
+        Ok(())
+    }
+
+    /// Returns the index of a component in the list.
+    pub fn position<C: Component<Object = E, Type = E::Aux>>(
+        &self,
+    ) -> Result<usize, ComponentError> {
+        let type_id = std::any::TypeId::of::<C>();
+        self.components
+            .as_ref()
+            .ok_or(ComponentError::UpdateInProgress)?
+            .iter()
+            .position(|x| x.type_id == type_id)
+            .ok_or(ComponentError::MissingComponent)
+    }
+
+    #[inline]
+    pub fn contains<C: Component<Object = E, Type = E::Aux>>(&self) -> bool {
+        self.position::<C>().is_ok()
+    }
+
     pub fn get<C: Component<Object = E, Type = E::Aux>>(&self) -> Result<&C, ComponentError> {
         use as_any::Downcast;
-        Ok(self
-            .components
-            .as_ref()
-            .ok_or(ComponentError::UpdateInProgress)?
-            .get(&std::any::TypeId::of::<C>())
-            .ok_or(ComponentError::MissingComponent)?
+        let index = self.position::<C>()?;
+        Ok(self.components.as_ref().unwrap()[index]
+            .component
             .as_ref()
             .downcast_ref::<C>()
             .unwrap())
@@ -722,16 +1898,44 @@ impl<E: Element> ComponentList<E> {
         &mut self,
     ) -> Result<&mut C, ComponentError> {
         use as_any::Downcast;
-        Ok(self
-            .components
-            .as_mut()
-            .ok_or(ComponentError::UpdateInProgress)?
-            .get_mut(&std::any::TypeId::of::<C>())
-            .ok_or(ComponentError::MissingComponent)?
+        let index = self.position::<C>()?;
+        Ok(self.components.as_mut().unwrap()[index]
+            .component
             .as_mut()
             .downcast_mut::<C>()
             .unwrap())
     }
+
+    /// Returns an iterator over the components, in the order they're updated.
+    ///
+    /// Nothing is yielded while the components are being updated.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn DispatchableComponent> {
+        self.components
+            .iter()
+            .flatten()
+            .map(|x| x.component.as_ref())
+    }
+
+    /// Returns an iterator over the components, in the order they're updated.
+    ///
+    /// Nothing is yielded while the components are being updated.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn DispatchableComponent> {
+        self.components
+            .iter_mut()
+            .flatten()
+            .map(|x| x.component.as_mut())
+    }
+
+    /// Returns the number of components, or zero while the components are being updated.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.components.as_ref().map(|x| x.len()).unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -771,11 +1975,24 @@ pub struct Common {
     visible: Visibility,
     updates: bool,
     rect: gfx::Rect,
-    parent: Option<Weak<Cell<Option<Common>>>>,
+    parent: Option<Weak<RefCell<Common>>>,
     cmds: CommandGroup,
     id: u64,
+    name: Option<String>,
+    tags: Vec<String>,
     info: Option<Box<dyn std::any::Any>>,
     should_detach: bool,
+    cursor_icon: Option<CursorIcon>,
+    dirty: Rc<damage::DirtyFlag>,
+    layout_dirty: Rc<damage::DirtyFlag>,
+    needs_layout: Rc<Cell<bool>>,
+    /// Flags of the parent, so that they can be set without borrowing the parent.
+    parent_flags: Option<Rc<SharedFlags>>,
+    geometry: Rc<spatial::GeometryChanges>,
+    lifecycle: Rc<lifecycle::Lifecycle>,
+    components: Option<Vec<WidgetComponentEntry>>,
+    /// Removed components which are yet to be detached.
+    removed_components: Vec<Box<dyn std::any::Any>>,
 }
 
 impl Common {
@@ -793,26 +2010,53 @@ impl Common {
         parent: impl Into<Option<CommonRef>>,
         info: impl Into<Option<Box<dyn std::any::Any>>>,
     ) -> Self {
+        let parent = parent.into();
+        // the flags are shared without borrowing the parent, which may be mutably borrowed (e.g. while it constructs this widget).
+        let parent_flags = parent.as_ref().map(|x| Rc::clone(&x.1));
+        let (geometry, lifecycle) = match &parent_flags {
+            Some(flags) => (Rc::clone(&flags.geometry), Rc::clone(&flags.lifecycle)),
+            None => Default::default(),
+        };
+
         Common {
             layout: None,
             layout_mode: Default::default(),
             visible: Default::default(),
             updates: true,
             rect: Default::default(),
-            parent: parent.into().map(|x| Rc::downgrade(x.get_rc())),
+            parent: parent.map(|x| Rc::downgrade(x.get_rc())),
             cmds: Default::default(),
             id: uniq::id::next(),
+            name: None,
+            tags: Vec::new(),
             info: info.into(),
             should_detach: false,
+            cursor_icon: None,
+            dirty: Rc::new(damage::DirtyFlag::new(
+                parent_flags.as_ref().map(|x| Rc::clone(&x.dirty)),
+            )),
+            layout_dirty: Rc::new(damage::DirtyFlag::new(
+                parent_flags.as_ref().map(|x| Rc::clone(&x.layout_dirty)),
+            )),
+            needs_layout: Default::default(),
+            parent_flags,
+            geometry,
+            lifecycle,
+            components: Some(Vec::new()),
+            removed_components: Vec::new(),
         }
     }
 
     /// Changes the widget rectangle.
     #[inline(always)]
     pub fn set_rect(&mut self, rect: gfx::Rect) {
+        let resized = self.rect.size != rect.size;
         self.rect = rect;
         self.repaint();
-        self.update_layout_size();
+        self.geometry.mark(self.id);
+        if resized {
+            self.resized();
+        }
     }
 
     /// Returns the widget rectangle.
@@ -824,9 +2068,13 @@ impl Common {
     /// Changes the widget rectangle size.
     #[inline]
     pub fn set_size(&mut self, size: gfx::Size) {
+        let resized = self.rect.size != size;
         self.rect.size = size;
         self.repaint();
-        self.update_layout_size();
+        self.geometry.mark(self.id);
+        if resized {
+            self.resized();
+        }
     }
 
     /// Returns the widget rectangle size.
@@ -840,6 +2088,7 @@ impl Common {
     pub fn set_position(&mut self, position: gfx::Point) {
         self.rect.origin = position;
         self.repaint();
+        self.geometry.mark(self.id);
     }
 
     /// Returns the widget rectangle position.
@@ -851,7 +2100,7 @@ impl Common {
     /// Sets the widget rectangle position from an absolute point.
     pub fn set_absolute_position(&mut self, position: gfx::Point) {
         if let Some(parent) = self.parent() {
-            self.set_position(position - parent.with(|x| x.absolute_position()).to_vector());
+            self.set_position(position - parent.read(|x| x.absolute_position()).to_vector());
         } else {
             self.set_position(position);
         }
@@ -860,7 +2109,7 @@ impl Common {
     /// Returns the widget rectangle position relative to the window.
     pub fn absolute_position(&self) -> gfx::Point {
         if let Some(parent) = self.parent() {
-            parent.with(|x| x.absolute_position()) + self.position().to_vector()
+            parent.read(|x| x.absolute_position()) + self.position().to_vector()
         } else {
             self.position()
         }
@@ -877,7 +2126,15 @@ impl Common {
     /// If `false`, this widget will be excluded from rendering.
     #[inline]
     pub fn set_visible(&mut self, visible: Visibility) {
-        self.visible = visible;
+        if self.visible != visible {
+            let laid_out = |x| x != Visibility::NoLayout && x != Visibility::None;
+            if laid_out(self.visible) != laid_out(visible) {
+                self.invalidate_parent_layout();
+            }
+            self.visible = visible;
+            self.dirty.mark();
+            self.geometry.mark(self.id);
+        }
     }
 
     /// Returns the visibility for this widget.
@@ -905,10 +2162,9 @@ impl Common {
     /// If `None` is returned then this is the root `Common`.
     #[inline]
     pub fn parent(&self) -> Option<CommonRef> {
-        self.parent
-            .clone()
-            .and_then(|x| x.upgrade())
-            .map(|x| CommonRef(x))
+        let parent = self.parent.as_ref().and_then(|x| x.upgrade())?;
+        let flags = Rc::clone(self.parent_flags.as_ref()?);
+        Some(CommonRef(parent, flags))
     }
 
     /// Returns the display command group.
@@ -921,12 +2177,19 @@ impl Common {
     #[inline]
     pub fn repaint(&mut self) {
         self.command_group().repaint();
+        self.dirty.mark();
+    }
+
+    /// Returns `true` if this widget or any of its descendants need to be redrawn.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.is_dirty()
     }
 
     /// Emits an event to the global queue on the behalf of [`id`](Common::id).
     #[inline]
     pub fn emit<T: 'static, E: 'static>(&self, aux: &mut Aux<T>, event: E) {
-        aux.queue.emit(self.id, event);
+        aux.emit(&self.id, event);
     }
 
     /// Returns the possible stored information.
@@ -977,27 +2240,129 @@ impl Common {
     #[inline]
     pub fn set_layout<L: layout::Layout>(&mut self, layout: impl Into<Option<layout::Node<L>>>) {
         self.layout = layout.into().map(|x| layout::DynamicNode(Box::new(x)));
+        self.invalidate_layout();
+    }
+
+    /// Returns the widget's layout, if any.
+    #[inline]
+    pub fn layout(&self) -> Option<&layout::DynamicNode> {
+        self.layout.as_ref()
     }
 
     /// Returns the widget's layout, if any.
+    ///
+    /// Since the layout may be modified through the returned reference, it is invalidated.
     #[inline]
     pub fn layout_mut(&mut self) -> Option<&mut layout::DynamicNode> {
+        self.invalidate_layout();
         self.layout.as_mut()
     }
 
     pub fn set_layout_mode(&mut self, mode: LayoutMode) {
         self.layout_mode = mode;
+        self.invalidate_layout();
         self.update_layout_size();
     }
 
+    /// Marks the widget's layout to be updated by the next [`update_dirty_layouts`](layout::update_dirty_layouts) pass.
+    pub fn invalidate_layout(&mut self) {
+        if self.layout.is_some() {
+            self.needs_layout.set(true);
+            self.layout_dirty.mark();
+        }
+    }
+
+    /// Returns `true` if the layout of this widget or any of its descendants needs to be updated.
+    #[inline]
+    pub fn is_layout_dirty(&self) -> bool {
+        self.layout_dirty.is_dirty()
+    }
+
     #[inline]
     pub fn layout_mode(&self) -> LayoutMode {
         self.layout_mode
     }
 
+    /// Changes the cursor icon shown while this widget is the topmost widget under the cursor.
+    ///
+    /// If `None`, the cursor icon of the parent applies.
+    #[inline]
+    pub fn set_cursor_icon(&mut self, icon: impl Into<Option<CursorIcon>>) {
+        self.cursor_icon = icon.into();
+    }
+
+    #[inline]
+    pub fn cursor_icon(&self) -> Option<CursorIcon> {
+        self.cursor_icon
+    }
+
+    /// Changes the name of the widget, which is used to refer to it in debugging output and [selectors](query::Selector) (`#name`).
+    ///
+    /// Names aren't required to be unique.
+    #[inline]
+    pub fn set_name(&mut self, name: impl Into<Option<String>>) {
+        self.name = name.into();
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Adds a class tag to the widget, which [selectors](query::Selector) can match (`.tag`).
+    ///
+    /// Returns `false` if the widget already had the tag.
+    pub fn add_tag(&mut self, tag: impl Into<String>) -> bool {
+        let tag = tag.into();
+        if self.has_tag(&tag) {
+            false
+        } else {
+            self.tags.push(tag);
+            true
+        }
+    }
+
+    /// Removes a class tag from the widget, returning `false` if the widget didn't have the tag.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let len = self.tags.len();
+        self.tags.retain(|x| x != tag);
+        self.tags.len() != len
+    }
+
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|x| x == tag)
+    }
+
+    /// Returns the class tags of the widget, in the order they were added.
+    #[inline]
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
     #[inline]
     pub fn mark_for_detach(&mut self) {
         self.should_detach = true;
+        self.dirty.mark();
+        self.geometry.mark(self.id);
+        self.lifecycle.invalidate();
+        // layouts drop detached widgets when they're updated.
+        self.invalidate_parent_layout();
+    }
+
+    /// Returns `true` if this widget is connected to a root widget through its parents, and neither it nor any of its
+    /// parents have been marked for detachment or dropped.
+    ///
+    /// Parents which are being mutably accessed are assumed to be attached.
+    pub fn is_attached(&self) -> bool {
+        if self.should_detach {
+            return false;
+        }
+
+        match &self.parent {
+            Some(parent) => parent.upgrade().map(|x| is_attached(&x)).unwrap_or(false),
+            None => true,
+        }
     }
 
     #[inline]
@@ -1005,6 +2370,107 @@ impl Common {
         self.should_detach
     }
 
+    /// Adds a widget component, which is updated after the components added before it.
+    ///
+    /// # Panics
+    /// The auxiliary type of the component must match that of the widget, otherwise the next update panics.
+    /// [`ElementMixin::add_component`](ElementMixin::add_component) checks this at compile time.
+    pub fn add_component<C: WidgetComponent>(
+        &mut self,
+        component: C,
+    ) -> Result<(), ComponentError> {
+        let type_id = std::any::TypeId::of::<C>();
+        let components = self
+            .components
+            .as_mut()
+            .ok_or(ComponentError::UpdateInProgress)?;
+        if components.iter().any(|x| x.type_id == type_id) {
+            return Err(ComponentError::DuplicateComponent);
+        }
+
+        let component: BoxedWidgetComponent<C::Aux> = Box::new(component);
+        components.push(WidgetComponentEntry {
+            type_id,
+            type_name: std::any::type_name::<C>(),
+            attached: false,
+            component: Box::new(component),
+        });
+        Ok(())
+    }
+
+    /// Removes a widget component.
+    ///
+    /// If the component has been attached, it is detached on the next update, and then dropped.
+    pub fn remove_component<C: WidgetComponent>(&mut self) -> Result<(), ComponentError> {
+        let type_id = std::any::TypeId::of::<C>();
+        let components = self
+            .components
+            .as_mut()
+            .ok_or(ComponentError::UpdateInProgress)?;
+        let index = components
+            .iter()
+            .position(|x| x.type_id == type_id)
+            .ok_or(ComponentError::MissingComponent)?;
+
+        let entry = components.remove(index);
+        if entry.attached {
+            self.removed_components.push(entry.component);
+        }
+        Ok(())
+    }
+
+    pub fn component<C: WidgetComponent>(&self) -> Result<&C, ComponentError> {
+        use as_any::Downcast;
+        let type_id = std::any::TypeId::of::<C>();
+        Ok(self
+            .components
+            .as_ref()
+            .ok_or(ComponentError::UpdateInProgress)?
+            .iter()
+            .find(|x| x.type_id == type_id)
+            .ok_or(ComponentError::MissingComponent)?
+            .component
+            .downcast_ref::<BoxedWidgetComponent<C::Aux>>()
+            .unwrap()
+            .as_ref()
+            .downcast_ref::<C>()
+            .unwrap())
+    }
+
+    pub fn component_mut<C: WidgetComponent>(&mut self) -> Result<&mut C, ComponentError> {
+        use as_any::Downcast;
+        let type_id = std::any::TypeId::of::<C>();
+        Ok(self
+            .components
+            .as_mut()
+            .ok_or(ComponentError::UpdateInProgress)?
+            .iter_mut()
+            .find(|x| x.type_id == type_id)
+            .ok_or(ComponentError::MissingComponent)?
+            .component
+            .downcast_mut::<BoxedWidgetComponent<C::Aux>>()
+            .unwrap()
+            .as_mut()
+            .downcast_mut::<C>()
+            .unwrap())
+    }
+
+    #[inline]
+    pub fn has_component<C: WidgetComponent>(&self) -> bool {
+        self.component::<C>().is_ok()
+    }
+
+    /// Returns the full type names of the widget components, in the order they're updated.
+    pub fn component_type_names(&self) -> Result<Vec<&'static str>, ComponentError> {
+        Ok(self
+            .components
+            .as_ref()
+            .ok_or(ComponentError::UpdateInProgress)?
+            .iter()
+            .map(|x| x.type_name)
+            .collect())
+    }
+
     fn update_layout_size(&mut self) {
         let size = self.size();
         let mut layout_size = None;
@@ -1016,9 +2482,40 @@ impl Common {
             }
         }
         if let Some(size) = layout_size {
-            self.rect.size = size;
+            if self.rect.size != size {
+                self.rect.size = size;
+                self.repaint();
+                self.geometry.mark(self.id);
+                self.invalidate_parent_layout();
+            }
         }
     }
+
+    fn resized(&mut self) {
+        self.invalidate_layout();
+        self.update_layout_size();
+        self.invalidate_parent_layout();
+    }
+
+    /// Invalidates the layout of the parent, which is assumed to be the layout this widget is placed in.
+    ///
+    /// The parent may be borrowed at this point (e.g. while its own layout resizes this widget), so the invalidation is
+    /// recorded through the flags shared with the parent rather than by borrowing it.
+    fn invalidate_parent_layout(&self) {
+        if let Some(flags) = &self.parent_flags {
+            flags.needs_layout.set(true);
+            self.layout_dirty.mark_parent();
+        }
+    }
+}
+
+impl Drop for Common {
+    fn drop(&mut self) {
+        // the parent needs to be redrawn without this widget.
+        self.dirty.mark_parent();
+        self.geometry.mark(self.id);
+        self.lifecycle.invalidate();
+    }
 }
 
 impl Id for Common {
@@ -1032,11 +2529,61 @@ impl Id for Common {
 
 /// Recursively propagate the `update` method.
 pub fn propagate_update<T: 'static>(widget: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>) {
-    for child in widget.children_mut().into_iter().rev() {
-        propagate_update(child, aux);
-    }
+    widget.for_each_child_mut_rev(&mut |child| propagate_update(child, aux));
 
     widget.update(aux);
+    aux.stats.record(|x| x.widgets_updated += 1);
+}
+
+/// Recursively dispatch the widget components and then the [`update_components`](Element::update_components) method.
+pub fn propagate_components<T: 'static>(widget: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>) {
+    widget.for_each_child_mut_rev(&mut |child| propagate_components(child, aux));
+
+    update_widget_components(widget, aux);
+    widget.update_components(aux);
+}
+
+fn update_widget_components<T: 'static>(widget: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>) {
+    let components = widget.common().with(|x| {
+        let idle = x.components.as_ref().map(|x| x.is_empty()).unwrap_or(true)
+            && x.removed_components.is_empty();
+        if idle {
+            None
+        } else {
+            Some((
+                x.components.take().unwrap(),
+                std::mem::take(&mut x.removed_components),
+            ))
+        }
+    });
+
+    let (mut components, removed) = match components {
+        Some(components) => components,
+        None => return,
+    };
+
+    for mut component in removed {
+        downcast_widget_component::<T>(&mut component).on_detach(widget, aux);
+    }
+
+    for entry in &mut components {
+        let component = downcast_widget_component::<T>(&mut entry.component);
+        if !entry.attached {
+            entry.attached = true;
+            component.on_attach(widget, aux);
+        }
+        component.update(widget, aux);
+    }
+
+    widget.common().with(|x| x.components = Some(components));
+}
+
+fn downcast_widget_component<T: 'static>(
+    component: &mut Box<dyn std::any::Any>,
+) -> &mut BoxedWidgetComponent<T> {
+    component
+        .downcast_mut::<BoxedWidgetComponent<T>>()
+        .expect("updated widget component with incorrect auxiliary")
 }
 
 /// Recursively propagate the `draw` method.
@@ -1046,15 +2593,15 @@ pub fn propagate_draw<T: 'static>(
     aux: &mut Aux<T>,
 ) {
     let v = widget.visible();
+    widget.common().read(|x| x.dirty.clear());
 
     if v != Visibility::NoSelf && v != Visibility::Invisible && v != Visibility::None {
         widget.draw(display, aux);
+        aux.stats.record(|x| x.widgets_drawn += 1);
     }
 
     if v != Visibility::NoChildren && v != Visibility::Invisible && v != Visibility::None {
-        for child in widget.children_mut() {
-            propagate_draw(child, display, aux);
-        }
+        widget.for_each_child_mut(&mut |child| propagate_draw(child, display, aux));
     }
 }
 
@@ -1077,12 +2624,18 @@ pub trait Element: AnyElement {
 
     #[inline]
     fn bounds(&self) -> gfx::Rect {
-        self.common().with(|x| x.absolute_rect())
+        self.common().read(|x| x.absolute_rect())
     }
 
     #[inline]
     fn update(&mut self, _aux: &mut Aux<Self::Aux>) {}
 
+    /// Dispatches the [components](Component) of the widget, typically through [`dispatch_components`](dispatch_components).
+    ///
+    /// This is invoked by the `"components"` system (see [`system`](system)), which runs before the `"update"` system.
+    #[inline]
+    fn update_components(&mut self, _aux: &mut Aux<Self::Aux>) {}
+
     #[inline]
     fn draw(&mut self, _display: &mut dyn gfx::GraphicsDisplay, _aux: &mut Aux<Self::Aux>) {}
 }
@@ -1090,7 +2643,7 @@ pub trait Element: AnyElement {
 impl<E: Element + ?Sized> Id for E {
     #[inline]
     fn id(&self) -> u64 {
-        self.common().with(|x| x.id())
+        self.common().read(|x| x.id())
     }
 }
 
@@ -1116,6 +2669,8 @@ pub trait AnyElement {
     fn as_any_box(self: Box<Self>) -> Box<dyn std::any::Any>;
     /// Returns the type ID of the element.
     fn type_id(&self) -> std::any::TypeId;
+    /// Returns the full type name of the element, as given by `std::any::type_name`.
+    fn type_name(&self) -> &'static str;
 }
 
 impl<E: Element + 'static> AnyElement for E {
@@ -1134,6 +2689,10 @@ impl<E: Element + 'static> AnyElement for E {
     fn type_id(&self) -> std::any::TypeId {
         std::any::TypeId::of::<E>()
     }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<E>()
+    }
 }
 
 /// Altered version of `reclutch::widget::WidgetChildren` incorporating `Element`.
@@ -1147,6 +2706,37 @@ pub trait WidgetChildren<T>: Element<Aux = T> + 'static {
     fn children_mut(&mut self) -> Vec<&mut dyn WidgetChildren<T>> {
         Vec::new()
     }
+
+    /// Invokes `f` with each immutable child, in the same order as [`children`](WidgetChildren::children).
+    ///
+    /// The default implementation goes through `children`; implement this (and the other `for_each_child` variants)
+    /// to traverse the children without allocating.
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for child in self.children() {
+            f(child);
+        }
+    }
+
+    /// Invokes `f` with each mutable child, in the same order as [`children_mut`](WidgetChildren::children_mut).
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for child in self.children_mut() {
+            f(child);
+        }
+    }
+
+    /// Same as [`for_each_child`](WidgetChildren::for_each_child), but in reverse order.
+    fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for child in self.children().into_iter().rev() {
+            f(child);
+        }
+    }
+
+    /// Same as [`for_each_child_mut`](WidgetChildren::for_each_child_mut), but in reverse order.
+    fn for_each_child_mut_rev(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for child in self.children_mut().into_iter().rev() {
+            f(child);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -1164,21 +2754,23 @@ fn visit_mut_impl<T: 'static, W: Element<Aux = T> + 'static>(
     visitor: &mut impl FnMut(&mut W),
     breakpoint: VisitorBreakpoint,
 ) -> bool {
-    for child in root.children_mut() {
+    let mut done = false;
+    root.for_each_child_mut(&mut |child| {
+        if done {
+            return;
+        }
         if let Some(x) = child.as_any_mut().downcast_mut::<W>() {
             visitor(x);
             if breakpoint == VisitorBreakpoint::FirstVisit {
-                return true;
+                done = true;
+                return;
             }
         }
-        if breakpoint == VisitorBreakpoint::EachVisit {
-            continue;
-        }
-        if visit_mut_impl(child, visitor, breakpoint) {
-            return true;
+        if breakpoint != VisitorBreakpoint::EachVisit {
+            done = visit_mut_impl(child, visitor, breakpoint);
         }
-    }
-    false
+    });
+    done
 }
 
 /// Mutable variant of [`visit`](visit).
@@ -1195,21 +2787,23 @@ fn visit_impl<T: 'static, W: Element<Aux = T> + 'static>(
     visitor: &mut impl FnMut(&W),
     breakpoint: VisitorBreakpoint,
 ) -> bool {
-    for child in root.children() {
+    let mut done = false;
+    root.for_each_child(&mut |child| {
+        if done {
+            return;
+        }
         if let Some(x) = child.as_any().downcast_ref::<W>() {
             visitor(x);
             if breakpoint == VisitorBreakpoint::FirstVisit {
-                return true;
+                done = true;
+                return;
             }
         }
-        if breakpoint == VisitorBreakpoint::EachVisit {
-            continue;
-        }
-        if visit_impl(child, visitor, breakpoint) {
-            return true;
+        if breakpoint != VisitorBreakpoint::EachVisit {
+            done = visit_impl(child, visitor, breakpoint);
         }
-    }
-    false
+    });
+    done
 }
 
 /// Visits every widget in a given mutable tree/branch (root) and will invoke the function if the widget
@@ -1223,6 +2817,8 @@ fn visit_impl<T: 'static, W: Element<Aux = T> + 'static>(
 /// ```
 ///
 /// The `breakpoint` parameter specifies when the visitor should break off or stop completely, if at all.
+///
+/// To find widgets by their name or tags as well, see [`query`](query).
 pub fn visit<T: 'static, W: Element<Aux = T> + 'static>(
     root: &dyn WidgetChildren<T>,
     mut visitor: impl FnMut(&W),
@@ -1250,6 +2846,35 @@ macro_rules! children {
         fn children_mut(&mut self) -> Vec<&mut dyn $crate::ui::WidgetChildren<$t>> {
             vec![$(&mut self.$child),*]
         }
+
+        fn for_each_child(&self, f: &mut dyn FnMut(&dyn $crate::ui::WidgetChildren<$t>)) {
+            $(f(&self.$child);)*
+        }
+
+        fn for_each_child_mut(
+            &mut self,
+            f: &mut dyn FnMut(&mut dyn $crate::ui::WidgetChildren<$t>),
+        ) {
+            $(f(&mut self.$child);)*
+        }
+
+        fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn $crate::ui::WidgetChildren<$t>)) {
+            let children: &[&dyn $crate::ui::WidgetChildren<$t>] = &[$(&self.$child),*];
+            for child in children.iter().rev() {
+                f(*child);
+            }
+        }
+
+        fn for_each_child_mut_rev(
+            &mut self,
+            f: &mut dyn FnMut(&mut dyn $crate::ui::WidgetChildren<$t>),
+        ) {
+            let children: &mut [&mut dyn $crate::ui::WidgetChildren<$t>] =
+                &mut [$(&mut self.$child),*];
+            for child in children.iter_mut().rev() {
+                f(&mut **child);
+            }
+        }
     };
 }
 
@@ -1285,11 +2910,21 @@ pub fn draw<T: 'static, W: WidgetChildren<T>>(
     aux: &mut Aux<T>,
     z_order: impl Into<Option<gfx::ZOrder>>,
 ) {
-    let mut cmds = obj.common().with(|x| x.command_group().0.take().unwrap());
+    let (id, rect, mut cmds) = obj
+        .common()
+        .with(|x| (x.id(), x.absolute_rect(), x.command_group().0.take().unwrap()));
+
+    // commands are in absolute coordinates, so a widget which moved (e.g. along with its parent) must repaint.
+    if aux.damage.record(id, rect, cmds.will_repaint()) {
+        cmds.repaint();
+    }
 
     cmds.push_with(
         display,
-        || draw_fn(obj, aux),
+        || {
+            aux.stats.record(|x| x.widgets_repainted += 1);
+            draw_fn(obj, aux)
+        },
         z_order.into().unwrap_or_default(),
         None,
         None,
@@ -1301,14 +2936,66 @@ pub fn draw<T: 'static, W: WidgetChildren<T>>(
 /// Propagates the repaint flag to children of a widget if it is set.
 pub fn propagate_repaint<T: 'static>(widget: &impl WidgetChildren<T>) {
     if widget.common().with(|x| x.command_group().will_repaint()) {
-        for child in widget.children() {
-            child.repaint();
+        widget.for_each_child(&mut |child| child.repaint());
+    }
+}
+
+/// Returns `None` if no widget within `widget` is under `point`, otherwise the cursor icon requested by the topmost
+/// widget under `point` or by its nearest ancestor which requests one (up to `widget`).
+fn cursor_icon_at<T: 'static>(
+    widget: &dyn WidgetChildren<T>,
+    point: gfx::Point,
+) -> Option<Option<CursorIcon>> {
+    let v = widget.visible();
+    if v == Visibility::None || v == Visibility::Invisible {
+        return None;
+    }
+
+    let icon = if v != Visibility::NoSelf {
+        widget.common().read(|x| {
+            if x.absolute_rect().contains(point) {
+                Some(x.cursor_icon())
+            } else {
+                None
+            }
+        })
+    } else {
+        None
+    };
+
+    if v != Visibility::NoChildren {
+        // later children are drawn on top, and the topmost child under the point hides the children beneath it.
+        let mut hit = None;
+        widget.for_each_child_rev(&mut |child| {
+            if hit.is_none() {
+                hit = cursor_icon_at(child, point);
+            }
+        });
+        if let Some(child_icon) = hit {
+            return Some(child_icon.or_else(|| icon.flatten()));
         }
     }
+
+    icon
+}
+
+/// Resolves the cursor icon requested by the topmost widget under `cursor` (or its nearest ancestor which requests one)
+/// and stores it in [`Aux::cursor_icon`](Aux::cursor_icon).
+///
+/// Returns `true` if the cursor icon changed.
+pub fn update_cursor_icon<T: 'static>(
+    root: &dyn WidgetChildren<T>,
+    aux: &mut Aux<T>,
+    cursor: gfx::Point,
+) -> bool {
+    let icon = cursor_icon_at(root, cursor).flatten().unwrap_or_default();
+    let changed = aux.cursor_icon != icon;
+    aux.cursor_icon = icon;
+    changed
 }
 
 /// Keyboard modifier keys state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct KeyModifiers {
     pub shift: bool,
     pub ctrl: bool,
@@ -1318,10 +3005,10 @@ pub struct KeyModifiers {
 
 pub fn propagate_visibility<T: 'static>(w: &mut dyn WidgetChildren<T>) {
     let v = w.visible();
-    for child in w.children_mut() {
+    w.for_each_child_mut(&mut |child| {
         child.set_visible(v);
         propagate_visibility(child);
-    }
+    });
 }
 
 /// Element convenience mixin with methods parallel to `Common`.
@@ -1335,7 +3022,7 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn rect(&self) -> gfx::Rect {
-        self.common().with(|x| x.rect())
+        self.common().read(|x| x.rect())
     }
 
     #[inline]
@@ -1345,7 +3032,7 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn size(&self) -> gfx::Size {
-        self.common().with(|x| x.size())
+        self.common().read(|x| x.size())
     }
 
     #[inline]
@@ -1355,7 +3042,7 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn position(&self) -> gfx::Point {
-        self.common().with(|x| x.position())
+        self.common().read(|x| x.position())
     }
 
     #[inline]
@@ -1365,12 +3052,12 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn absolute_position(&self) -> gfx::Point {
-        self.common().with(|x| x.absolute_position())
+        self.common().read(|x| x.absolute_position())
     }
 
     #[inline]
     fn absolute_rect(&self) -> gfx::Rect {
-        self.common().with(|x| x.absolute_rect())
+        self.common().read(|x| x.absolute_rect())
     }
 
     #[inline]
@@ -1380,7 +3067,7 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn visible(&self) -> Visibility {
-        self.common().with(|x| x.visible())
+        self.common().read(|x| x.visible())
     }
 
     #[inline]
@@ -1390,12 +3077,12 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn updates(&self) -> bool {
-        self.common().with(|x| x.updates())
+        self.common().read(|x| x.updates())
     }
 
     #[inline]
     fn parent(&self) -> Option<CommonRef> {
-        self.common().with(|x| x.parent())
+        self.common().read(|x| x.parent())
     }
 
     #[inline]
@@ -1404,7 +3091,7 @@ pub trait ElementMixin: Element {
     }
 
     fn emit<T: 'static, E: 'static>(&self, aux: &mut Aux<T>, event: E) {
-        self.common().with(|x| x.emit(aux, event));
+        self.common().read(|x| x.emit(aux, event));
     }
 
     #[inline]
@@ -1428,7 +3115,42 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn layout_mode(&self) -> LayoutMode {
-        self.common().with(|x| x.layout_mode())
+        self.common().read(|x| x.layout_mode())
+    }
+
+    #[inline]
+    fn set_cursor_icon(&self, icon: impl Into<Option<CursorIcon>>) {
+        self.common().with(|x| x.set_cursor_icon(icon));
+    }
+
+    #[inline]
+    fn cursor_icon(&self) -> Option<CursorIcon> {
+        self.common().read(|x| x.cursor_icon())
+    }
+
+    #[inline]
+    fn set_name(&self, name: impl Into<Option<String>>) {
+        self.common().with(|x| x.set_name(name));
+    }
+
+    #[inline]
+    fn name(&self) -> Option<String> {
+        self.common().read(|x| x.name().map(ToString::to_string))
+    }
+
+    #[inline]
+    fn add_tag(&self, tag: impl Into<String>) -> bool {
+        self.common().with(|x| x.add_tag(tag))
+    }
+
+    #[inline]
+    fn remove_tag(&self, tag: &str) -> bool {
+        self.common().with(|x| x.remove_tag(tag))
+    }
+
+    #[inline]
+    fn has_tag(&self, tag: &str) -> bool {
+        self.common().read(|x| x.has_tag(tag))
     }
 
     #[inline]
@@ -1438,8 +3160,63 @@ pub trait ElementMixin: Element {
 
     #[inline]
     fn is_marked_for_detach(&self) -> bool {
-        self.common().with(|x| x.is_marked_for_detach())
+        self.common().read(|x| x.is_marked_for_detach())
+    }
+
+    #[inline]
+    fn add_component<C: WidgetComponent<Aux = Self::Aux>>(
+        &self,
+        component: C,
+    ) -> Result<(), ComponentError> {
+        self.common().with(|x| x.add_component(component))
+    }
+
+    #[inline]
+    fn remove_component<C: WidgetComponent<Aux = Self::Aux>>(&self) -> Result<(), ComponentError> {
+        self.common().with(|x| x.remove_component::<C>())
+    }
+
+    #[inline]
+    fn has_component<C: WidgetComponent<Aux = Self::Aux>>(&self) -> bool {
+        self.common().read(|x| x.has_component::<C>())
     }
 }
 
 impl<E: Element + ?Sized> ElementMixin for E {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nests_reads() {
+        let common = CommonRef::new(None);
+        let (outer, inner) = common.read(|x| (x.id, common.read(|x| x.id)));
+        assert_eq!(outer, inner);
+    }
+
+    #[test]
+    fn fails_conflicting_access() {
+        let common = CommonRef::new(None);
+        assert_eq!(
+            common.read(|_| common.try_with(|_| ())),
+            Err(CommonAccessError::AlreadyBorrowed)
+        );
+        assert_eq!(
+            common.with(|_| common.try_read(|_| ())),
+            Err(CommonAccessError::AlreadyMutablyBorrowed)
+        );
+        assert_eq!(common.read(|_| common.try_read(|_| ())), Ok(()));
+    }
+
+    #[test]
+    fn reads_parent_while_it_is_read() {
+        let parent = CommonRef::new(None);
+        parent.with(|x| x.set_position(gfx::Point::new(10.0, 20.0)));
+        let child = CommonRef::new(parent.clone());
+        child.with(|x| x.set_position(gfx::Point::new(1.0, 2.0)));
+
+        let position = parent.read(|_| child.read(|x| x.absolute_position()));
+        assert_eq!(position, gfx::Point::new(11.0, 22.0));
+    }
+}
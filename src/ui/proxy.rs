@@ -0,0 +1,105 @@
+//! Thread-safe delivery of events to the UI queue.
+//!
+//! The UI queue is `Rc`-based and thus confined to the UI thread.
+//! An [`EventProxy`](EventProxy) (obtained through [`Aux::proxy`](super::Aux::proxy)) can be sent to any thread
+//! to queue events, which are then emitted on the UI queue by [`Aux::process_proxied_events`](super::Aux::process_proxied_events).
+
+use {
+    super::{Emitter, Id},
+    std::sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+};
+
+type EmitFn = Box<dyn FnOnce(&Emitter) + Send>;
+type Waker = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProxyError {
+    #[error("The UI receiving the event has been dropped")]
+    Disconnected,
+}
+
+/// Sending half of the proxy, which can be cloned and sent across threads.
+#[derive(Clone)]
+pub struct EventProxy {
+    sender: mpsc::Sender<EmitFn>,
+    waker: Option<Waker>,
+    wake_pending: Arc<AtomicBool>,
+}
+
+impl EventProxy {
+    /// Queues `event` to be emitted to `id` on the UI thread.
+    ///
+    /// Events are emitted in the order they were queued, across all the clones of a proxy.
+    ///
+    /// Widgets aren't `Send`, so on other threads `id` is typically a `u64` obtained from the widget beforehand.
+    pub fn emit<E: Send + 'static>(&self, id: &impl Id, event: E) -> Result<(), ProxyError> {
+        let id = id.id();
+        self.sender
+            .send(Box::new(move |emitter: &Emitter| emitter.emit(id, event)))
+            .map_err(|_| ProxyError::Disconnected)?;
+
+        if let Some(waker) = &self.waker {
+            // a single wake-up is enough to process every event queued before it.
+            if !self.wake_pending.swap(true, Ordering::AcqRel) {
+                waker();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Receiving half of the proxy, owned by [`Aux`](super::Aux).
+pub struct ProxyReceiver {
+    sender: mpsc::Sender<EmitFn>,
+    receiver: mpsc::Receiver<EmitFn>,
+    waker: Option<Waker>,
+    wake_pending: Arc<AtomicBool>,
+}
+
+impl ProxyReceiver {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        ProxyReceiver {
+            sender,
+            receiver,
+            waker: None,
+            wake_pending: Default::default(),
+        }
+    }
+
+    /// Changes the function invoked (from the sending thread) when events are queued, so that the UI thread can be woken.
+    ///
+    /// Only proxies created after this call will use the new waker.
+    pub fn set_waker(&mut self, waker: impl Fn() + Send + Sync + 'static) {
+        self.waker = Some(Arc::new(waker));
+    }
+
+    /// Creates a new proxy which queues events for this receiver.
+    pub fn proxy(&self) -> EventProxy {
+        EventProxy {
+            sender: self.sender.clone(),
+            waker: self.waker.clone(),
+            wake_pending: self.wake_pending.clone(),
+        }
+    }
+
+    /// Emits all the queued events through `emitter`, returning the number of events emitted.
+    pub fn process(&self, emitter: &Emitter) -> usize {
+        self.wake_pending.store(false, Ordering::Release);
+        self.receiver.try_iter().fold(0, |count, emit| {
+            emit(emitter);
+            count + 1
+        })
+    }
+}
+
+impl Default for ProxyReceiver {
+    #[inline]
+    fn default() -> Self {
+        ProxyReceiver::new()
+    }
+}
@@ -0,0 +1,505 @@
+//! Selector-based queries over widget trees.
+//!
+//! Selectors are a small subset of CSS selectors, matched against the type name, [name](super::Common::set_name) and
+//! [tags](super::Common::add_tag) of widgets:
+//!
+//! - `Button` matches widgets whose type is named `Button` (generic parameters and module paths are ignored).
+//! - `#save` matches widgets named `save`.
+//! - `.primary` matches widgets tagged `primary`.
+//! - `*` matches any widget.
+//! - `Button#save.primary` matches widgets which satisfy all of the above.
+//! - `Dialog TextBox` matches `TextBox` widgets anywhere within a `Dialog`.
+//! - `Dialog > TextBox` matches `TextBox` widgets which are direct children of a `Dialog`.
+//! - `Button, Label` matches widgets which match either selector.
+//!
+//! The root of a query is matched as well as its descendants, however ancestors of the root are never considered.
+//!
+//! ```ignore
+//! let selector = Selector::parse("Dialog > TextBox.required")?;
+//! select_each_mut(root, &selector, |text_box: &mut TextBox<T>| {
+//!     // ...
+//! });
+//! ```
+
+use super::{AnyElement, CommonRef, Element, WidgetChildren};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SelectorError {
+    #[error("The selector is empty")]
+    Empty,
+    #[error("Unexpected `{0}` at {1}")]
+    UnexpectedChar(char, usize),
+    #[error("Expected a name after `{0}` at {1}")]
+    MissingIdentifier(char, usize),
+    #[error("Expected a selector after `{0}` at {1}")]
+    MissingSelector(char, usize),
+}
+
+/// Part of a selector which matches a single widget; e.g. `Button#save.primary`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Compound {
+    type_name: Option<String>,
+    name: Option<String>,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+/// Sequence of compound selectors, where each is related to the next by a combinator; e.g. `Dialog > TextBox`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Complex {
+    /// The compound matching the widget itself comes last.
+    compounds: Vec<Compound>,
+    /// `combinators[i]` relates `compounds[i]` to `compounds[i + 1]`.
+    combinators: Vec<Combinator>,
+}
+
+/// Parsed selector. See the [module documentation](self) for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    groups: Vec<Complex>,
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Self, SelectorError> {
+        Parser {
+            chars: selector.char_indices().collect(),
+            pos: 0,
+        }
+        .parse()
+    }
+}
+
+impl std::str::FromStr for Selector {
+    type Err = SelectorError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Selector::parse(s)
+    }
+}
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    pos: usize,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, char)> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while let Some((_, c)) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.pos != start
+    }
+
+    fn ident(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some((_, c)) = self.peek() {
+            if !is_ident_char(c) {
+                break;
+            }
+            ident.push(c);
+            self.pos += 1;
+        }
+        ident
+    }
+
+    fn parse(mut self) -> Result<Selector, SelectorError> {
+        let mut groups = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() && groups.is_empty() {
+                return Err(SelectorError::Empty);
+            }
+
+            groups.push(self.complex()?);
+
+            match self.peek() {
+                None => return Ok(Selector { groups }),
+                Some((i, ',')) => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    if self.peek().is_none() {
+                        return Err(SelectorError::MissingSelector(',', i));
+                    }
+                }
+                Some((i, c)) => return Err(SelectorError::UnexpectedChar(c, i)),
+            }
+        }
+    }
+
+    fn complex(&mut self) -> Result<Complex, SelectorError> {
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = Vec::new();
+        loop {
+            let spaced = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some((_, ',')) => break,
+                Some((i, '>')) => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    match self.peek() {
+                        None | Some((_, ',')) => {
+                            return Err(SelectorError::MissingSelector('>', i))
+                        }
+                        _ => Combinator::Child,
+                    }
+                }
+                Some(_) if spaced => Combinator::Descendant,
+                Some((i, c)) => return Err(SelectorError::UnexpectedChar(c, i)),
+            };
+            combinators.push(combinator);
+            compounds.push(self.compound()?);
+        }
+
+        Ok(Complex {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn compound(&mut self) -> Result<Compound, SelectorError> {
+        let mut compound = Compound::default();
+        let mut empty = true;
+
+        match self.peek() {
+            Some((_, '*')) => {
+                self.pos += 1;
+                empty = false;
+            }
+            Some((_, c)) if is_ident_char(c) => {
+                compound.type_name = Some(self.ident());
+                empty = false;
+            }
+            _ => {}
+        }
+
+        while let Some((i, c)) = self.peek() {
+            if c != '#' && c != '.' {
+                break;
+            }
+            self.pos += 1;
+            let ident = self.ident();
+            if ident.is_empty() {
+                return Err(SelectorError::MissingIdentifier(c, i));
+            }
+            if c == '#' {
+                compound.name = Some(ident);
+            } else {
+                compound.tags.push(ident);
+            }
+            empty = false;
+        }
+
+        if empty {
+            Err(match self.peek() {
+                Some((i, c)) => SelectorError::UnexpectedChar(c, i),
+                None => SelectorError::Empty,
+            })
+        } else {
+            Ok(compound)
+        }
+    }
+}
+
+/// Returns the name of a type without its module path or generic parameters; e.g. `Button` for
+/// `otway::kit::button::Button<T>`.
+pub fn short_type_name(type_name: &str) -> &str {
+    let type_name = type_name.split('<').next().unwrap_or(type_name);
+    type_name.rsplit("::").next().unwrap_or(type_name)
+}
+
+/// Properties of a widget which selectors match against.
+struct Node {
+    type_name: &'static str,
+    name: Option<String>,
+    tags: Vec<String>,
+}
+
+impl Node {
+    fn of<T: 'static>(widget: &dyn WidgetChildren<T>) -> Self {
+        let (name, tags) = widget
+            .common()
+            .read(|x| (x.name().map(ToString::to_string), x.tags().to_vec()));
+        Node {
+            type_name: short_type_name(widget.type_name()),
+            name,
+            tags,
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, node: &Node) -> bool {
+        self.type_name
+            .as_ref()
+            .map(|x| x == node.type_name)
+            .unwrap_or(true)
+            && self
+                .name
+                .as_ref()
+                .map(|x| node.name.as_ref() == Some(x))
+                .unwrap_or(true)
+            && self.tags.iter().all(|x| node.tags.contains(x))
+    }
+}
+
+impl Complex {
+    /// Returns `true` if `compounds[..=last]` matches `node`, given its `ancestors` (outermost first).
+    fn matches_at(&self, last: usize, node: &Node, ancestors: &[Node]) -> bool {
+        if !self.compounds[last].matches(node) {
+            return false;
+        }
+        if last == 0 {
+            return true;
+        }
+
+        match self.combinators[last - 1] {
+            Combinator::Child => ancestors
+                .split_last()
+                .map(|(parent, rest)| self.matches_at(last - 1, parent, rest))
+                .unwrap_or(false),
+            Combinator::Descendant => (0..ancestors.len())
+                .rev()
+                .any(|i| self.matches_at(last - 1, &ancestors[i], &ancestors[..i])),
+        }
+    }
+}
+
+impl Selector {
+    fn matches(&self, node: &Node, ancestors: &[Node]) -> bool {
+        self.groups
+            .iter()
+            .any(|x| x.matches_at(x.compounds.len() - 1, node, ancestors))
+    }
+}
+
+fn walk<T: 'static>(
+    widget: &dyn WidgetChildren<T>,
+    selector: &Selector,
+    ancestors: &mut Vec<Node>,
+    f: &mut dyn FnMut(&dyn WidgetChildren<T>) -> bool,
+) -> bool {
+    let node = Node::of(widget);
+    if selector.matches(&node, ancestors) && !f(widget) {
+        return false;
+    }
+
+    ancestors.push(node);
+    let mut proceed = true;
+    widget.for_each_child(&mut |child| {
+        if proceed {
+            proceed = walk(child, selector, ancestors, f);
+        }
+    });
+    ancestors.pop();
+    proceed
+}
+
+fn walk_mut<T: 'static>(
+    widget: &mut dyn WidgetChildren<T>,
+    selector: &Selector,
+    ancestors: &mut Vec<Node>,
+    f: &mut dyn FnMut(&mut dyn WidgetChildren<T>),
+) {
+    let node = Node::of(widget);
+    if selector.matches(&node, ancestors) {
+        f(widget);
+    }
+
+    ancestors.push(node);
+    widget.for_each_child_mut(&mut |child| walk_mut(child, selector, ancestors, f));
+    ancestors.pop();
+}
+
+/// Returns the widgets within the tree of `root` which match `selector`, in pre-order.
+pub fn select<T: 'static>(root: &dyn WidgetChildren<T>, selector: &Selector) -> Vec<CommonRef> {
+    let mut matches = Vec::new();
+    walk(root, selector, &mut Vec::new(), &mut |widget| {
+        matches.push(widget.common().clone());
+        true
+    });
+    matches
+}
+
+/// Returns the first widget within the tree of `root` which matches `selector`, in pre-order.
+pub fn select_first<T: 'static>(
+    root: &dyn WidgetChildren<T>,
+    selector: &Selector,
+) -> Option<CommonRef> {
+    let mut found = None;
+    walk(root, selector, &mut Vec::new(), &mut |widget| {
+        found = Some(widget.common().clone());
+        false
+    });
+    found
+}
+
+/// Invokes `visitor` with each widget of type `W` within the tree of `root` which matches `selector`, in pre-order.
+///
+/// Matching widgets of other types are skipped.
+pub fn select_each<T: 'static, W: Element<Aux = T> + 'static>(
+    root: &dyn WidgetChildren<T>,
+    selector: &Selector,
+    mut visitor: impl FnMut(&W),
+) {
+    walk(root, selector, &mut Vec::new(), &mut |widget| {
+        if let Some(x) = widget.as_any().downcast_ref::<W>() {
+            visitor(x);
+        }
+        true
+    });
+}
+
+/// Mutable variant of [`select_each`](select_each).
+pub fn select_each_mut<T: 'static, W: Element<Aux = T> + 'static>(
+    root: &mut dyn WidgetChildren<T>,
+    selector: &Selector,
+    mut visitor: impl FnMut(&mut W),
+) {
+    walk_mut(root, selector, &mut Vec::new(), &mut |widget| {
+        if let Some(x) = widget.as_any_mut().downcast_mut::<W>() {
+            visitor(x);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(type_name: &'static str, name: Option<&str>, tags: &[&str]) -> Node {
+        Node {
+            type_name,
+            name: name.map(ToString::to_string),
+            tags: tags.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn matches(selector: &str, node: &Node, ancestors: &[Node]) -> bool {
+        Selector::parse(selector).unwrap().matches(node, ancestors)
+    }
+
+    #[test]
+    fn parses_names() {
+        let selector = Selector::parse("#save").unwrap();
+        assert_eq!(
+            selector.groups,
+            vec![Complex {
+                compounds: vec![Compound {
+                    type_name: None,
+                    name: Some("save".into()),
+                    tags: Vec::new(),
+                }],
+                combinators: Vec::new(),
+            }]
+        );
+
+        assert!(matches("#save", &node("Button", Some("save"), &[]), &[]));
+        assert!(!matches("#save", &node("Button", Some("open"), &[]), &[]));
+        assert!(!matches("#save", &node("Button", None, &[]), &[]));
+    }
+
+    #[test]
+    fn matches_types_and_tags() {
+        assert!(matches(
+            "Button.primary",
+            &node("Button", None, &["large", "primary"]),
+            &[]
+        ));
+        assert!(!matches("Button.primary", &node("Button", None, &[]), &[]));
+        assert!(!matches(
+            "Button.primary",
+            &node("Label", None, &["primary"]),
+            &[]
+        ));
+        assert!(matches("*", &node("Label", None, &[]), &[]));
+        assert_eq!(short_type_name("otway::kit::button::Button<T>"), "Button");
+    }
+
+    #[test]
+    fn matches_children() {
+        let text_box = node("TextBox", None, &[]);
+        assert!(matches(
+            "Dialog > TextBox",
+            &text_box,
+            &[node("Dialog", None, &[])]
+        ));
+        assert!(matches(
+            "Dialog>TextBox",
+            &text_box,
+            &[node("Dialog", None, &[])]
+        ));
+        assert!(!matches(
+            "Dialog > TextBox",
+            &text_box,
+            &[node("Dialog", None, &[]), node("Panel", None, &[])]
+        ));
+        assert!(!matches("Dialog > TextBox", &text_box, &[]));
+    }
+
+    #[test]
+    fn matches_descendants() {
+        let text_box = node("TextBox", None, &[]);
+        let ancestors = [node("Dialog", Some("login"), &[]), node("Panel", None, &[])];
+        assert!(matches("Dialog TextBox", &text_box, &ancestors));
+        assert!(matches("#login Panel > TextBox", &text_box, &ancestors));
+        assert!(!matches("Panel Dialog TextBox", &text_box, &ancestors));
+        assert!(!matches("Window TextBox", &text_box, &ancestors));
+    }
+
+    #[test]
+    fn matches_any_group() {
+        let selector = Selector::parse("Button, Label").unwrap();
+        assert_eq!(selector.groups.len(), 2);
+        assert!(selector.matches(&node("Button", None, &[]), &[]));
+        assert!(selector.matches(&node("Label", None, &[]), &[]));
+        assert!(!selector.matches(&node("TextBox", None, &[]), &[]));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(Selector::parse(""), Err(SelectorError::Empty));
+        assert_eq!(Selector::parse("   "), Err(SelectorError::Empty));
+        assert_eq!(
+            Selector::parse("Button$"),
+            Err(SelectorError::UnexpectedChar('$', 6))
+        );
+        assert_eq!(
+            Selector::parse(", Button"),
+            Err(SelectorError::UnexpectedChar(',', 0))
+        );
+        assert_eq!(
+            Selector::parse("#"),
+            Err(SelectorError::MissingIdentifier('#', 0))
+        );
+        assert_eq!(
+            Selector::parse("Button."),
+            Err(SelectorError::MissingIdentifier('.', 6))
+        );
+        assert_eq!(
+            Selector::parse("Dialog >"),
+            Err(SelectorError::MissingSelector('>', 7))
+        );
+        assert_eq!(
+            Selector::parse("Button,"),
+            Err(SelectorError::MissingSelector(',', 6))
+        );
+    }
+}
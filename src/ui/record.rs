@@ -0,0 +1,741 @@
+//! Recording and replaying of raw input sessions.
+//!
+//! While recording (see [`Aux::start_recording`](super::Aux::start_recording)), every [`InputEvent`](super::InputEvent)
+//! passed to [`Aux::dispatch_input`](super::Aux::dispatch_input) is recorded before any input filter sees it, along with
+//! resizes reported through [`Aux::record_resize`](super::Aux::record_resize). Entries are timestamped by the clock of the
+//! timers, so that a session recorded in the [headless driver](crate::headless) replays identically.
+//!
+//! Recordings are saved in a line-based text format, which can be streamed to a file as the session is recorded so that it
+//! survives a crash:
+//!
+//! ```text
+//! otway-recording 1
+//! 0.000000000 1 move 12 40
+//! 0.184021300 3 press left 12 40
+//! 0.261700400 4 release left 12 40
+//! 1.032981000 9 key-press 30 A - 0 "a"
+//! 1.033004100 9 text "a"
+//! 2.500000000 14 resize 800 600
+//! ```
+//!
+//! Each line holds the time since the recording started in seconds, the [frame](super::trace::EventTrace::frame), the kind of
+//! input and its fields. Key modifiers are written as a combination of `s` (shift), `c` (ctrl), `a` (alt) and `l` (logo),
+//! and absent values as `-`. Strings are quoted and escaped as in JSON, the same as in [snapshots](super::snapshot).
+//!
+//! [`InputReplayer`](InputReplayer) re-injects a recording into a running application in real time, whereas
+//! [`Headless::replay`](crate::headless::Headless::replay) replays it deterministically.
+
+use {
+    super::{
+        snapshot::json_string, InputEvent, KeyEvent, KeyInput, KeyModifiers, MouseButton,
+        PointerInput, PointerKind, PointerPhase, Preedit, ScrollInput,
+    },
+    reclutch::display as gfx,
+    std::{
+        io::{self, Write},
+        path::Path,
+        time::{Duration, Instant},
+    },
+};
+
+const HEADER: &str = "otway-recording";
+const VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error("{0}")]
+    IoError(#[from] io::Error),
+    #[error("Invalid recording at line {line}: {message}")]
+    ParseError { line: usize, message: String },
+    #[error("Unsupported recording version `{0}`")]
+    UnsupportedVersion(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedInput {
+    Input(InputEvent),
+    /// The UI was resized to a given logical size.
+    Resize(gfx::Size),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEntry {
+    /// Time since the recording started.
+    pub time: Duration,
+    /// The [frame](super::trace::EventTrace::frame) in which the input was recorded.
+    pub frame: u64,
+    pub input: RecordedInput,
+}
+
+/// A recorded input session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub entries: Vec<RecordedEntry>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Serializes the recording to the text format.
+    pub fn to_text(&self) -> String {
+        let mut out = header();
+        for entry in &self.entries {
+            out.push_str(&write_entry(entry));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parses a recording from the text format.
+    pub fn parse(text: &str) -> Result<Self, RecordingError> {
+        let mut lines = text.lines().enumerate();
+        match lines
+            .next()
+            .map(|(_, x)| x.split_whitespace().collect::<Vec<_>>())
+        {
+            Some(header) if header.len() == 2 && header[0] == HEADER => {
+                if header[1] != VERSION.to_string() {
+                    return Err(RecordingError::UnsupportedVersion(header[1].to_string()));
+                }
+            }
+            _ => {
+                return Err(RecordingError::ParseError {
+                    line: 1,
+                    message: format!("expected `{} {}`", HEADER, VERSION),
+                })
+            }
+        }
+
+        let mut entries = Vec::new();
+        for (i, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(
+                parse_entry(line).map_err(|message| RecordingError::ParseError {
+                    line: i + 1,
+                    message,
+                })?,
+            );
+        }
+        Ok(Recording { entries })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Recording::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the time of the last entry.
+    pub fn duration(&self) -> Duration {
+        self.entries.last().map(|x| x.time).unwrap_or_default()
+    }
+}
+
+/// Records input into a [`Recording`](Recording), optionally streaming it to a writer.
+///
+/// This is normally driven through [`Aux`](super::Aux) rather than directly.
+#[derive(Default)]
+pub struct InputRecorder {
+    start: Option<Instant>,
+    recording: Recording,
+    writer: Option<Box<dyn Write>>,
+    error: Option<io::Error>,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// Starts a new recording at `now`, discarding any recording in progress.
+    pub fn start(&mut self, now: Instant) {
+        self.start = Some(now);
+        self.recording = Recording::new();
+        self.writer = None;
+        self.error = None;
+    }
+
+    /// Starts a new recording at `now` which is also written to `writer`, entry by entry.
+    pub fn start_to(&mut self, now: Instant, writer: impl Write + 'static) -> io::Result<()> {
+        self.start(now);
+        let mut writer: Box<dyn Write> = Box::new(writer);
+        writer.write_all(header().as_bytes())?;
+        writer.flush()?;
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    /// Stops recording and returns the recording, if one was in progress.
+    ///
+    /// If writing an entry to the writer failed, the writer was dropped and the error is returned here; the returned
+    /// recording is still complete.
+    pub fn stop(&mut self) -> Option<(Recording, Option<io::Error>)> {
+        self.start.take()?;
+        self.writer = None;
+        Some((std::mem::take(&mut self.recording), self.error.take()))
+    }
+
+    pub fn record(&mut self, now: Instant, frame: u64, input: RecordedInput) {
+        let start = match self.start {
+            Some(start) => start,
+            None => return,
+        };
+
+        let entry = RecordedEntry {
+            time: now.saturating_duration_since(start),
+            frame,
+            input,
+        };
+
+        if let Some(writer) = &mut self.writer {
+            // flushed per entry so that the file is complete up to a crash.
+            let result = writeln!(writer, "{}", write_entry(&entry)).and_then(|_| writer.flush());
+            if let Err(error) = result {
+                self.writer = None;
+                self.error = Some(error);
+            }
+        }
+
+        self.recording.entries.push(entry);
+    }
+}
+
+/// Re-injects a recording in real time.
+///
+/// The replay starts at the first call to [`due`](InputReplayer::due).
+/// Entries are replayed in order and none are skipped, however the UI may process them in different frames than those they
+/// were recorded in.
+pub struct InputReplayer {
+    recording: Recording,
+    next: usize,
+    start: Option<Instant>,
+}
+
+impl InputReplayer {
+    pub fn new(recording: Recording) -> Self {
+        InputReplayer {
+            recording,
+            next: 0,
+            start: None,
+        }
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.next == self.recording.entries.len()
+    }
+
+    /// Returns the entries which are due at `now` and haven't been replayed yet.
+    pub fn due(&mut self, now: Instant) -> Vec<RecordedEntry> {
+        let start = *self.start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start);
+        let due = self.recording.entries[self.next..]
+            .iter()
+            .take_while(|x| x.time <= elapsed)
+            .count();
+        let entries = self.recording.entries[self.next..self.next + due].to_vec();
+        self.next += due;
+        entries
+    }
+
+    /// Returns when the next entry is due, if the replay has started and isn't finished.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        Some(self.start? + self.recording.entries.get(self.next)?.time)
+    }
+}
+
+fn header() -> String {
+    format!("{} {}\n", HEADER, VERSION)
+}
+
+fn write_option<T: ToString>(x: Option<T>) -> String {
+    x.map(|x| x.to_string()).unwrap_or_else(|| "-".to_string())
+}
+
+fn write_button(button: MouseButton) -> String {
+    match button {
+        MouseButton::Left => "left".to_string(),
+        MouseButton::Middle => "middle".to_string(),
+        MouseButton::Right => "right".to_string(),
+        MouseButton::Other(x) => x.to_string(),
+    }
+}
+
+fn write_modifiers(modifiers: KeyModifiers) -> String {
+    let mut out = String::new();
+    for &(flag, c) in &[
+        (modifiers.shift, 's'),
+        (modifiers.ctrl, 'c'),
+        (modifiers.alt, 'a'),
+        (modifiers.logo, 'l'),
+    ] {
+        if flag {
+            out.push(c);
+        }
+    }
+    if out.is_empty() {
+        out.push('-');
+    }
+    out
+}
+
+fn write_key(kind: &str, key: &KeyEvent) -> String {
+    format!(
+        "{} {} {} {} {} {}",
+        kind,
+        key.scancode,
+        write_option(key.key.map(KeyInput::name)),
+        write_modifiers(key.modifiers),
+        key.repeat as u8,
+        json_string(&key.text)
+    )
+}
+
+fn write_entry(entry: &RecordedEntry) -> String {
+    let input = match &entry.input {
+        RecordedInput::Input(InputEvent::MouseMove(pos)) => format!("move {} {}", pos.x, pos.y),
+        RecordedInput::Input(InputEvent::MousePress(button, pos)) => {
+            format!("press {} {} {}", write_button(*button), pos.x, pos.y)
+        }
+        RecordedInput::Input(InputEvent::MouseRelease(button, pos)) => {
+            format!("release {} {} {}", write_button(*button), pos.x, pos.y)
+        }
+        RecordedInput::Input(InputEvent::KeyPress(key)) => write_key("key-press", key),
+        RecordedInput::Input(InputEvent::KeyRelease(key)) => write_key("key-release", key),
+        RecordedInput::Input(InputEvent::Scroll(scroll)) => format!(
+            "scroll {} {} {} {} {} {}",
+            scroll.lines.x,
+            scroll.lines.y,
+            scroll.pixels.x,
+            scroll.pixels.y,
+            scroll.position.x,
+            scroll.position.y
+        ),
+        RecordedInput::Input(InputEvent::Pointer(pointer)) => format!(
+            "pointer {} {} {} {} {} {} {} {}",
+            pointer.id,
+            match pointer.kind {
+                PointerKind::Mouse => "mouse",
+                PointerKind::Touch => "touch",
+                PointerKind::Pen => "pen",
+            },
+            match pointer.phase {
+                PointerPhase::Began => "began",
+                PointerPhase::Moved => "moved",
+                PointerPhase::Ended => "ended",
+                PointerPhase::Cancelled => "cancelled",
+            },
+            pointer.position.x,
+            pointer.position.y,
+            write_option(pointer.pressure),
+            write_option(pointer.tilt),
+            pointer.primary as u8
+        ),
+        RecordedInput::Input(InputEvent::Text(c)) => {
+            format!("text {}", json_string(&c.to_string()))
+        }
+        RecordedInput::Input(InputEvent::Preedit(preedit)) => format!(
+            "preedit {} {}",
+            json_string(&preedit.text),
+            preedit
+                .cursor
+                .map(|(start, end)| format!("{} {}", start, end))
+                .unwrap_or_else(|| "-".to_string())
+        ),
+        RecordedInput::Input(InputEvent::Commit(text)) => format!("commit {}", json_string(text)),
+        RecordedInput::Resize(size) => format!("resize {} {}", size.width, size.height),
+    };
+
+    format!(
+        "{}.{:09} {} {}",
+        entry.time.as_secs(),
+        entry.time.subsec_nanos(),
+        entry.frame,
+        input
+    )
+}
+
+/// Splits a line into whitespace-separated fields, where quoted strings are unescaped into a single field.
+fn split_fields(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut field = String::new();
+            loop {
+                match chars.next().ok_or("unterminated string")? {
+                    '"' => break,
+                    '\\' => match chars.next().ok_or("unterminated string")? {
+                        '"' => field.push('"'),
+                        '\\' => field.push('\\'),
+                        'n' => field.push('\n'),
+                        'r' => field.push('\r'),
+                        't' => field.push('\t'),
+                        'u' => {
+                            let code: String = chars.by_ref().take(4).collect();
+                            let c = u32::from_str_radix(&code, 16)
+                                .ok()
+                                .filter(|_| code.len() == 4)
+                                .and_then(std::char::from_u32)
+                                .ok_or_else(|| format!("invalid escape `\\u{}`", code))?;
+                            field.push(c);
+                        }
+                        c => return Err(format!("invalid escape `\\{}`", c)),
+                    },
+                    c => field.push(c),
+                }
+            }
+            fields.push(field);
+        } else {
+            let mut field = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+            fields.push(field);
+        }
+    }
+    Ok(fields)
+}
+
+struct Fields {
+    fields: std::vec::IntoIter<String>,
+}
+
+impl Fields {
+    fn next(&mut self) -> Result<String, String> {
+        self.fields
+            .next()
+            .ok_or_else(|| "missing field".to_string())
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        let field = self.next()?;
+        field
+            .parse()
+            .map_err(|_| format!("invalid value `{}`", field))
+    }
+
+    fn option<T: std::str::FromStr>(&mut self) -> Result<Option<T>, String> {
+        let field = self.next()?;
+        if field == "-" {
+            Ok(None)
+        } else {
+            field
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("invalid value `{}`", field))
+        }
+    }
+
+    fn flag(&mut self) -> Result<bool, String> {
+        match self.next()?.as_str() {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            x => Err(format!("invalid flag `{}`", x)),
+        }
+    }
+
+    fn point(&mut self) -> Result<gfx::Point, String> {
+        Ok(gfx::Point::new(self.parse()?, self.parse()?))
+    }
+
+    fn vector(&mut self) -> Result<gfx::Vector, String> {
+        Ok(gfx::Vector::new(self.parse()?, self.parse()?))
+    }
+
+    fn button(&mut self) -> Result<MouseButton, String> {
+        let field = self.next()?;
+        Ok(match field.as_str() {
+            "left" => MouseButton::Left,
+            "middle" => MouseButton::Middle,
+            "right" => MouseButton::Right,
+            x => MouseButton::Other(
+                x.parse()
+                    .map_err(|_| format!("invalid mouse button `{}`", x))?,
+            ),
+        })
+    }
+
+    fn modifiers(&mut self) -> Result<KeyModifiers, String> {
+        let field = self.next()?;
+        let mut modifiers = KeyModifiers::default();
+        if field != "-" {
+            for c in field.chars() {
+                match c {
+                    's' => modifiers.shift = true,
+                    'c' => modifiers.ctrl = true,
+                    'a' => modifiers.alt = true,
+                    'l' => modifiers.logo = true,
+                    c => return Err(format!("invalid modifier `{}`", c)),
+                }
+            }
+        }
+        Ok(modifiers)
+    }
+
+    fn key(&mut self) -> Result<KeyEvent, String> {
+        let scancode = self.parse()?;
+        let key = self.next()?;
+        let key = if key == "-" {
+            None
+        } else {
+            Some(KeyInput::from_name(&key).ok_or_else(|| format!("invalid key `{}`", key))?)
+        };
+        Ok(KeyEvent {
+            scancode,
+            key,
+            modifiers: self.modifiers()?,
+            repeat: self.flag()?,
+            text: self.next()?,
+        })
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        match self.fields.next() {
+            Some(x) => Err(format!("unexpected field `{}`", x)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_time(field: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid time `{}`", field);
+    let mut parts = field.splitn(2, '.');
+    let secs: u64 = parts.next().unwrap().parse().map_err(|_| invalid())?;
+    let nanos = match parts.next() {
+        Some(x) if !x.is_empty() && x.len() <= 9 => {
+            let nanos: u32 = x.parse().map_err(|_| invalid())?;
+            nanos * 10u32.pow(9 - x.len() as u32)
+        }
+        None => 0,
+        _ => return Err(invalid()),
+    };
+    Ok(Duration::new(secs, nanos))
+}
+
+fn parse_entry(line: &str) -> Result<RecordedEntry, String> {
+    let mut fields = Fields {
+        fields: split_fields(line)?.into_iter(),
+    };
+    let time = parse_time(&fields.next()?)?;
+    let frame = fields.parse()?;
+
+    let input = match fields.next()?.as_str() {
+        "move" => RecordedInput::Input(InputEvent::MouseMove(fields.point()?)),
+        "press" => RecordedInput::Input(InputEvent::MousePress(fields.button()?, fields.point()?)),
+        "release" => {
+            RecordedInput::Input(InputEvent::MouseRelease(fields.button()?, fields.point()?))
+        }
+        "key-press" => RecordedInput::Input(InputEvent::KeyPress(fields.key()?)),
+        "key-release" => RecordedInput::Input(InputEvent::KeyRelease(fields.key()?)),
+        "scroll" => RecordedInput::Input(InputEvent::Scroll(ScrollInput {
+            lines: fields.vector()?,
+            pixels: fields.vector()?,
+            position: fields.point()?,
+        })),
+        "pointer" => RecordedInput::Input(InputEvent::Pointer(PointerInput {
+            id: fields.parse()?,
+            kind: match fields.next()?.as_str() {
+                "mouse" => PointerKind::Mouse,
+                "touch" => PointerKind::Touch,
+                "pen" => PointerKind::Pen,
+                x => return Err(format!("invalid pointer kind `{}`", x)),
+            },
+            phase: match fields.next()?.as_str() {
+                "began" => PointerPhase::Began,
+                "moved" => PointerPhase::Moved,
+                "ended" => PointerPhase::Ended,
+                "cancelled" => PointerPhase::Cancelled,
+                x => return Err(format!("invalid pointer phase `{}`", x)),
+            },
+            position: fields.point()?,
+            pressure: fields.option()?,
+            tilt: fields.option()?,
+            primary: fields.flag()?,
+        })),
+        "text" => {
+            let text = fields.next()?;
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => RecordedInput::Input(InputEvent::Text(c)),
+                _ => return Err(format!("expected a single character, found `{}`", text)),
+            }
+        }
+        "preedit" => {
+            let text = fields.next()?;
+            let cursor = match fields.option::<usize>()? {
+                Some(start) => Some((start, fields.parse()?)),
+                None => None,
+            };
+            RecordedInput::Input(InputEvent::Preedit(Preedit { text, cursor }))
+        }
+        "commit" => RecordedInput::Input(InputEvent::Commit(fields.next()?)),
+        "resize" => RecordedInput::Resize(gfx::Size::new(fields.parse()?, fields.parse()?)),
+        x => return Err(format!("unknown input `{}`", x)),
+    };
+    fields.finish()?;
+
+    Ok(RecordedEntry { time, frame, input })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: Duration, frame: u64, input: InputEvent) -> RecordedEntry {
+        RecordedEntry {
+            time,
+            frame,
+            input: RecordedInput::Input(input),
+        }
+    }
+
+    fn parse_error(text: &str) -> (usize, String) {
+        match Recording::parse(text) {
+            Err(RecordingError::ParseError { line, message }) => (line, message),
+            x => panic!("expected a parse error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn round_trips_every_input() {
+        let point = gfx::Point::new(12.5, -3.0);
+        let key = KeyEvent {
+            scancode: 30,
+            key: Some(KeyInput::A),
+            modifiers: KeyModifiers {
+                shift: true,
+                ctrl: false,
+                alt: true,
+                logo: true,
+            },
+            repeat: true,
+            text: "a \"quoted\" \\ line\n\ttab\r\u{1}é".to_string(),
+        };
+        let pointer = PointerInput {
+            id: 7,
+            kind: PointerKind::Pen,
+            phase: PointerPhase::Moved,
+            position: point,
+            pressure: Some(0.25),
+            tilt: None,
+            primary: true,
+        };
+        let inputs = vec![
+            InputEvent::MouseMove(point),
+            InputEvent::MousePress(MouseButton::Left, point),
+            InputEvent::MouseRelease(MouseButton::Other(4), point),
+            InputEvent::MousePress(MouseButton::Middle, point),
+            InputEvent::MouseRelease(MouseButton::Right, point),
+            InputEvent::KeyPress(key),
+            InputEvent::KeyRelease(KeyEvent::new(None)),
+            InputEvent::Scroll(ScrollInput {
+                lines: gfx::Vector::new(0.0, -1.0),
+                pixels: gfx::Vector::new(1.5, 2.0),
+                position: point,
+            }),
+            InputEvent::Pointer(pointer),
+            InputEvent::Pointer(PointerInput {
+                kind: PointerKind::Touch,
+                phase: PointerPhase::Cancelled,
+                pressure: None,
+                tilt: Some(0.5),
+                primary: false,
+                ..pointer
+            }),
+            InputEvent::Text('"'),
+            InputEvent::Text(' '),
+            InputEvent::Preedit(Preedit {
+                text: "にほん".to_string(),
+                cursor: Some((3, 6)),
+            }),
+            InputEvent::Preedit(Preedit::new("")),
+            InputEvent::Commit("日本 \"語\"".to_string()),
+        ];
+
+        let mut recording = Recording {
+            entries: inputs
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| entry(Duration::new(i as u64, i as u32 * 1001), i as u64, x))
+                .collect(),
+        };
+        recording.entries.push(RecordedEntry {
+            time: Duration::from_millis(20_500),
+            frame: 40,
+            input: RecordedInput::Resize(gfx::Size::new(800.0, 600.5)),
+        });
+
+        let text = recording.to_text();
+        assert_eq!(Recording::parse(&text).unwrap(), recording, "{}", text);
+    }
+
+    #[test]
+    fn parses_fractional_seconds() {
+        assert_eq!(parse_time("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_time("1.5").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_time("0.000000001").unwrap(), Duration::new(0, 1));
+        assert_eq!(parse_time("3.250").unwrap(), Duration::from_millis(3250));
+        assert!(parse_time("1.").is_err());
+        assert!(parse_time("1.0000000001").is_err());
+        assert!(parse_time("-1").is_err());
+        assert!(parse_time("x").is_err());
+    }
+
+    #[test]
+    fn splits_quoted_fields() {
+        assert_eq!(
+            split_fields(r#"text "a b" - "\u0001\"\\""#).unwrap(),
+            vec!["text", "a b", "-", "\u{1}\"\\"]
+        );
+        assert!(split_fields(r#""\x""#).is_err());
+        assert!(split_fields(r#""\u12""#).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert_eq!(parse_error("recording 1\n").0, 1);
+        assert_eq!(parse_error("").0, 1);
+        assert!(matches!(
+            Recording::parse("otway-recording 2\n"),
+            Err(RecordingError::UnsupportedVersion(x)) if x == "2"
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_entries() {
+        let (line, message) = parse_error("otway-recording 1\n0.5 1 move 1 2\n1 2 text \"a\n");
+        assert_eq!(line, 3);
+        assert_eq!(message, "unterminated string");
+
+        let (line, message) = parse_error("otway-recording 1\n\n1 2 resize 800 600 1\n");
+        assert_eq!(line, 3);
+        assert_eq!(message, "unexpected field `1`");
+
+        let (line, message) = parse_error("otway-recording 1\n1 2 move 1\n");
+        assert_eq!(line, 2);
+        assert_eq!(message, "missing field");
+
+        let (_, message) = parse_error("otway-recording 1\n1 2 jump 1 2\n");
+        assert_eq!(message, "unknown input `jump`");
+    }
+}
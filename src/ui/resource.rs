@@ -0,0 +1,102 @@
+//! Type-map of global resources, stored in [`Aux`](super::Aux) alongside the application data.
+//!
+//! Resources let libraries keep their own global state (e.g. a clipboard or a localizer) without touching the data type
+//! of the application. There is at most one resource of each type.
+//!
+//! Event handlers reach resources through their `Aux` parameter; `Read<Aux<T>>` is enough to read a resource,
+//! whereas `Write<Aux<T>>` is needed to mutate one. Alternatively, handlers can take a resource as a parameter of its own
+//! (e.g. `Write<R>`) if the listener is dispatched through [`dispatch_with_resource`](super::dispatch_with_resource).
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ResourceError {
+    #[error("No resource of type `{0}` has been inserted")]
+    MissingResource(&'static str),
+    #[error("The resource of type `{0}` is being accessed through `with_resource`")]
+    ResourceInUse(&'static str),
+}
+
+/// Storage of resources, keyed by type.
+#[derive(Default)]
+pub struct Resources {
+    /// A resource is `None` while it's taken out by `Aux::with_resource`.
+    resources: HashMap<TypeId, Option<Box<dyn Any>>>,
+}
+
+impl Resources {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Inserts a resource, returning the resource of the same type which it replaced.
+    pub fn insert<R: 'static>(&mut self, resource: R) -> Option<R> {
+        self.resources
+            .insert(TypeId::of::<R>(), Some(Box::new(resource)))
+            .flatten()
+            .map(|x| *x.downcast::<R>().unwrap())
+    }
+
+    /// Removes a resource and returns it.
+    pub fn remove<R: 'static>(&mut self) -> Result<R, ResourceError> {
+        let resource = self
+            .resources
+            .get_mut(&TypeId::of::<R>())
+            .ok_or_else(|| ResourceError::MissingResource(type_name::<R>()))?
+            .take()
+            .ok_or_else(|| ResourceError::ResourceInUse(type_name::<R>()))?;
+        self.resources.remove(&TypeId::of::<R>());
+        Ok(*resource.downcast::<R>().unwrap())
+    }
+
+    pub fn get<R: 'static>(&self) -> Result<&R, ResourceError> {
+        Ok(self
+            .resources
+            .get(&TypeId::of::<R>())
+            .ok_or_else(|| ResourceError::MissingResource(type_name::<R>()))?
+            .as_ref()
+            .ok_or_else(|| ResourceError::ResourceInUse(type_name::<R>()))?
+            .downcast_ref::<R>()
+            .unwrap())
+    }
+
+    pub fn get_mut<R: 'static>(&mut self) -> Result<&mut R, ResourceError> {
+        Ok(self
+            .resources
+            .get_mut(&TypeId::of::<R>())
+            .ok_or_else(|| ResourceError::MissingResource(type_name::<R>()))?
+            .as_mut()
+            .ok_or_else(|| ResourceError::ResourceInUse(type_name::<R>()))?
+            .downcast_mut::<R>()
+            .unwrap())
+    }
+
+    /// Returns `true` if a resource of type `R` has been inserted (even if it is in use).
+    #[inline]
+    pub fn contains<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    /// Takes a resource out of the storage, so that it can be mutated alongside the rest of the storage.
+    pub(crate) fn take<R: 'static>(&mut self) -> Result<Box<R>, ResourceError> {
+        Ok(self
+            .resources
+            .get_mut(&TypeId::of::<R>())
+            .ok_or_else(|| ResourceError::MissingResource(type_name::<R>()))?
+            .take()
+            .ok_or_else(|| ResourceError::ResourceInUse(type_name::<R>()))?
+            .downcast::<R>()
+            .unwrap())
+    }
+
+    /// Returns a resource taken out by [`take`](Resources::take), unless it has been replaced or removed in the meantime.
+    pub(crate) fn put_back<R: 'static>(&mut self, resource: Box<R>) {
+        if let Some(slot @ None) = self.resources.get_mut(&TypeId::of::<R>()) {
+            *slot = Some(resource);
+        }
+    }
+}
@@ -0,0 +1,518 @@
+//! Snapshots of widget trees, for inspecting layouts and for snapshot tests.
+//!
+//! A [`WidgetSnapshot`](WidgetSnapshot) captures the state of every widget in a tree which is relevant to layout.
+//! It can be serialized to JSON for external tools, or to a stable text format in which widget IDs are replaced by their
+//! pre-order index (`@0` being the root) and types are shortened, so that snapshots don't change between runs.
+//!
+//! ```ignore
+//! let snapshot = WidgetSnapshot::capture(&root, &aux);
+//! let expected = std::fs::read_to_string("tests/snapshots/dialog.txt")?;
+//! if let Some(diff) = snapshot::diff_text(&expected, &snapshot.to_text()) {
+//!     panic!("snapshot mismatch:\n{}", diff);
+//! }
+//! ```
+
+use {
+    super::{
+        layout, query::short_type_name, AnyElement, Aux, Element, LayoutMode, Visibility,
+        WidgetChildren,
+    },
+    reclutch::display as gfx,
+    std::{collections::HashMap, fmt::Write},
+};
+
+/// Item within a [`LayoutSnapshot`](LayoutSnapshot).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemSnapshot {
+    Widget { id: u64, rect: gfx::Rect },
+    Layout(LayoutSnapshot),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutSnapshot {
+    pub type_name: String,
+    pub rect: gfx::Rect,
+    pub items: Vec<ItemSnapshot>,
+}
+
+impl LayoutSnapshot {
+    fn capture(node: &layout::DynamicNode) -> Self {
+        LayoutSnapshot {
+            type_name: node.type_name().to_string(),
+            rect: node.rect(),
+            items: node
+                .items()
+                .into_iter()
+                .map(|item| match item {
+                    layout::Item::Widget(common) => ItemSnapshot::Widget {
+                        id: common.read(|x| x.id()),
+                        rect: item.rect(),
+                    },
+                    layout::Item::Layout(node) => {
+                        ItemSnapshot::Layout(LayoutSnapshot::capture(node))
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetSnapshot {
+    pub type_name: String,
+    pub id: u64,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    pub rect: gfx::Rect,
+    pub absolute_rect: gfx::Rect,
+    pub visibility: Visibility,
+    pub layout_mode: LayoutMode,
+    pub layout: Option<LayoutSnapshot>,
+    pub focused: bool,
+    /// Type names of the [widget components](super::WidgetComponent).
+    pub components: Vec<String>,
+    pub children: Vec<WidgetSnapshot>,
+}
+
+impl WidgetSnapshot {
+    /// Captures the tree of `root`.
+    ///
+    /// The components of a widget which is being updated are omitted.
+    pub fn capture<T: 'static>(root: &dyn WidgetChildren<T>, aux: &Aux<T>) -> Self {
+        let mut snapshot = root.common().read(|x| WidgetSnapshot {
+            type_name: root.type_name().to_string(),
+            id: x.id(),
+            name: x.name().map(ToString::to_string),
+            tags: x.tags().to_vec(),
+            rect: x.rect(),
+            absolute_rect: x.absolute_rect(),
+            visibility: x.visible(),
+            layout_mode: x.layout_mode(),
+            layout: x.layout().map(LayoutSnapshot::capture),
+            focused: aux.has_focus(root.common()),
+            components: x
+                .component_type_names()
+                .unwrap_or_default()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            children: Vec::new(),
+        });
+        root.for_each_child(&mut |child| {
+            snapshot.children.push(WidgetSnapshot::capture(child, aux))
+        });
+        snapshot
+    }
+
+    /// Returns the snapshot of the first widget named `name` within this tree, in pre-order.
+    pub fn find(&self, name: &str) -> Option<&WidgetSnapshot> {
+        if self.name.as_deref() == Some(name) {
+            Some(self)
+        } else {
+            self.children.iter().find_map(|x| x.find(name))
+        }
+    }
+
+    /// Serializes the tree to pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    /// Serializes the tree to the stable text format.
+    pub fn to_text(&self) -> String {
+        let mut ids = HashMap::new();
+        self.number(&mut ids);
+        let mut out = String::new();
+        self.write_text(&mut out, 0, &ids);
+        out
+    }
+
+    /// Compares this tree to `new` through their text format, returning the differing lines if they aren't equal.
+    #[inline]
+    pub fn diff(&self, new: &WidgetSnapshot) -> Option<String> {
+        diff_text(&self.to_text(), &new.to_text())
+    }
+
+    /// Assigns pre-order indices to widget IDs.
+    fn number(&self, ids: &mut HashMap<u64, usize>) {
+        let index = ids.len();
+        ids.insert(self.id, index);
+        for child in &self.children {
+            child.number(ids);
+        }
+    }
+
+    fn write_text(&self, out: &mut String, depth: usize, ids: &HashMap<u64, usize>) {
+        let indent = "  ".repeat(depth);
+        write!(
+            out,
+            "{}{} @{}",
+            indent,
+            short_type_name(&self.type_name),
+            ids[&self.id]
+        )
+        .unwrap();
+        if let Some(name) = &self.name {
+            write!(out, " #{}", name).unwrap();
+        }
+        for tag in &self.tags {
+            write!(out, " .{}", tag).unwrap();
+        }
+        write!(
+            out,
+            " {} abs {} {:?} {:?}",
+            fmt_rect(self.rect),
+            fmt_rect(self.absolute_rect),
+            self.visibility,
+            self.layout_mode
+        )
+        .unwrap();
+        if self.focused {
+            out.push_str(" focused");
+        }
+        out.push('\n');
+
+        if !self.components.is_empty() {
+            let components: Vec<_> = self.components.iter().map(|x| short_type_name(x)).collect();
+            writeln!(out, "{}  components: {}", indent, components.join(", ")).unwrap();
+        }
+        if let Some(layout) = &self.layout {
+            layout.write_text(out, depth + 1, ids);
+        }
+        for child in &self.children {
+            child.write_text(out, depth + 1, ids);
+        }
+    }
+
+    fn write_json(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+        out.push_str("{\n");
+        writeln!(
+            out,
+            "{}\"type_name\": {},",
+            indent,
+            json_string(&self.type_name)
+        )
+        .unwrap();
+        writeln!(out, "{}\"id\": {},", indent, self.id).unwrap();
+        writeln!(
+            out,
+            "{}\"name\": {},",
+            indent,
+            self.name
+                .as_deref()
+                .map(json_string)
+                .unwrap_or_else(|| "null".to_string())
+        )
+        .unwrap();
+        let tags: Vec<_> = self.tags.iter().map(|x| json_string(x)).collect();
+        writeln!(out, "{}\"tags\": [{}],", indent, tags.join(", ")).unwrap();
+        writeln!(out, "{}\"rect\": {},", indent, json_rect(self.rect)).unwrap();
+        writeln!(
+            out,
+            "{}\"absolute_rect\": {},",
+            indent,
+            json_rect(self.absolute_rect)
+        )
+        .unwrap();
+        writeln!(out, "{}\"visibility\": \"{:?}\",", indent, self.visibility).unwrap();
+        writeln!(
+            out,
+            "{}\"layout_mode\": \"{:?}\",",
+            indent, self.layout_mode
+        )
+        .unwrap();
+        write!(out, "{}\"layout\": ", indent).unwrap();
+        match &self.layout {
+            Some(layout) => layout.write_json(out, depth + 1),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\n");
+        writeln!(out, "{}\"focused\": {},", indent, self.focused).unwrap();
+        let components: Vec<_> = self.components.iter().map(|x| json_string(x)).collect();
+        writeln!(
+            out,
+            "{}\"components\": [{}],",
+            indent,
+            components.join(", ")
+        )
+        .unwrap();
+        write!(out, "{}\"children\": [", indent).unwrap();
+        for (i, child) in self.children.iter().enumerate() {
+            out.push_str(if i == 0 { "\n" } else { ",\n" });
+            out.push_str(&"  ".repeat(depth + 2));
+            child.write_json(out, depth + 2);
+        }
+        if !self.children.is_empty() {
+            write!(out, "\n{}", indent).unwrap();
+        }
+        write!(out, "]\n{}}}", "  ".repeat(depth)).unwrap();
+    }
+}
+
+impl LayoutSnapshot {
+    fn write_text(&self, out: &mut String, depth: usize, ids: &HashMap<u64, usize>) {
+        let indent = "  ".repeat(depth);
+        writeln!(
+            out,
+            "{}layout {} {}",
+            indent,
+            short_type_name(&self.type_name),
+            fmt_rect(self.rect)
+        )
+        .unwrap();
+        for item in &self.items {
+            match item {
+                ItemSnapshot::Widget { id, rect } => {
+                    // widgets which aren't within the tree (e.g. ones wrongly kept alive by the layout) are marked with `?`.
+                    let id = ids
+                        .get(id)
+                        .map(|x| format!("@{}", x))
+                        .unwrap_or_else(|| "?".to_string());
+                    writeln!(out, "{}  item {} {}", indent, id, fmt_rect(*rect)).unwrap();
+                }
+                ItemSnapshot::Layout(layout) => layout.write_text(out, depth + 1, ids),
+            }
+        }
+    }
+
+    fn write_json(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+        out.push_str("{\n");
+        writeln!(
+            out,
+            "{}\"type_name\": {},",
+            indent,
+            json_string(&self.type_name)
+        )
+        .unwrap();
+        writeln!(out, "{}\"rect\": {},", indent, json_rect(self.rect)).unwrap();
+        write!(out, "{}\"items\": [", indent).unwrap();
+        for (i, item) in self.items.iter().enumerate() {
+            out.push_str(if i == 0 { "\n" } else { ",\n" });
+            out.push_str(&"  ".repeat(depth + 2));
+            match item {
+                ItemSnapshot::Widget { id, rect } => write!(
+                    out,
+                    "{{\"widget\": {}, \"rect\": {}}}",
+                    id,
+                    json_rect(*rect)
+                )
+                .unwrap(),
+                ItemSnapshot::Layout(layout) => layout.write_json(out, depth + 2),
+            }
+        }
+        if !self.items.is_empty() {
+            write!(out, "\n{}", indent).unwrap();
+        }
+        write!(out, "]\n{}}}", "  ".repeat(depth)).unwrap();
+    }
+}
+
+pub(crate) fn fmt_rect(rect: gfx::Rect) -> String {
+    format!(
+        "({}, {}, {}x{})",
+        rect.origin.x, rect.origin.y, rect.size.width, rect.size.height
+    )
+}
+
+fn json_rect(rect: gfx::Rect) -> String {
+    format!(
+        "{{\"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}}}",
+        json_number(rect.origin.x),
+        json_number(rect.origin.y),
+        json_number(rect.size.width),
+        json_number(rect.size.height)
+    )
+}
+
+/// JSON has no representation of non-finite numbers.
+fn json_number(x: f32) -> String {
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Quotes and escapes a string as in JSON; also used by the text format of [recordings](super::record).
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Line-based diff of two snapshots in the text format.
+///
+/// Returns `None` if they're equal, otherwise every line of both, prefixed by `-` if it was only in `old`, `+` if
+/// it was only in `new`, or a space if it was in both.
+///
+/// Lines common to the start and end of both are skipped before diffing the rest in linear space
+/// (with Hirschberg's algorithm), so that large snapshots can be compared.
+pub fn diff_text(old: &str, new: &str) -> Option<String> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    if old == new {
+        return None;
+    }
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut out = String::new();
+    for line in &old[..prefix] {
+        writeln!(out, "  {}", line).unwrap();
+    }
+    diff_lines(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+        &mut out,
+    );
+    for line in &old[old.len() - suffix..] {
+        writeln!(out, "  {}", line).unwrap();
+    }
+    Some(out)
+}
+
+/// Writes a shortest edit script from `old` to `new`, splitting `old` in half around the point where the longest
+/// common subsequence crosses it.
+fn diff_lines(old: &[&str], new: &[&str], out: &mut String) {
+    match old {
+        [] => {
+            for line in new {
+                writeln!(out, "+ {}", line).unwrap();
+            }
+        }
+        _ if new.is_empty() => {
+            for line in old {
+                writeln!(out, "- {}", line).unwrap();
+            }
+        }
+        [line] => match new.iter().position(|x| x == line) {
+            Some(k) => {
+                diff_lines(&[], &new[..k], out);
+                writeln!(out, "  {}", line).unwrap();
+                diff_lines(&[], &new[k + 1..], out);
+            }
+            None => {
+                diff_lines(old, &[], out);
+                diff_lines(&[], new, out);
+            }
+        },
+        _ => {
+            let mid = old.len() / 2;
+            let forward = lcs_lengths(old[..mid].iter(), new.iter());
+            let backward = lcs_lengths(old[mid..].iter().rev(), new.iter().rev());
+            // forward[k] + backward[new.len() - k] is the length of the longest common subsequence which splits
+            // `new` at `k`.
+            let k = (0..=new.len())
+                .max_by_key(|&k| (forward[k] + backward[new.len() - k], std::cmp::Reverse(k)))
+                .unwrap();
+            diff_lines(&old[..mid], &new[..k], out);
+            diff_lines(&old[mid..], &new[k..], out);
+        }
+    }
+}
+
+/// Returns the length of the longest common subsequence of `old` and each prefix of `new`, by length of the prefix.
+fn lcs_lengths<'a, 'b>(
+    old: impl Iterator<Item = &'a &'b str>,
+    new: impl Iterator<Item = &'a &'b str> + Clone,
+) -> Vec<usize>
+where
+    'b: 'a,
+{
+    let mut row = vec![0; new.clone().count() + 1];
+    for a in old {
+        let mut diagonal = 0;
+        for (j, b) in new.clone().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == b {
+                diagonal + 1
+            } else {
+                above.max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(json_string("Button"), "\"Button\"");
+        assert_eq!(
+            json_string("a\"b\\c\nd\re\tf\u{1}é"),
+            "\"a\\\"b\\\\c\\nd\\re\\tf\\u0001é\""
+        );
+    }
+
+    #[test]
+    fn diffs_equal_text() {
+        assert_eq!(diff_text("a\nb\n", "a\nb"), None);
+        assert_eq!(diff_text("", ""), None);
+    }
+
+    #[test]
+    fn diffs_insertions() {
+        assert_eq!(
+            diff_text("a\nb\nc", "a\nx\nb\nc\ny").unwrap(),
+            "  a\n+ x\n  b\n  c\n+ y\n"
+        );
+        assert_eq!(diff_text("", "a").unwrap(), "+ a\n");
+    }
+
+    #[test]
+    fn diffs_deletions() {
+        assert_eq!(
+            diff_text("x\na\nb\ny\nc", "a\nb\nc").unwrap(),
+            "- x\n  a\n  b\n- y\n  c\n"
+        );
+        assert_eq!(diff_text("a", "").unwrap(), "- a\n");
+    }
+
+    #[test]
+    fn diffs_replacements() {
+        assert_eq!(
+            diff_text("a\nb\nc\nd", "a\nx\nc\ny").unwrap(),
+            "  a\n- b\n+ x\n  c\n- d\n+ y\n"
+        );
+    }
+
+    #[test]
+    fn diffs_large_text() {
+        let old: Vec<_> = (0..10_000).map(|x| x.to_string()).collect();
+        let mut new = old.clone();
+        new[5_000] = "changed".to_string();
+        new.remove(7_500);
+
+        let diff = diff_text(&old.join("\n"), &new.join("\n")).unwrap();
+        let changed: Vec<_> = diff.lines().filter(|x| !x.starts_with(' ')).collect();
+        assert_eq!(changed, vec!["- 5000", "+ changed", "- 7500"]);
+        assert_eq!(diff.lines().count(), 10_001);
+    }
+}
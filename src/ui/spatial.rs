@@ -0,0 +1,488 @@
+//! Spatial index of widgets, used to route pointer input to the widgets under the pointer.
+//!
+//! Widgets are registered in the index of [`Aux`](super::Aux) (typically by [`kit::InteractionState`](crate::kit::InteractionState)).
+//! Changing the rectangle or visibility of a widget, or detaching it, records its ID in the [`GeometryChanges`] of its tree.
+//! On the next query, the index updates the cells of only the changed widgets and the registered widgets beneath them.
+//! Hence, a pointer event costs a lookup in a grid rather than a bounds check in every interactive widget.
+
+use {
+    super::{CommonRef, WeakCommonRef},
+    reclutch::display as gfx,
+    std::{
+        cell::RefCell,
+        collections::{HashMap, HashSet},
+        rc::{Rc, Weak},
+    },
+};
+
+/// IDs of the widgets whose geometry changed, shared by the widgets of a tree.
+///
+/// The changes are forwarded to each spatial index which registered a widget of the tree;
+/// if there is none, they're discarded.
+#[derive(Default)]
+pub(crate) struct GeometryChanges {
+    subscribers: RefCell<Vec<Weak<RefCell<HashSet<u64>>>>>,
+}
+
+impl GeometryChanges {
+    /// Records that the geometry of the widget `id` changed.
+    pub(crate) fn mark(&self, id: u64) {
+        self.subscribers.borrow_mut().retain(|x| match x.upgrade() {
+            Some(changed) => {
+                changed.borrow_mut().insert(id);
+                true
+            }
+            None => false,
+        });
+    }
+
+    fn subscribe(&self, changed: &Rc<RefCell<HashSet<u64>>>) {
+        let mut subscribers = self.subscribers.borrow_mut();
+        if !subscribers
+            .iter()
+            .any(|x| x.as_ptr() == Rc::as_ptr(changed))
+        {
+            subscribers.push(Rc::downgrade(changed));
+        }
+    }
+}
+
+/// Widgets covering more grid cells than this are checked on every query instead.
+const MAX_CELLS: i64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placement {
+    None,
+    Cells((i32, i32), (i32, i32)),
+    Oversized,
+}
+
+struct Entry {
+    widget: WeakCommonRef,
+    /// Number of times the widget has been inserted and not yet removed.
+    registrations: usize,
+    rect: gfx::Rect,
+    placement: Placement,
+    ancestors: Vec<u64>,
+}
+
+/// Grid of the absolute rectangles of registered widgets.
+pub struct SpatialIndex {
+    cell_size: f32,
+    entries: HashMap<u64, Entry>,
+    /// Registered widgets beneath each widget, whose absolute rectangles move along with it.
+    descendants: HashMap<u64, HashSet<u64>>,
+    cells: HashMap<(i32, i32), Vec<u64>>,
+    oversized: Vec<u64>,
+    /// Widgets whose geometry changed since the last query, including ones registered since.
+    changed: Rc<RefCell<HashSet<u64>>>,
+    /// Widgets which contained each pointer at its last routed position (`None` is the mouse).
+    last_targets: HashMap<Option<u64>, Vec<u64>>,
+    /// Widgets which contained each pointer when it was pressed, until it is released.
+    captures: HashMap<Option<u64>, Vec<u64>>,
+}
+
+impl SpatialIndex {
+    /// Creates an empty index whose grid cells are `cell_size` by `cell_size`.
+    pub fn new(cell_size: f32) -> Self {
+        SpatialIndex {
+            cell_size,
+            entries: HashMap::new(),
+            descendants: HashMap::new(),
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+            changed: Default::default(),
+            last_targets: HashMap::new(),
+            captures: HashMap::new(),
+        }
+    }
+
+    /// Registers a widget; it is removed once dropped or detached.
+    ///
+    /// Registrations are counted, since several components of a widget (e.g. a button's interaction state and a
+    /// [`Draggable`](crate::kit::Draggable)) may each register it. Each should [`remove`](SpatialIndex::remove) the widget
+    /// once it no longer needs input, and the widget is only unregistered once all of them have.
+    pub fn insert(&mut self, common: &CommonRef) {
+        let id = common.read(|x| x.id());
+        self.entries
+            .entry(id)
+            .or_insert_with(|| Entry {
+                widget: common.downgrade(),
+                registrations: 0,
+                rect: gfx::Rect::default(),
+                placement: Placement::None,
+                ancestors: Vec::new(),
+            })
+            .registrations += 1;
+        self.changed.borrow_mut().insert(id);
+    }
+
+    /// Removes a registration of a widget, unregistering it if no other registration remains.
+    ///
+    /// Returns `false` if the widget wasn't registered.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let entry = match self.entries.get_mut(&id) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        entry.registrations -= 1;
+        if entry.registrations == 0 {
+            let entry = self.entries.remove(&id).unwrap();
+            self.unplace(id, entry.placement);
+            self.forget_ancestors(id, &entry.ancestors);
+        }
+        true
+    }
+
+    #[inline]
+    pub fn contains(&self, id: u64) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /// Returns a registered widget, if it hasn't been dropped.
+    pub fn get(&self, id: u64) -> Option<CommonRef> {
+        self.entries.get(&id)?.widget.upgrade()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the IDs of the registered widgets whose absolute rectangle contains `point`, in no particular order.
+    pub fn query(&mut self, point: gfx::Point) -> Vec<u64> {
+        self.refresh();
+
+        let cell = self.cell(point);
+        self.cells
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .chain(&self.oversized)
+            .copied()
+            .filter(|id| self.entries[id].rect.contains(point))
+            .collect()
+    }
+
+    /// Returns the IDs of the widgets which pointer input at `point` should be delivered to.
+    ///
+    /// These are the widgets containing `point`, along with the widgets which contained the previous position of `pointer`
+    /// (so that they can tell the pointer left).
+    /// Between being `pressed` and `released`, the pointer is also captured by the widgets which contained it when pressed,
+    /// so that they can follow it beyond their bounds (e.g. for dragging or gestures).
+    /// Once a touch or stylus is released, its previous position is forgotten.
+    pub(crate) fn route(
+        &mut self,
+        pointer: Option<u64>,
+        point: gfx::Point,
+        pressed: bool,
+        released: bool,
+    ) -> Vec<u64> {
+        let targets = self.query(point);
+        if pressed {
+            self.captures.insert(pointer, targets.clone());
+        }
+        let captured = if released {
+            self.captures.remove(&pointer)
+        } else {
+            self.captures.get(&pointer).cloned()
+        };
+
+        let previous = if released && pointer.is_some() {
+            self.last_targets.remove(&pointer)
+        } else {
+            self.last_targets.insert(pointer, targets.clone())
+        };
+
+        let mut routed = targets;
+        for id in previous
+            .into_iter()
+            .flatten()
+            .chain(captured.into_iter().flatten())
+        {
+            if !routed.contains(&id) {
+                routed.push(id);
+            }
+        }
+        routed
+    }
+
+    fn cell(&self, point: gfx::Point) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cells(&self, rect: gfx::Rect) -> Placement {
+        if rect.is_empty() {
+            return Placement::None;
+        }
+
+        let min = self.cell(rect.origin);
+        let max = self.cell(rect.max());
+        let count = (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1);
+        if count > MAX_CELLS {
+            Placement::Oversized
+        } else {
+            Placement::Cells(min, max)
+        }
+    }
+
+    /// Updates the widgets whose geometry changed, along with the registered widgets beneath them.
+    fn refresh(&mut self) {
+        let changed = std::mem::take(&mut *self.changed.borrow_mut());
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut update = HashSet::new();
+        for id in changed {
+            if self.entries.contains_key(&id) {
+                update.insert(id);
+            }
+            if let Some(descendants) = self.descendants.get(&id) {
+                update.extend(descendants.iter().copied());
+            }
+        }
+
+        for id in update {
+            self.update(id);
+        }
+    }
+
+    fn update(&mut self, id: u64) {
+        let (widget, placement, old_ancestors) = match self.entries.get_mut(&id) {
+            Some(entry) => (
+                entry.widget.upgrade(),
+                entry.placement,
+                std::mem::take(&mut entry.ancestors),
+            ),
+            None => return,
+        };
+        self.unplace(id, placement);
+        self.forget_ancestors(id, &old_ancestors);
+
+        let widget = match widget {
+            Some(widget) => Rc::clone(widget.get_rc()),
+            None => {
+                self.entries.remove(&id);
+                return;
+            }
+        };
+
+        // walk up the tree, to be notified when an ancestor moves and to drop the widget once detached.
+        let mut ancestors = Vec::new();
+        let mut attached = !widget.borrow().should_detach;
+        widget.borrow().geometry.subscribe(&self.changed);
+        let mut parent = widget.borrow().parent.clone();
+        while let Some(weak) = parent {
+            match weak.upgrade() {
+                Some(common) => {
+                    let common = common.borrow();
+                    ancestors.push(common.id);
+                    attached &= !common.should_detach;
+                    common.geometry.subscribe(&self.changed);
+                    parent = common.parent.clone();
+                }
+                None => {
+                    attached = false;
+                    break;
+                }
+            }
+        }
+
+        if !attached {
+            self.entries.remove(&id);
+            return;
+        }
+
+        for &ancestor in &ancestors {
+            self.descendants.entry(ancestor).or_default().insert(id);
+        }
+
+        let rect = widget.borrow().absolute_rect();
+        let placement = self.cells(rect);
+        match placement {
+            Placement::None => {}
+            Placement::Cells(min, max) => {
+                for y in min.1..=max.1 {
+                    for x in min.0..=max.0 {
+                        self.cells.entry((x, y)).or_default().push(id);
+                    }
+                }
+            }
+            Placement::Oversized => self.oversized.push(id),
+        }
+
+        let entry = self.entries.get_mut(&id).unwrap();
+        entry.rect = rect;
+        entry.placement = placement;
+        entry.ancestors = ancestors;
+    }
+
+    fn unplace(&mut self, id: u64, placement: Placement) {
+        match placement {
+            Placement::None => {}
+            Placement::Cells(min, max) => {
+                for y in min.1..=max.1 {
+                    for x in min.0..=max.0 {
+                        if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                            cell.retain(|&x| x != id);
+                            if cell.is_empty() {
+                                self.cells.remove(&(x, y));
+                            }
+                        }
+                    }
+                }
+            }
+            Placement::Oversized => self.oversized.retain(|&x| x != id),
+        }
+    }
+
+    fn forget_ancestors(&mut self, id: u64, ancestors: &[u64]) {
+        for ancestor in ancestors {
+            if let Some(descendants) = self.descendants.get_mut(ancestor) {
+                descendants.remove(&id);
+                if descendants.is_empty() {
+                    self.descendants.remove(ancestor);
+                }
+            }
+        }
+    }
+}
+
+impl Default for SpatialIndex {
+    #[inline]
+    fn default() -> Self {
+        SpatialIndex::new(64.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_moved_ancestors() {
+        let parent = CommonRef::new(None);
+        let child = CommonRef::new(parent.clone());
+        child.with(|x| {
+            x.set_rect(gfx::Rect::new(
+                gfx::Point::new(10.0, 10.0),
+                gfx::Size::new(20.0, 20.0),
+            ))
+        });
+
+        let mut index = SpatialIndex::new(16.0);
+        index.insert(&child);
+        let id = child.read(|x| x.id());
+        assert_eq!(index.query(gfx::Point::new(15.0, 15.0)), vec![id]);
+
+        parent.with(|x| x.set_position(gfx::Point::new(100.0, 0.0)));
+        assert!(index.query(gfx::Point::new(15.0, 15.0)).is_empty());
+        assert_eq!(index.query(gfx::Point::new(115.0, 15.0)), vec![id]);
+
+        parent.with(|x| x.mark_for_detach());
+        assert!(index.query(gfx::Point::new(115.0, 15.0)).is_empty());
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn counts_registrations() {
+        let widget = CommonRef::new(None);
+        widget.with(|x| {
+            x.set_rect(gfx::Rect::new(
+                gfx::Point::new(0.0, 0.0),
+                gfx::Size::new(10.0, 10.0),
+            ))
+        });
+        let id = widget.read(|x| x.id());
+
+        let mut index = SpatialIndex::new(16.0);
+        index.insert(&widget);
+        index.insert(&widget);
+        assert!(index.remove(id));
+        assert_eq!(index.query(gfx::Point::new(5.0, 5.0)), vec![id]);
+        assert!(index.remove(id));
+        assert!(index.query(gfx::Point::new(5.0, 5.0)).is_empty());
+        assert!(!index.remove(id));
+    }
+
+    fn widget(rect: gfx::Rect) -> (CommonRef, u64) {
+        let widget = CommonRef::new(None);
+        widget.with(|x| x.set_rect(rect));
+        let id = widget.read(|x| x.id());
+        (widget, id)
+    }
+
+    #[test]
+    fn captures_pointer_between_press_and_release() {
+        let (widget, id) = widget(gfx::Rect::new(
+            gfx::Point::new(0.0, 0.0),
+            gfx::Size::new(10.0, 10.0),
+        ));
+        let mut index = SpatialIndex::new(16.0);
+        index.insert(&widget);
+
+        let inside = gfx::Point::new(5.0, 5.0);
+        let outside = gfx::Point::new(50.0, 50.0);
+        assert!(index.route(None, outside, false, false).is_empty());
+        assert_eq!(index.route(None, inside, true, false), vec![id]);
+        // the first move outside is routed to the widget regardless, so that it can tell the pointer left.
+        assert_eq!(index.route(None, outside, false, false), vec![id]);
+        assert_eq!(
+            index.route(None, gfx::Point::new(60.0, 60.0), false, false),
+            vec![id],
+            "the press should capture the pointer"
+        );
+        assert_eq!(index.route(None, outside, false, true), vec![id]);
+        assert!(
+            index
+                .route(None, gfx::Point::new(60.0, 60.0), false, false)
+                .is_empty(),
+            "the release should end the capture"
+        );
+
+        // touches are captured separately from the mouse, and forgotten once released.
+        let touch = Some(1);
+        assert_eq!(index.route(touch, inside, true, false), vec![id]);
+        assert!(index.route(None, outside, false, false).is_empty());
+        assert_eq!(index.route(touch, outside, false, true), vec![id]);
+        assert!(index.route(touch, outside, false, false).is_empty());
+    }
+
+    #[test]
+    fn checks_oversized_widgets_on_every_query() {
+        let (widget, id) = widget(gfx::Rect::new(
+            gfx::Point::new(0.0, 0.0),
+            gfx::Size::new(100.0, 100.0),
+        ));
+        // 100 by 100 cells, far more than `MAX_CELLS`.
+        let mut index = SpatialIndex::new(1.0);
+        index.insert(&widget);
+        assert_eq!(index.query(gfx::Point::new(99.0, 99.0)), vec![id]);
+        assert_eq!(index.entries[&id].placement, Placement::Oversized);
+        assert!(index.cells.is_empty());
+        assert!(index.query(gfx::Point::new(150.0, 50.0)).is_empty());
+
+        widget.with(|x| x.set_position(gfx::Point::new(100.0, 0.0)));
+        assert_eq!(index.query(gfx::Point::new(150.0, 50.0)), vec![id]);
+        assert!(index.query(gfx::Point::new(50.0, 50.0)).is_empty());
+
+        // shrinking the widget places it in the grid.
+        widget.with(|x| x.set_size(gfx::Size::new(2.0, 2.0)));
+        assert_eq!(index.query(gfx::Point::new(101.0, 1.0)), vec![id]);
+        assert!(index.oversized.is_empty());
+        assert_eq!(index.cells.len(), 9);
+
+        assert!(index.remove(id));
+        assert!(index.query(gfx::Point::new(101.0, 1.0)).is_empty());
+        assert!(index.cells.is_empty() && index.oversized.is_empty());
+    }
+}
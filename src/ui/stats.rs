@@ -0,0 +1,44 @@
+//! Counters of the work done by the UI, for profiling and debugging tools.
+//!
+//! The counters of a UI are kept in [`Aux::stats`](super::Aux::stats), and accumulate until they're
+//! [taken](Stats::take), which is typically done once per frame.
+
+use std::cell::Cell;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Widgets whose `update` was propagated to.
+    pub widgets_updated: usize,
+    /// Widgets whose `draw` was propagated to.
+    pub widgets_drawn: usize,
+    /// Widgets which regenerated their display commands when drawn.
+    pub widgets_repainted: usize,
+    /// Events emitted on the global queue through an [`Emitter`](super::Emitter).
+    pub events_emitted: usize,
+    /// Raw input events dispatched through [`Aux::dispatch_input`](super::Aux::dispatch_input).
+    pub input_events: usize,
+}
+
+/// Accumulated counters of a UI.
+#[derive(Debug, Default)]
+pub struct Stats(Cell<FrameStats>);
+
+impl Stats {
+    pub(crate) fn record(&self, f: impl FnOnce(&mut FrameStats)) {
+        let mut stats = self.0.get();
+        f(&mut stats);
+        self.0.set(stats);
+    }
+
+    /// Returns the counters accumulated so far, without resetting them.
+    #[inline]
+    pub fn current(&self) -> FrameStats {
+        self.0.get()
+    }
+
+    /// Returns the counters accumulated since the last call, and resets them.
+    #[inline]
+    pub fn take(&self) -> FrameStats {
+        self.0.take()
+    }
+}
@@ -0,0 +1,369 @@
+//! Systems which run over the entire widget tree every frame.
+//!
+//! Systems are registered by name in the [`Systems`](Systems) of [`Aux`](super::Aux), along with constraints on which
+//! systems they run before or after. [`run_systems`](run_systems) then runs them in order, once per frame.
+//!
+//! By default, the `"components"` system dispatches the components of every widget (e.g. the interaction state which
+//! turns routed input into presses), then the `"update"` system propagates an update through the tree and the `"layout"`
+//! system updates invalidated layouts. Any of these can be replaced, and any other behaviour which spans many widgets
+//! (e.g. focus) can be implemented as a system, typically finding the widgets it applies to by their
+//! [components](super::WidgetComponent) through [`query`](query).
+
+use super::{layout, propagate_components, propagate_update, Aux, WidgetChildren, WidgetComponent};
+
+/// Logic which runs over the entire widget tree.
+pub trait System<T: 'static>: 'static {
+    fn run(&mut self, root: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>);
+}
+
+impl<T: 'static, F: FnMut(&mut dyn WidgetChildren<T>, &mut Aux<T>) + 'static> System<T> for F {
+    #[inline]
+    fn run(&mut self, root: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>) {
+        self(root, aux)
+    }
+}
+
+/// Dispatches the components of the widgets within the tree. Registered as `"components"`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ComponentSystem;
+
+impl<T: 'static> System<T> for ComponentSystem {
+    #[inline]
+    fn run(&mut self, root: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>) {
+        propagate_components(root, aux);
+    }
+}
+
+/// Propagates an update through the tree. Registered as `"update"`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdateSystem;
+
+impl<T: 'static> System<T> for UpdateSystem {
+    #[inline]
+    fn run(&mut self, root: &mut dyn WidgetChildren<T>, aux: &mut Aux<T>) {
+        propagate_update(root, aux);
+    }
+}
+
+/// Updates the invalidated layouts within the tree. Registered as `"layout"`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LayoutSystem;
+
+impl<T: 'static> System<T> for LayoutSystem {
+    #[inline]
+    fn run(&mut self, root: &mut dyn WidgetChildren<T>, _aux: &mut Aux<T>) {
+        layout::update_dirty_layouts(root);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SystemError {
+    #[error("Systems are being run")]
+    RunInProgress,
+    #[error("A system named `{0}` already exists")]
+    DuplicateSystem(&'static str),
+    #[error("No system is named `{0}`")]
+    MissingSystem(&'static str),
+    #[error("The ordering constraints of `{0}` form a cycle")]
+    Cycle(&'static str),
+}
+
+/// Constraints on when a system runs, relative to other systems.
+///
+/// Constraints which name systems that aren't registered are ignored.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SystemOrder {
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+}
+
+impl SystemOrder {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The system runs before the system named `name`.
+    #[inline]
+    pub fn before(mut self, name: &'static str) -> Self {
+        self.before.push(name);
+        self
+    }
+
+    /// The system runs after the system named `name`.
+    #[inline]
+    pub fn after(mut self, name: &'static str) -> Self {
+        self.after.push(name);
+        self
+    }
+}
+
+struct SystemEntry<T: 'static> {
+    name: &'static str,
+    order: SystemOrder,
+    system: Box<dyn System<T>>,
+}
+
+/// Registry of named systems.
+///
+/// Systems run in the order they were added, unless this would violate their ordering constraints.
+pub struct Systems<T: 'static> {
+    systems: Option<Vec<SystemEntry<T>>>,
+}
+
+impl<T: 'static> Systems<T> {
+    /// Creates a registry with the `"components"`, `"update"` and `"layout"` systems.
+    pub fn new() -> Self {
+        let mut systems = Systems::empty();
+        systems
+            .add("components", ComponentSystem, SystemOrder::new())
+            .unwrap();
+        systems
+            .add(
+                "update",
+                UpdateSystem,
+                SystemOrder::new().after("components"),
+            )
+            .unwrap();
+        systems
+            .add("layout", LayoutSystem, SystemOrder::new().after("update"))
+            .unwrap();
+        systems
+    }
+
+    /// Creates a registry without any systems.
+    #[inline]
+    pub fn empty() -> Self {
+        Systems {
+            systems: Some(Vec::new()),
+        }
+    }
+
+    /// Registers a system under `name`.
+    ///
+    /// Fails if there is already a system named `name` or if the ordering constraints form a cycle.
+    pub fn add(
+        &mut self,
+        name: &'static str,
+        system: impl System<T>,
+        order: SystemOrder,
+    ) -> Result<(), SystemError> {
+        let systems = self.systems.as_mut().ok_or(SystemError::RunInProgress)?;
+        if systems.iter().any(|x| x.name == name) {
+            return Err(SystemError::DuplicateSystem(name));
+        }
+
+        systems.push(SystemEntry {
+            name,
+            order,
+            system: Box::new(system),
+        });
+        if sort(systems).is_err() {
+            systems.pop();
+            return Err(SystemError::Cycle(name));
+        }
+        Ok(())
+    }
+
+    /// Replaces the system named `name`, keeping its ordering constraints, and returns the previous system.
+    pub fn replace(
+        &mut self,
+        name: &'static str,
+        system: impl System<T>,
+    ) -> Result<Box<dyn System<T>>, SystemError> {
+        let entry = self
+            .systems
+            .as_mut()
+            .ok_or(SystemError::RunInProgress)?
+            .iter_mut()
+            .find(|x| x.name == name)
+            .ok_or(SystemError::MissingSystem(name))?;
+        Ok(std::mem::replace(&mut entry.system, Box::new(system)))
+    }
+
+    /// Unregisters the system named `name` and returns it.
+    pub fn remove(&mut self, name: &'static str) -> Result<Box<dyn System<T>>, SystemError> {
+        let systems = self.systems.as_mut().ok_or(SystemError::RunInProgress)?;
+        let idx = systems
+            .iter()
+            .position(|x| x.name == name)
+            .ok_or(SystemError::MissingSystem(name))?;
+        Ok(systems.remove(idx).system)
+    }
+
+    pub fn contains(&self, name: &'static str) -> bool {
+        self.systems.iter().flatten().any(|x| x.name == name)
+    }
+
+    /// Returns the names of the systems, in the order they run.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.systems.iter().flatten().map(|x| x.name).collect()
+    }
+}
+
+impl<T: 'static> Default for Systems<T> {
+    #[inline]
+    fn default() -> Self {
+        Systems::new()
+    }
+}
+
+/// Sorts the systems so that their ordering constraints are satisfied, otherwise keeping their current order.
+///
+/// If the constraints form a cycle, the systems are left untouched.
+fn sort<T: 'static>(systems: &mut Vec<SystemEntry<T>>) -> Result<(), ()> {
+    // whether `a` must run before `b`.
+    let precedes = |a: &SystemEntry<T>, b: &SystemEntry<T>| {
+        a.order.before.contains(&b.name) || b.order.after.contains(&a.name)
+    };
+
+    let mut remaining: Vec<usize> = (0..systems.len()).collect();
+    let mut order = Vec::with_capacity(systems.len());
+    while !remaining.is_empty() {
+        let next = remaining
+            .iter()
+            .position(|&x| {
+                !remaining
+                    .iter()
+                    .any(|&y| precedes(&systems[y], &systems[x]))
+            })
+            .ok_or(())?;
+        order.push(remaining.remove(next));
+    }
+
+    let mut entries: Vec<_> = systems.drain(..).map(Some).collect();
+    systems.extend(order.into_iter().map(|x| entries[x].take().unwrap()));
+    Ok(())
+}
+
+/// Runs the systems registered in `aux`, in order.
+pub fn run_systems<T: 'static>(
+    root: &mut dyn WidgetChildren<T>,
+    aux: &mut Aux<T>,
+) -> Result<(), SystemError> {
+    let mut systems = aux
+        .systems
+        .systems
+        .take()
+        .ok_or(SystemError::RunInProgress)?;
+    aux.trace.advance_frame();
+    for entry in &mut systems {
+        entry.system.run(root, aux);
+    }
+    aux.systems.systems = Some(systems);
+    Ok(())
+}
+
+/// Visits the widgets within the tree of `root` which have the widget component `C`, in pre-order.
+pub fn query<T: 'static, C: WidgetComponent<Aux = T>>(
+    root: &dyn WidgetChildren<T>,
+    f: &mut dyn FnMut(&dyn WidgetChildren<T>),
+) {
+    if root.common().read(|x| x.has_component::<C>()) {
+        f(root);
+    }
+    root.for_each_child(&mut |child| query::<T, C>(child, f));
+}
+
+/// Mutable variant of [`query`](query).
+pub fn query_mut<T: 'static, C: WidgetComponent<Aux = T>>(
+    root: &mut dyn WidgetChildren<T>,
+    f: &mut dyn FnMut(&mut dyn WidgetChildren<T>),
+) {
+    if root.common().read(|x| x.has_component::<C>()) {
+        f(root);
+    }
+    root.for_each_child_mut(&mut |child| query_mut::<T, C>(child, f));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_root: &mut dyn WidgetChildren<()>, _aux: &mut Aux<()>) {}
+
+    #[test]
+    fn keeps_registration_order_without_constraints() {
+        let mut systems = Systems::<()>::empty();
+        systems.add("a", noop, SystemOrder::new()).unwrap();
+        systems.add("b", noop, SystemOrder::new()).unwrap();
+        systems.add("c", noop, SystemOrder::new()).unwrap();
+        assert_eq!(systems.names(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn satisfies_before_and_after() {
+        let mut systems = Systems::<()>::empty();
+        systems.add("a", noop, SystemOrder::new()).unwrap();
+        systems.add("b", noop, SystemOrder::new()).unwrap();
+        systems
+            .add("c", noop, SystemOrder::new().before("a"))
+            .unwrap();
+        assert_eq!(systems.names(), ["b", "c", "a"]);
+
+        systems
+            .add("d", noop, SystemOrder::new().after("b").before("c"))
+            .unwrap();
+        assert_eq!(systems.names(), ["b", "d", "c", "a"]);
+    }
+
+    #[test]
+    fn ignores_constraints_on_missing_systems() {
+        let mut systems = Systems::<()>::empty();
+        systems.add("a", noop, SystemOrder::new()).unwrap();
+        systems
+            .add("b", noop, SystemOrder::new().before("missing"))
+            .unwrap();
+        assert_eq!(systems.names(), ["a", "b"]);
+    }
+
+    #[test]
+    fn rejects_cycles_without_changing_the_registry() {
+        let mut systems = Systems::<()>::empty();
+        systems.add("a", noop, SystemOrder::new()).unwrap();
+        systems
+            .add("b", noop, SystemOrder::new().before("a"))
+            .unwrap();
+        assert_eq!(systems.names(), ["b", "a"]);
+
+        assert!(matches!(
+            systems.add("c", noop, SystemOrder::new().after("a").before("b")),
+            Err(SystemError::Cycle("c"))
+        ));
+        assert!(!systems.contains("c"));
+        assert_eq!(systems.names(), ["b", "a"]);
+        assert!(matches!(
+            systems.add("a", noop, SystemOrder::new()),
+            Err(SystemError::DuplicateSystem("a"))
+        ));
+    }
+
+    #[test]
+    fn replace_keeps_constraints() {
+        let mut systems = Systems::<()>::empty();
+        systems.add("a", noop, SystemOrder::new()).unwrap();
+        systems
+            .add("b", noop, SystemOrder::new().before("a"))
+            .unwrap();
+        systems.replace("b", noop).unwrap();
+        assert_eq!(systems.names(), ["b", "a"]);
+
+        // only a cycle if `b` still runs before `a`.
+        assert!(matches!(
+            systems.add("c", noop, SystemOrder::new().after("a").before("b")),
+            Err(SystemError::Cycle("c"))
+        ));
+        assert!(matches!(
+            systems.replace("missing", noop),
+            Err(SystemError::MissingSystem("missing"))
+        ));
+    }
+
+    #[test]
+    fn default_order() {
+        assert_eq!(
+            Systems::<()>::new().names(),
+            ["components", "update", "layout"]
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Single-threaded executor for futures owned by widgets.
+//!
+//! Tasks are spawned through [`Aux::spawn`](super::Aux::spawn) and polled by [`Aux::run_tasks`](super::Aux::run_tasks).
+//! Once a task completes, its output is emitted as a [`TaskOutputEvent`](TaskOutputEvent) from the ID of the widget which owns it.
+//! A task is cancelled once its owner is dropped or marked for detachment.
+//!
+//! Tasks can wait on [`Delay`](Delay) (which follows the clock of the timers) and on the [`channel`](channel)
+//! receiver, which can be fed from any thread.
+
+use {
+    super::{is_detached, timer::Clock, Common, CommonRef, Emitter},
+    std::{
+        cell::RefCell,
+        collections::{HashMap, VecDeque},
+        future::Future,
+        panic::{self, AssertUnwindSafe},
+        pin::Pin,
+        rc::{Rc, Weak},
+        sync::{Arc, Mutex},
+        task::{Context, Poll, Wake, Waker},
+        time::{Duration, Instant},
+    },
+};
+
+type EmitFn = Box<dyn FnOnce(&Emitter)>;
+type LoopWaker = Arc<dyn Fn() + Send + Sync>;
+
+/// Handle to a spawned task, used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskHandle(u64);
+
+/// A task completed with an output.
+#[derive(Debug, Clone)]
+pub struct TaskOutputEvent<O> {
+    pub handle: TaskHandle,
+    pub output: O,
+}
+
+/// A task panicked while being polled; it will not be polled again.
+#[derive(Debug, Clone)]
+pub struct TaskPanicEvent {
+    pub handle: TaskHandle,
+    /// The panic message, if it was a string.
+    pub message: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChannelError {
+    #[error("The receiving end of the channel has been dropped")]
+    Disconnected,
+}
+
+struct Task {
+    owner: Weak<RefCell<Common>>,
+    owner_id: u64,
+    future: Pin<Box<dyn Future<Output = EmitFn>>>,
+}
+
+impl Task {
+    #[inline]
+    fn is_cancelled(&self) -> bool {
+        self.owner
+            .upgrade()
+            .map(|x| is_detached(&x))
+            .unwrap_or(true)
+    }
+}
+
+struct TaskWaker {
+    handle: u64,
+    ready: Arc<Mutex<VecDeque<u64>>>,
+    wake_loop: Option<LoopWaker>,
+}
+
+impl Wake for TaskWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        {
+            let mut ready = self.ready.lock().unwrap();
+            if !ready.contains(&self.handle) {
+                ready.push_back(self.handle);
+            }
+        }
+
+        if let Some(wake_loop) = &self.wake_loop {
+            wake_loop();
+        }
+    }
+}
+
+/// Pending [`Delay`](Delay)s, keyed by delay, with the waker of their latest poll.
+#[derive(Default)]
+struct SleeperSet {
+    sleepers: HashMap<u64, (Instant, Waker)>,
+    next_key: u64,
+}
+
+type Sleepers = Rc<RefCell<SleeperSet>>;
+
+/// Executor of tasks, owned by [`Aux`](super::Aux).
+pub struct Executor {
+    tasks: HashMap<u64, Task>,
+    ready: Arc<Mutex<VecDeque<u64>>>,
+    sleepers: Sleepers,
+    next_handle: u64,
+    wake_loop: Option<LoopWaker>,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        Executor {
+            tasks: HashMap::new(),
+            ready: Default::default(),
+            sleepers: Default::default(),
+            next_handle: 0,
+            wake_loop: None,
+        }
+    }
+
+    /// Changes the function invoked (possibly from another thread) when a task is woken, so that the UI thread can be woken.
+    pub fn set_waker(&mut self, waker: impl Fn() + Send + Sync + 'static) {
+        self.wake_loop = Some(Arc::new(waker));
+    }
+
+    /// Spawns a task owned by `owner`.
+    ///
+    /// The output is emitted as a [`TaskOutputEvent`](TaskOutputEvent) from the ID of `owner`.
+    pub fn spawn<O: 'static>(
+        &mut self,
+        owner: &CommonRef,
+        future: impl Future<Output = O> + 'static,
+    ) -> TaskHandle {
+        let handle = TaskHandle(self.next_handle);
+        self.next_handle += 1;
+
+        let owner_id = owner.read(|x| x.id());
+        self.tasks.insert(
+            handle.0,
+            Task {
+                owner: Rc::downgrade(owner.get_rc()),
+                owner_id,
+                future: Box::pin(async move {
+                    let output = future.await;
+                    Box::new(move |emitter: &Emitter| {
+                        emitter.emit(owner_id, TaskOutputEvent { handle, output })
+                    }) as EmitFn
+                }),
+            },
+        );
+        self.ready.lock().unwrap().push_back(handle.0);
+
+        handle
+    }
+
+    /// Cancels a task, returning `false` if it had already completed or been cancelled.
+    #[inline]
+    pub fn cancel(&mut self, handle: TaskHandle) -> bool {
+        self.tasks.remove(&handle.0).is_some()
+    }
+
+    #[inline]
+    pub fn is_active(&self, handle: TaskHandle) -> bool {
+        self.tasks.contains_key(&handle.0)
+    }
+
+    /// Returns the number of tasks which haven't completed.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Returns `true` if any task has been woken and is waiting to be polled.
+    #[inline]
+    pub fn has_ready(&self) -> bool {
+        !self.ready.lock().unwrap().is_empty()
+    }
+
+    /// Returns the earliest deadline of all the pending [`Delay`](Delay)s.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.sleepers
+            .borrow()
+            .sleepers
+            .values()
+            .map(|&(x, _)| x)
+            .min()
+    }
+
+    /// Returns a future which completes once `duration` has elapsed on `clock`.
+    pub fn delay(&self, clock: Rc<dyn Clock>, duration: Duration) -> Delay {
+        let key = {
+            let mut sleepers = self.sleepers.borrow_mut();
+            sleepers.next_key += 1;
+            sleepers.next_key
+        };
+        Delay {
+            deadline: clock.now() + duration,
+            clock,
+            key,
+            sleepers: Rc::clone(&self.sleepers),
+        }
+    }
+
+    /// Wakes tasks waiting on elapsed delays, then polls every woken task once.
+    ///
+    /// Outputs and panics are emitted through `emitter`. Returns the number of tasks which finished.
+    pub fn run(&mut self, emitter: &Emitter, now: Instant) -> usize {
+        let elapsed: Vec<_> = {
+            let mut sleepers = self.sleepers.borrow_mut();
+            let sleepers = &mut sleepers.sleepers;
+            let keys: Vec<_> = sleepers
+                .iter()
+                .filter(|(_, &(x, _))| x <= now)
+                .map(|(&key, _)| key)
+                .collect();
+            keys.into_iter()
+                .filter_map(|key| sleepers.remove(&key))
+                .collect()
+        };
+        for (_, waker) in elapsed {
+            waker.wake();
+        }
+
+        self.tasks.retain(|_, task| !task.is_cancelled());
+
+        let ready = std::mem::take(&mut *self.ready.lock().unwrap());
+        let mut finished = 0;
+        for handle in ready {
+            let task = if let Some(task) = self.tasks.get_mut(&handle) {
+                task
+            } else {
+                continue;
+            };
+
+            let waker = Waker::from(Arc::new(TaskWaker {
+                handle,
+                ready: Arc::clone(&self.ready),
+                wake_loop: self.wake_loop.clone(),
+            }));
+            let mut cx = Context::from_waker(&waker);
+
+            match panic::catch_unwind(AssertUnwindSafe(|| task.future.as_mut().poll(&mut cx))) {
+                Ok(Poll::Pending) => continue,
+                Ok(Poll::Ready(emit)) => emit(emitter),
+                Err(payload) => {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .map(|x| x.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned());
+                    emitter.emit(
+                        task.owner_id,
+                        TaskPanicEvent {
+                            handle: TaskHandle(handle),
+                            message,
+                        },
+                    );
+                }
+            }
+
+            self.tasks.remove(&handle);
+            finished += 1;
+        }
+
+        finished
+    }
+}
+
+impl Default for Executor {
+    #[inline]
+    fn default() -> Self {
+        Executor::new()
+    }
+}
+
+/// Future which completes once a deadline has elapsed.
+///
+/// Created through [`Aux::delay`](super::Aux::delay).
+pub struct Delay {
+    deadline: Instant,
+    clock: Rc<dyn Clock>,
+    key: u64,
+    sleepers: Sleepers,
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut sleepers = self.sleepers.borrow_mut();
+        if self.clock.now() >= self.deadline {
+            sleepers.sleepers.remove(&self.key);
+            return Poll::Ready(());
+        }
+
+        // a delay is registered once, however often it is polled; only its waker is kept up to date.
+        match sleepers.sleepers.get_mut(&self.key) {
+            Some((_, waker)) if waker.will_wake(cx.waker()) => {}
+            Some((_, waker)) => *waker = cx.waker().clone(),
+            None => {
+                sleepers
+                    .sleepers
+                    .insert(self.key, (self.deadline, cx.waker().clone()));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        // the delay of a cancelled task is dropped along with it, and must not wake anything afterwards.
+        if let Ok(mut sleepers) = self.sleepers.try_borrow_mut() {
+            sleepers.sleepers.remove(&self.key);
+        }
+    }
+}
+
+struct Channel<T> {
+    values: VecDeque<T>,
+    waker: Option<Waker>,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+/// Creates a channel whose sending half can be used from any thread, and whose receiving half can be awaited by a task.
+pub fn channel<T: Send>() -> (Sender<T>, Receiver<T>) {
+    let channel = Arc::new(Mutex::new(Channel {
+        values: VecDeque::new(),
+        waker: None,
+        senders: 1,
+        receiver_alive: true,
+    }));
+    (Sender(Arc::clone(&channel)), Receiver(channel))
+}
+
+/// Sending half of a [`channel`](channel).
+pub struct Sender<T>(Arc<Mutex<Channel<T>>>);
+
+impl<T> Sender<T> {
+    pub fn send(&self, value: T) -> Result<(), ChannelError> {
+        let mut channel = self.0.lock().unwrap();
+        if !channel.receiver_alive {
+            return Err(ChannelError::Disconnected);
+        }
+        channel.values.push_back(value);
+        if let Some(waker) = channel.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.0.lock().unwrap().senders += 1;
+        Sender(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut channel = self.0.lock().unwrap();
+        channel.senders -= 1;
+        if channel.senders == 0 {
+            if let Some(waker) = channel.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Receiving half of a [`channel`](channel).
+pub struct Receiver<T>(Arc<Mutex<Channel<T>>>);
+
+impl<T> Receiver<T> {
+    /// Returns a future which resolves to the next value, or `None` once all the senders have been dropped.
+    #[inline]
+    pub fn recv(&mut self) -> Recv<T> {
+        Recv(self)
+    }
+
+    /// Returns the next value if one is available, without waiting.
+    #[inline]
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.0.lock().unwrap().values.pop_front()
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut channel = self.0.lock().unwrap();
+        channel.receiver_alive = false;
+        channel.values.clear();
+    }
+}
+
+/// Future returned by [`Receiver::recv`](Receiver::recv).
+pub struct Recv<'a, T>(&'a mut Receiver<T>);
+
+impl<'a, T> Future for Recv<'a, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        let mut channel = (self.0).0.lock().unwrap();
+        if let Some(value) = channel.values.pop_front() {
+            Poll::Ready(Some(value))
+        } else if channel.senders == 0 {
+            Poll::Ready(None)
+        } else {
+            channel.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
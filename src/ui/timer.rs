@@ -0,0 +1,245 @@
+//! Timers which emit events once a deadline elapses.
+//!
+//! Timers are normally scheduled through [`Aux::set_timeout`](super::Aux::set_timeout) and
+//! [`Aux::set_interval`](super::Aux::set_interval), and are processed by [`Aux::process_timers`](super::Aux::process_timers).
+//!
+//! A timer can be given an owner with [`Timers::set_owner`](Timers::set_owner), in which case it is cancelled once its owner
+//! is dropped or marked for detachment, the same as a task.
+
+use {
+    super::{is_detached, Common, CommonRef, Emitter},
+    std::{
+        cell::{Cell, RefCell},
+        rc::{Rc, Weak},
+        time::{Duration, Instant},
+    },
+};
+
+/// Source of the current time for timers.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Clock which reads the system's monotonic clock.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock which only advances when told to, for deterministic tests.
+///
+/// Clones share the same time, so a clone can be kept to advance the clock given to [`Timers`](Timers).
+#[derive(Debug, Clone)]
+pub struct ManualClock(Rc<Cell<Instant>>);
+
+impl ManualClock {
+    pub fn new(start: Instant) -> Self {
+        ManualClock(Rc::new(Cell::new(start)))
+    }
+
+    #[inline]
+    pub fn advance(&self, duration: Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+
+    #[inline]
+    pub fn set(&self, now: Instant) {
+        self.0.set(now);
+    }
+}
+
+impl Default for ManualClock {
+    #[inline]
+    fn default() -> Self {
+        ManualClock::new(Instant::now())
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+/// Handle to a scheduled timer, used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimerHandle(u64);
+
+struct Timer {
+    handle: TimerHandle,
+    deadline: Instant,
+    interval: Option<Duration>,
+    id: u64,
+    owner: Option<Weak<RefCell<Common>>>,
+    emit: Box<dyn FnMut(&Emitter, u64)>,
+}
+
+impl Timer {
+    #[inline]
+    fn is_cancelled(&self) -> bool {
+        self.owner
+            .as_ref()
+            .map(|x| x.upgrade().map(|x| is_detached(&x)).unwrap_or(true))
+            .unwrap_or(false)
+    }
+}
+
+/// Set of scheduled timers.
+pub struct Timers {
+    clock: Rc<dyn Clock>,
+    timers: Vec<Timer>,
+    next_handle: u64,
+}
+
+impl Timers {
+    /// Shortest interval of a repeating timer; shorter intervals are clamped to this.
+    pub const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Timers {
+            clock: Rc::new(clock),
+            timers: Vec::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// Replaces the clock used to schedule and process timers.
+    ///
+    /// Deadlines of timers which are already scheduled are not adjusted.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Rc::new(clock);
+    }
+
+    /// Returns the clock used to schedule and process timers.
+    #[inline]
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        Rc::clone(&self.clock)
+    }
+
+    #[inline]
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Schedules a timer which emits `event` to `id` once `delay` has elapsed.
+    pub fn set_timeout<E: 'static>(&mut self, id: u64, delay: Duration, event: E) -> TimerHandle {
+        let mut event = Some(event);
+        self.schedule(id, delay, None, move |emitter, id| {
+            if let Some(event) = event.take() {
+                emitter.emit(id, event);
+            }
+        })
+    }
+
+    /// Schedules a timer which emits a clone of `event` to `id` every `interval`, until cancelled.
+    ///
+    /// `interval` is clamped to at least [`MIN_INTERVAL`](Timers::MIN_INTERVAL), since a zero interval would be due on
+    /// every pass of the event loop.
+    pub fn set_interval<E: Clone + 'static>(
+        &mut self,
+        id: u64,
+        interval: Duration,
+        event: E,
+    ) -> TimerHandle {
+        let interval = interval.max(Self::MIN_INTERVAL);
+        self.schedule(id, interval, Some(interval), move |emitter, id| {
+            emitter.emit(id, event.clone())
+        })
+    }
+
+    fn schedule(
+        &mut self,
+        id: u64,
+        delay: Duration,
+        interval: Option<Duration>,
+        emit: impl FnMut(&Emitter, u64) + 'static,
+    ) -> TimerHandle {
+        let handle = TimerHandle(self.next_handle);
+        self.next_handle += 1;
+        self.timers.push(Timer {
+            handle,
+            deadline: self.clock.now() + delay,
+            interval,
+            id,
+            owner: None,
+            emit: Box::new(emit),
+        });
+        handle
+    }
+
+    /// Ties a timer to `owner`, so that it is cancelled once `owner` is dropped or marked for detachment.
+    ///
+    /// Returns `false` if the timer had already finished or been cancelled.
+    pub fn set_owner(&mut self, handle: TimerHandle, owner: &CommonRef) -> bool {
+        match self.timers.iter_mut().find(|x| x.handle == handle) {
+            Some(timer) => {
+                timer.owner = Some(Rc::downgrade(owner.get_rc()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels a timer, returning `false` if it had already finished or been cancelled.
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|x| x.handle != handle);
+        len != self.timers.len()
+    }
+
+    #[inline]
+    pub fn is_active(&self, handle: TimerHandle) -> bool {
+        self.timers.iter().any(|x| x.handle == handle)
+    }
+
+    /// Returns the earliest deadline of all the scheduled timers.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|x| x.deadline).min()
+    }
+
+    /// Emits the events of all the timers which have elapsed, in the order of their deadlines.
+    ///
+    /// One-shot timers are removed. Repeating timers emit at most once per call; missed intervals are skipped.
+    /// Returns the number of events emitted.
+    pub fn process(&mut self, emitter: &Emitter) -> usize {
+        self.timers.retain(|x| !x.is_cancelled());
+
+        let now = self.clock.now();
+        let mut elapsed: Vec<_> = self
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.deadline <= now)
+            .map(|(i, x)| (x.deadline, x.handle, i))
+            .collect();
+        elapsed.sort();
+
+        for &(_, _, i) in &elapsed {
+            let timer = &mut self.timers[i];
+            (timer.emit)(emitter, timer.id);
+            if let Some(interval) = timer.interval {
+                timer.deadline += interval;
+                if timer.deadline <= now {
+                    timer.deadline = now + interval;
+                }
+            }
+        }
+
+        self.timers
+            .retain(|x| x.interval.is_some() || x.deadline > now);
+        elapsed.len()
+    }
+}
+
+impl Default for Timers {
+    #[inline]
+    fn default() -> Self {
+        Timers::new(SystemClock)
+    }
+}
@@ -0,0 +1,149 @@
+//! Tracing of the events emitted on the global queue through an [`Emitter`](super::Emitter).
+//!
+//! This covers every event the UI emits itself (input, timer, task, proxied and lifecycle events) as well as those emitted
+//! through [`Aux::emit`](super::Aux::emit) and [`Common::emit`](super::Common::emit).
+//!
+//! The trace is disabled by default. Once enabled, it keeps the most recent entries and optionally passes every entry to a
+//! sink as it's recorded (e.g. to write it to a log file).
+//!
+//! ```ignore
+//! aux.trace.enable();
+//! aux.trace.set_sink(|entry| writeln!(log, "{}", entry).unwrap());
+//! ```
+
+use std::{cell::RefCell, collections::VecDeque, fmt};
+
+/// An emitted event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The [frame](EventTrace::frame) in which the event was emitted.
+    pub frame: u64,
+    /// ID which the event was emitted from.
+    pub source: u64,
+    /// Full type name of the event, as given by `std::any::type_name`.
+    pub type_name: &'static str,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] @{} {}", self.frame, self.source, self.type_name)
+    }
+}
+
+/// Log of emitted events.
+pub struct EventTrace {
+    enabled: bool,
+    frame: u64,
+    capacity: usize,
+    entries: RefCell<VecDeque<TraceEntry>>,
+    sink: RefCell<Option<Box<dyn FnMut(&TraceEntry)>>>,
+}
+
+impl EventTrace {
+    /// Number of entries kept by default.
+    pub const DEFAULT_CAPACITY: usize = 4096;
+
+    pub fn new() -> Self {
+        EventTrace {
+            enabled: false,
+            frame: 0,
+            capacity: Self::DEFAULT_CAPACITY,
+            entries: RefCell::new(VecDeque::new()),
+            sink: RefCell::new(None),
+        }
+    }
+
+    #[inline]
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    #[inline]
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the number of times the systems have run (see [`run_systems`](super::system::run_systems)).
+    ///
+    /// This is counted whether or not the trace is enabled.
+    #[inline]
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Changes the number of entries which are kept, dropping the oldest entries if there are more.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let entries = self.entries.get_mut();
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+    }
+
+    /// Passes every entry to `sink` as it's recorded, in addition to keeping it.
+    #[inline]
+    pub fn set_sink(&mut self, sink: impl FnMut(&TraceEntry) + 'static) {
+        *self.sink.get_mut() = Some(Box::new(sink));
+    }
+
+    #[inline]
+    pub fn clear_sink(&mut self) {
+        *self.sink.get_mut() = None;
+    }
+
+    /// Returns the kept entries, oldest first.
+    #[inline]
+    pub fn entries(&self) -> Vec<TraceEntry> {
+        self.entries.borrow().iter().copied().collect()
+    }
+
+    /// Removes the kept entries and returns them, oldest first.
+    #[inline]
+    pub fn take(&mut self) -> Vec<TraceEntry> {
+        self.entries.get_mut().drain(..).collect()
+    }
+
+    pub(crate) fn record(&self, source: u64, type_name: &'static str) {
+        if !self.enabled {
+            return;
+        }
+
+        let entry = TraceEntry {
+            frame: self.frame,
+            source,
+            type_name,
+        };
+
+        // the sink isn't reentrant; events which it emits itself aren't passed to it.
+        if let Ok(mut sink) = self.sink.try_borrow_mut() {
+            if let Some(sink) = &mut *sink {
+                sink(&entry);
+            }
+        }
+
+        if self.capacity > 0 {
+            let mut entries = self.entries.borrow_mut();
+            if entries.len() == self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn advance_frame(&mut self) {
+        self.frame += 1;
+    }
+}
+
+impl Default for EventTrace {
+    #[inline]
+    fn default() -> Self {
+        EventTrace::new()
+    }
+}
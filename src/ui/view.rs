@@ -104,10 +104,13 @@ impl<T: 'static, S: 'static> View<T, S> {
     }
 
     /// Removes a child widget.
+    ///
+    /// The child is marked for detachment, so that layouts drop it. Once it has been reported as detached
+    /// (see [`Aux::process_lifecycle`](Aux::process_lifecycle)), the handlers for it are removed from the listener.
     pub fn remove<W: WidgetChildren<T> + 'static>(&mut self, child: ChildRef<W>) -> Option<W> {
-        self.children
-            .remove(&child.0)
-            .map(|x| *x.as_any_box().downcast::<W>().unwrap())
+        let child = self.children.remove(&child.0)?;
+        child.common().with(|x| x.mark_for_detach());
+        Some(*child.as_any_box().downcast::<W>().unwrap())
     }
 
     /// Returns `true` if this view has a given child widget, otherwise `false`.
@@ -121,7 +124,7 @@ impl<T: 'static, S: 'static> View<T, S> {
         child: ChildRef<W>,
         mut handler: impl FnMut(&mut Self, &mut Aux<T>, &Eo) + 'static,
     ) {
-        let id = self.get(child).map(|x| x.common().with(|x| x.id()));
+        let id = self.get(child).map(|x| x.common().read(|x| x.id()));
         if let Some(id) = id {
             self.listener
                 .on(id, move |(view, aux), ev| handler(view, aux, ev));
@@ -135,7 +138,7 @@ impl<T: 'static, S: 'static> View<T, S> {
         child: ChildRef<W>,
         mut handler: impl FnMut(&mut Self, &mut Aux<T>, &Eo) + 'static,
     ) {
-        let id = self.get(child).map(|x| x.common().with(|x| x.id()));
+        let id = self.get(child).map(|x| x.common().read(|x| x.id()));
         if let Some(id) = id {
             self.listener
                 .late_on(id, move |(view, aux), ev| handler(view, aux, ev));
@@ -200,6 +203,30 @@ impl<T: 'static, S: 'static> WidgetChildren<T> for View<T, S> {
     fn children_mut(&mut self) -> Vec<&mut dyn WidgetChildren<T>> {
         self.children.values_mut().map(|x| &mut **x).collect()
     }
+
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for child in self.children.values() {
+            f(&**child);
+        }
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for child in self.children.values_mut() {
+            f(&mut **child);
+        }
+    }
+
+    fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for child in self.children.values().rev() {
+            f(&**child);
+        }
+    }
+
+    fn for_each_child_mut_rev(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for child in self.children.values_mut().rev() {
+            f(&mut **child);
+        }
+    }
 }
 
 impl<T: 'static, S: 'static> Element for View<T, S> {
@@ -219,6 +246,34 @@ impl<T: 'static, S: 'static> Element for View<T, S> {
 pub trait ViewPart<T: 'static>: 'static {
     fn children(&self) -> Vec<&dyn WidgetChildren<T>>;
     fn children_mut(&mut self) -> Vec<&mut dyn WidgetChildren<T>>;
+
+    /// See [`WidgetChildren::for_each_child`](WidgetChildren::for_each_child).
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for child in self.children() {
+            f(child);
+        }
+    }
+
+    /// See [`WidgetChildren::for_each_child_mut`](WidgetChildren::for_each_child_mut).
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for child in self.children_mut() {
+            f(child);
+        }
+    }
+
+    /// See [`WidgetChildren::for_each_child_rev`](WidgetChildren::for_each_child_rev).
+    fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        for child in self.children().into_iter().rev() {
+            f(child);
+        }
+    }
+
+    /// See [`WidgetChildren::for_each_child_mut_rev`](WidgetChildren::for_each_child_mut_rev).
+    fn for_each_child_mut_rev(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        for child in self.children_mut().into_iter().rev() {
+            f(child);
+        }
+    }
 }
 
 /// Same as `View`, however children are stored in the state type (`S`) via `ViewPart`.
@@ -301,6 +356,26 @@ impl<T: 'static, S: ViewPart<T>> WidgetChildren<T> for PartialView<T, S> {
     fn children_mut(&mut self) -> Vec<&mut dyn WidgetChildren<T>> {
         self.state.children_mut()
     }
+
+    #[inline]
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        self.state.for_each_child(f)
+    }
+
+    #[inline]
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        self.state.for_each_child_mut(f)
+    }
+
+    #[inline]
+    fn for_each_child_rev(&self, f: &mut dyn FnMut(&dyn WidgetChildren<T>)) {
+        self.state.for_each_child_rev(f)
+    }
+
+    #[inline]
+    fn for_each_child_mut_rev(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<T>)) {
+        self.state.for_each_child_mut_rev(f)
+    }
 }
 
 impl<T: 'static, S: ViewPart<T>> Element for PartialView<T, S> {
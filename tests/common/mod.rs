@@ -0,0 +1,49 @@
+//! Helpers shared by the integration tests.
+
+use {
+    otway::{
+        theme::{self, AnyPainter, Theme},
+        ui,
+    },
+    reclutch::display as gfx,
+};
+
+struct NullPainter;
+
+impl AnyPainter<()> for NullPainter {
+    fn paint(
+        &mut self,
+        _obj: &mut dyn std::any::Any,
+        _aux: &mut ui::Aux<()>,
+    ) -> Vec<gfx::DisplayCommand> {
+        Vec::new()
+    }
+
+    fn size_hint(&mut self, _obj: &mut dyn std::any::Any) -> gfx::Size {
+        gfx::Size::zero()
+    }
+
+    fn metrics(&self, _obj: &dyn std::any::Any, _metric: &'static str) -> Option<f32> {
+        Some(0.0)
+    }
+}
+
+/// Theme which paints nothing, for running UIs headless.
+pub struct NullTheme;
+
+impl Theme<()> for NullTheme {
+    fn painter(&self, _p: &'static str) -> Box<dyn AnyPainter<()>> {
+        Box::new(NullPainter)
+    }
+
+    fn color(&self, _c: &'static str) -> gfx::Color {
+        gfx::Color::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    fn standards(&self) -> theme::Standards {
+        theme::Standards {
+            label_size: 12.0,
+            button_text_alignment: ui::layout::Alignment::Middle,
+        }
+    }
+}
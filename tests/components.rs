@@ -0,0 +1,188 @@
+//! Ordering, attachment and detachment of the components of a widget.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::ui::{self, Component, ComponentError, ComponentList},
+    std::{cell::RefCell, marker::PhantomData, rc::Rc},
+};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+struct Widget {
+    common: ui::CommonRef,
+    components: ComponentList<Widget>,
+}
+
+impl ui::Element for Widget {
+    type Aux = ();
+
+    #[inline]
+    fn common(&self) -> &ui::CommonRef {
+        &self.common
+    }
+}
+
+impl ui::WidgetChildren<()> for Widget {}
+
+struct First;
+struct Second;
+struct Third;
+
+struct Logger<M: 'static> {
+    name: &'static str,
+    log: Log,
+    phantom: PhantomData<M>,
+}
+
+impl<M: 'static> Logger<M> {
+    fn new(name: &'static str, log: &Log) -> Self {
+        Logger {
+            name,
+            log: Rc::clone(log),
+            phantom: PhantomData,
+        }
+    }
+
+    fn push(&self, what: &str) {
+        self.log
+            .borrow_mut()
+            .push(format!("{} {}", self.name, what));
+    }
+}
+
+impl<M: 'static> Component for Logger<M> {
+    type Type = ();
+    type Object = Widget;
+
+    fn update(&mut self, _obj: &mut Widget, _aux: &mut ui::Aux<()>) {
+        self.push("update");
+    }
+
+    fn on_attach(&mut self, _obj: &mut Widget, _aux: &mut ui::Aux<()>) {
+        self.push("attach");
+    }
+
+    fn on_detach(&mut self, _obj: &mut Widget, _aux: &mut ui::Aux<()>) {
+        self.push("detach");
+    }
+}
+
+fn new_widget() -> (Widget, ui::Aux<()>) {
+    let common = ui::CommonRef::new(None);
+    let aux = ui::Aux::new((), Box::new(NullTheme), common.clone());
+    let widget = Widget {
+        common,
+        components: ComponentList::new(),
+    };
+    (widget, aux)
+}
+
+fn dispatch(widget: &mut Widget, aux: &mut ui::Aux<()>) {
+    ui::dispatch_components(widget, aux, |x| &mut x.components).unwrap();
+}
+
+fn take(log: &Log) -> Vec<String> {
+    log.borrow_mut().drain(..).collect()
+}
+
+#[test]
+fn dispatches_in_insertion_order() {
+    let log = Log::default();
+    let (mut widget, mut aux) = new_widget();
+    let components = &mut widget.components;
+    components
+        .push(Logger::<First>::new("first", &log))
+        .unwrap();
+    components
+        .push(Logger::<Second>::new("second", &log))
+        .unwrap();
+    components
+        .insert(0, Logger::<Third>::new("third", &log))
+        .unwrap();
+    assert_eq!(components.position::<Logger<Third>>().unwrap(), 0);
+    assert_eq!(components.position::<Logger<Second>>().unwrap(), 2);
+    assert_eq!(components.len(), 3);
+
+    dispatch(&mut widget, &mut aux);
+    assert_eq!(
+        take(&log),
+        [
+            "third attach",
+            "third update",
+            "first attach",
+            "first update",
+            "second attach",
+            "second update",
+        ]
+    );
+}
+
+#[test]
+fn rejects_duplicates() {
+    let log = Log::default();
+    let (mut widget, _aux) = new_widget();
+    widget
+        .components
+        .push(Logger::<First>::new("first", &log))
+        .unwrap();
+    assert!(matches!(
+        widget.components.push(Logger::<First>::new("first", &log)),
+        Err(ComponentError::DuplicateComponent)
+    ));
+    assert_eq!(widget.components.len(), 1);
+}
+
+#[test]
+fn attaches_once_before_the_first_update() {
+    let log = Log::default();
+    let (mut widget, mut aux) = new_widget();
+    widget
+        .components
+        .push(Logger::<First>::new("first", &log))
+        .unwrap();
+    assert!(take(&log).is_empty());
+
+    dispatch(&mut widget, &mut aux);
+    dispatch(&mut widget, &mut aux);
+    assert_eq!(take(&log), ["first attach", "first update", "first update"]);
+}
+
+#[test]
+fn detaches_removed_components_on_the_next_dispatch() {
+    let log = Log::default();
+    let (mut widget, mut aux) = new_widget();
+    widget
+        .components
+        .push(Logger::<First>::new("first", &log))
+        .unwrap();
+    dispatch(&mut widget, &mut aux);
+    take(&log);
+
+    widget.components.remove::<Logger<First>>().unwrap();
+    assert!(!widget.components.contains::<Logger<First>>());
+    assert!(take(&log).is_empty());
+    dispatch(&mut widget, &mut aux);
+    assert_eq!(take(&log), ["first detach"]);
+    dispatch(&mut widget, &mut aux);
+    assert!(take(&log).is_empty());
+}
+
+#[test]
+fn drops_never_attached_components_without_detaching() {
+    let log = Log::default();
+    let (mut widget, mut aux) = new_widget();
+    widget
+        .components
+        .push(Logger::<First>::new("first", &log))
+        .unwrap();
+    widget.components.remove::<Logger<First>>().unwrap();
+    assert!(matches!(
+        widget.components.remove::<Logger<First>>(),
+        Err(ComponentError::MissingComponent)
+    ));
+
+    dispatch(&mut widget, &mut aux);
+    assert!(take(&log).is_empty());
+}
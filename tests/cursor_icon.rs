@@ -0,0 +1,62 @@
+//! Resolving the cursor icon from the topmost widget under the cursor.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{self, view::View},
+    },
+    reclutch::display as gfx,
+};
+
+fn rect(x: f32, y: f32, width: f32, height: f32) -> gfx::Rect {
+    gfx::Rect::new(gfx::Point::new(x, y), gfx::Size::new(width, height))
+}
+
+#[test]
+fn topmost_sibling_hides_the_icons_beneath_it() {
+    let mut ui = Headless::new(
+        |parent, aux| {
+            let mut view = View::new(parent, aux, ());
+            // drawn first, so beneath the popup.
+            let text = view.child(kit::Label::new, aux);
+            let text = view.get(text).unwrap();
+            text.set_rect(rect(0.0, 0.0, 60.0, 60.0));
+            text.common()
+                .with(|x| x.set_cursor_icon(ui::CursorIcon::Text));
+
+            let popup = view.child(kit::Label::new, aux);
+            view.get(popup)
+                .unwrap()
+                .set_rect(rect(20.0, 20.0, 60.0, 60.0));
+            view
+        },
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    );
+
+    ui.mouse_move(gfx::Point::new(10.0, 10.0));
+    assert_eq!(ui.cursor_icon(), ui::CursorIcon::Text);
+
+    ui.mouse_move(gfx::Point::new(40.0, 40.0));
+    assert_eq!(
+        ui.cursor_icon(),
+        ui::CursorIcon::Default,
+        "the popup should hide the text beneath it"
+    );
+
+    ui.root()
+        .common()
+        .with(|x| x.set_cursor_icon(ui::CursorIcon::Pointer));
+    ui.mouse_move(gfx::Point::new(41.0, 41.0));
+    assert_eq!(
+        ui.cursor_icon(),
+        ui::CursorIcon::Pointer,
+        "the popup should fall back to its parent"
+    );
+}
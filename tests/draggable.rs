@@ -0,0 +1,53 @@
+//! Adding and removing the `Draggable` component on a button.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{headless::Headless, kit, prelude::*, ui::view::View},
+    reclutch::display as gfx,
+    std::{cell::Cell, rc::Rc},
+};
+
+#[test]
+fn button_receives_input_once_draggable_is_removed() {
+    let presses = Rc::new(Cell::new(0));
+    let mut button = None;
+    let mut ui = Headless::new(
+        |parent, aux| {
+            let mut view = View::new(parent, aux, ());
+            let child = view.child(kit::Button::new, aux);
+            view.get(child).unwrap().set_rect(gfx::Rect::new(
+                gfx::Point::new(10.0, 10.0),
+                gfx::Size::new(40.0, 20.0),
+            ));
+            let presses = Rc::clone(&presses);
+            view.handle(child, move |_, _, _: &kit::PressEvent| {
+                presses.set(presses.get() + 1)
+            });
+            button = Some(child);
+            view
+        },
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    );
+    let button = button.unwrap();
+    let inside = gfx::Point::new(20.0, 20.0);
+
+    let (view, aux) = ui.parts_mut();
+    let draggable = kit::Draggable::new(aux);
+    view.get(button).unwrap().add_component(draggable).unwrap();
+    ui.update();
+    ui.click(inside);
+    assert_eq!(presses.get(), 0, "the drag should consume the press");
+
+    ui.root()
+        .get(button)
+        .unwrap()
+        .remove_component::<kit::Draggable<()>>()
+        .unwrap();
+    ui.update();
+    ui.click(inside);
+    assert_eq!(presses.get(), 1);
+}
@@ -0,0 +1,124 @@
+//! Ordering, consumption, rewriting and removal of input filters.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::ui::{self, InputEvent, KeyEvent, KeyInput},
+    std::{cell::RefCell, rc::Rc},
+};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+fn new_aux() -> ui::Aux<()> {
+    ui::Aux::new((), Box::new(NullTheme), ui::CommonRef::new(None))
+}
+
+fn key(key: KeyInput) -> InputEvent {
+    InputEvent::KeyPress(KeyEvent::new(key))
+}
+
+/// Adds a filter which logs `name` and the key of the input it sees.
+fn logger(
+    aux: &mut ui::Aux<()>,
+    priority: i32,
+    name: &'static str,
+    log: &Log,
+) -> ui::filter::FilterHandle {
+    let log = Rc::clone(log);
+    aux.add_filter(priority, move |_, input| {
+        let key = match input.get() {
+            InputEvent::KeyPress(x) => x.key.map(KeyInput::name).unwrap_or(""),
+            _ => "",
+        };
+        log.borrow_mut().push(format!("{} {}", name, key));
+    })
+}
+
+fn take(log: &Log) -> Vec<String> {
+    log.borrow_mut().drain(..).collect()
+}
+
+#[test]
+fn run_by_descending_priority() {
+    let log = Log::default();
+    let mut aux = new_aux();
+    logger(&mut aux, 0, "zero", &log);
+    logger(&mut aux, 10, "ten", &log);
+    logger(&mut aux, -5, "negative", &log);
+    logger(&mut aux, 10, "second ten", &log);
+
+    assert!(aux.dispatch_input(key(KeyInput::A)));
+    assert_eq!(
+        take(&log),
+        ["ten A", "second ten A", "zero A", "negative A"]
+    );
+}
+
+#[test]
+fn consuming_stops_the_chain() {
+    let log = Log::default();
+    let mut aux = new_aux();
+    logger(&mut aux, 0, "after", &log);
+    aux.add_filter(5, |_, input| input.consume());
+    logger(&mut aux, 10, "before", &log);
+
+    assert!(
+        !aux.dispatch_input(key(KeyInput::A)),
+        "consumed input shouldn't be emitted"
+    );
+    assert_eq!(take(&log), ["before A"]);
+}
+
+#[test]
+fn rewrites_reach_later_filters() {
+    let log = Log::default();
+    let mut aux = new_aux();
+    aux.add_filter(10, |_, input| {
+        if input.get() == &key(KeyInput::A) {
+            input.replace(key(KeyInput::B));
+        }
+    });
+    logger(&mut aux, 0, "after", &log);
+
+    assert!(aux.dispatch_input(key(KeyInput::A)));
+    assert_eq!(take(&log), ["after B"]);
+}
+
+#[test]
+fn removed_by_handle() {
+    let log = Log::default();
+    let mut aux = new_aux();
+    let first = logger(&mut aux, 10, "first", &log);
+    let second = logger(&mut aux, 0, "second", &log);
+
+    assert!(aux.remove_filter(first));
+    assert!(!aux.remove_filter(first));
+    aux.dispatch_input(key(KeyInput::A));
+    assert_eq!(take(&log), ["second A"]);
+
+    // removing a filter which hasn't run yet skips it for the input being dispatched.
+    aux.add_filter(20, move |aux, _| {
+        aux.remove_filter(second);
+    });
+    aux.dispatch_input(key(KeyInput::A));
+    assert!(take(&log).is_empty());
+    assert_eq!(aux.filters.len(), 1);
+}
+
+#[test]
+fn dispatching_filters_skip_their_own_input() {
+    let log = Log::default();
+    let mut aux = new_aux();
+    let inner = Rc::clone(&log);
+    aux.add_filter(10, move |aux, input| {
+        inner.borrow_mut().push("dispatcher".into());
+        if input.get() == &key(KeyInput::A) {
+            aux.dispatch_input(key(KeyInput::B));
+        }
+    });
+    logger(&mut aux, 0, "after", &log);
+
+    aux.dispatch_input(key(KeyInput::A));
+    assert_eq!(take(&log), ["dispatcher", "after B", "after A"]);
+}
@@ -0,0 +1,192 @@
+//! Confining input to the subtree holding an input grab.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{
+            self,
+            view::{ChildRef, View},
+        },
+    },
+    reclutch::display as gfx,
+    std::{cell::RefCell, rc::Rc},
+};
+
+type Log = Rc<RefCell<Vec<&'static str>>>;
+
+struct Buttons {
+    ui: Headless<(), View<(), ()>>,
+    outside: ChildRef<kit::Button<()>>,
+    inside: ChildRef<kit::Button<()>>,
+    log: Log,
+}
+
+fn point_outside() -> gfx::Point {
+    gfx::Point::new(20.0, 20.0)
+}
+
+fn point_inside() -> gfx::Point {
+    gfx::Point::new(70.0, 20.0)
+}
+
+impl Buttons {
+    fn new() -> Self {
+        let log = Log::default();
+        let mut children = None;
+        let ui = Headless::new(
+            |parent, aux| {
+                let mut view = View::new(parent, aux, ());
+                let outside = button(&mut view, aux, "outside", 10.0, &log);
+                let inside = button(&mut view, aux, "inside", 60.0, &log);
+                children = Some((outside, inside));
+                view
+            },
+            (),
+            Box::new(NullTheme),
+            gfx::Size::new(100.0, 100.0),
+        );
+        let (outside, inside) = children.unwrap();
+        Buttons {
+            ui,
+            outside,
+            inside,
+            log,
+        }
+    }
+
+    fn common(&self, child: ChildRef<kit::Button<()>>) -> ui::CommonRef {
+        self.ui.root().get(child).unwrap().common().clone()
+    }
+
+    fn take(&self) -> Vec<&'static str> {
+        self.log.borrow_mut().drain(..).collect()
+    }
+}
+
+fn button(
+    view: &mut View<(), ()>,
+    aux: &mut ui::Aux<()>,
+    name: &'static str,
+    x: f32,
+    log: &Log,
+) -> ChildRef<kit::Button<()>> {
+    let child = view.child(kit::Button::new, aux);
+    view.get(child).unwrap().set_rect(gfx::Rect::new(
+        gfx::Point::new(x, 10.0),
+        gfx::Size::new(30.0, 20.0),
+    ));
+    let presses = Rc::clone(log);
+    view.handle(child, move |_, _, _: &kit::PressEvent| {
+        presses.borrow_mut().push(name)
+    });
+    let keys = Rc::clone(log);
+    view.handle(child, move |_, _, _: &ui::KeyPressEvent| {
+        keys.borrow_mut().push(name)
+    });
+    let dismissals = Rc::clone(log);
+    view.handle(child, move |_, _, _: &ui::GrabDismissEvent| {
+        dismissals.borrow_mut().push("dismiss")
+    });
+    child
+}
+
+#[test]
+fn pointer_input_is_confined_to_the_grab() {
+    let mut buttons = Buttons::new();
+    let inside = buttons.common(buttons.inside);
+    buttons.ui.aux_mut().push_grab(&inside, false);
+
+    buttons.ui.click(point_outside());
+    assert!(buttons.take().is_empty());
+    buttons.ui.click(point_inside());
+    assert_eq!(buttons.take(), ["inside"]);
+
+    assert!(buttons.ui.aux_mut().pop_grab(&inside));
+    buttons.ui.click(point_outside());
+    assert_eq!(buttons.take(), ["outside"]);
+}
+
+#[test]
+fn key_input_is_confined_to_the_grab() {
+    let mut buttons = Buttons::new();
+    let outside = buttons.common(buttons.outside);
+    let inside = buttons.common(buttons.inside);
+    buttons.ui.aux_mut().grab_focus(outside.clone());
+    buttons.ui.key_press(ui::KeyInput::A);
+    assert_eq!(buttons.take(), ["outside"]);
+
+    buttons.ui.aux_mut().push_grab(&inside, false);
+    assert!(
+        !buttons.ui.aux().has_focus(&outside),
+        "focus outside the grab should be cleared"
+    );
+    buttons.ui.key_press(ui::KeyInput::A);
+    assert_eq!(buttons.take(), ["inside"]);
+}
+
+#[test]
+fn innermost_grab_wins() {
+    let mut buttons = Buttons::new();
+    let root = buttons.ui.root().common().clone();
+    let inside = buttons.common(buttons.inside);
+    buttons.ui.aux_mut().push_grab(&root, false);
+    buttons.ui.aux_mut().push_grab(&inside, false);
+
+    buttons.ui.click(point_outside());
+    assert!(buttons.take().is_empty());
+
+    assert!(buttons.ui.aux_mut().pop_grab(&inside));
+    assert!(buttons.ui.aux().input_grab() == Some(root.clone()));
+    buttons.ui.click(point_outside());
+    assert_eq!(buttons.take(), ["outside"]);
+}
+
+#[test]
+fn outside_press_dismisses() {
+    let mut buttons = Buttons::new();
+    let inside = buttons.common(buttons.inside);
+    buttons.ui.aux_mut().push_grab(&inside, true);
+
+    buttons.ui.click(point_inside());
+    assert_eq!(buttons.take(), ["inside"]);
+    buttons.ui.click(point_outside());
+    assert_eq!(buttons.take(), ["dismiss"]);
+    assert!(
+        buttons.ui.aux().input_grab() == Some(inside),
+        "dismissal shouldn't release the grab"
+    );
+}
+
+#[test]
+fn detaching_releases_the_grab() {
+    let mut buttons = Buttons::new();
+    let inside = buttons.common(buttons.inside);
+    buttons.ui.aux_mut().push_grab(&inside, false);
+    drop(inside);
+
+    let child = buttons.inside;
+    assert!(buttons.ui.root_mut().remove(child).is_some());
+    buttons.ui.update();
+    assert!(buttons.ui.aux().input_grab().is_none());
+
+    buttons.ui.click(point_outside());
+    assert_eq!(buttons.take(), ["outside"]);
+}
+
+#[test]
+fn focus_cannot_leave_the_grab() {
+    let mut buttons = Buttons::new();
+    let outside = buttons.common(buttons.outside);
+    let inside = buttons.common(buttons.inside);
+    buttons.ui.aux_mut().push_grab(&inside, false);
+
+    buttons.ui.aux_mut().grab_focus(outside.clone());
+    assert!(!buttons.ui.aux().has_focus(&outside));
+    buttons.ui.aux_mut().grab_focus(inside.clone());
+    assert!(buttons.ui.aux().has_focus(&inside));
+}
@@ -0,0 +1,305 @@
+//! Updating only the layouts invalidated by a change in size, and only as far up as sizes change.
+
+use {
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        theme::{self, AnyPainter, Theme},
+        ui::{
+            self,
+            layout::{self, Layout, VStack},
+            view::{ChildRef, View},
+        },
+    },
+    reclutch::display as gfx,
+    std::{cell::Cell, rc::Rc},
+};
+
+/// Sizes labels by their text, 10 pixels per character and 10 pixels high.
+struct TextPainter;
+
+impl AnyPainter<()> for TextPainter {
+    fn paint(
+        &mut self,
+        _obj: &mut dyn std::any::Any,
+        _aux: &mut ui::Aux<()>,
+    ) -> Vec<gfx::DisplayCommand> {
+        Vec::new()
+    }
+
+    fn size_hint(&mut self, obj: &mut dyn std::any::Any) -> gfx::Size {
+        match obj.downcast_ref::<kit::Label<()>>().map(|x| x.text()) {
+            Some(gfx::DisplayText::Simple(text)) => {
+                gfx::Size::new(text.chars().count() as f32 * 10.0, 10.0)
+            }
+            _ => gfx::Size::zero(),
+        }
+    }
+
+    fn metrics(&self, _obj: &dyn std::any::Any, _metric: &'static str) -> Option<f32> {
+        Some(0.0)
+    }
+}
+
+struct TextTheme;
+
+impl Theme<()> for TextTheme {
+    fn painter(&self, _p: &'static str) -> Box<dyn AnyPainter<()>> {
+        Box::new(TextPainter)
+    }
+
+    fn color(&self, _c: &'static str) -> gfx::Color {
+        gfx::Color::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    fn standards(&self) -> theme::Standards {
+        theme::Standards {
+            label_size: 12.0,
+            button_text_alignment: layout::Alignment::Middle,
+        }
+    }
+}
+
+/// Layout which counts how often it's updated.
+struct Counted<L: Layout> {
+    inner: L,
+    updates: Rc<Cell<u32>>,
+}
+
+impl<L: Layout> Layout for Counted<L> {
+    type Config = L::Config;
+    type Id = L::Id;
+
+    fn push(&mut self, item: impl Into<layout::Item>, config: L::Config) -> L::Id {
+        self.inner.push(item, config)
+    }
+
+    fn remove(&mut self, id: &L::Id) -> Option<layout::Item> {
+        self.inner.remove(id)
+    }
+
+    fn get(&self, id: &L::Id) -> Option<&layout::Item> {
+        self.inner.get(id)
+    }
+
+    fn get_mut(&mut self, id: &L::Id) -> Option<&mut layout::Item> {
+        self.inner.get_mut(id)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn items(&self) -> Vec<(&layout::Item, &L::Id)> {
+        self.inner.items()
+    }
+
+    fn min_size(&self) -> gfx::Size {
+        self.inner.min_size()
+    }
+
+    fn update(&mut self, bounds: gfx::Rect) {
+        self.updates.set(self.updates.get() + 1);
+        self.inner.update(bounds)
+    }
+}
+
+fn label(view: &mut View<(), ()>, aux: &mut ui::Aux<()>, text: &str) -> ChildRef<kit::Label<()>> {
+    let label = view.child(kit::Label::new, aux);
+    view.get_mut(label).unwrap().set_text(text.to_string());
+    label
+}
+
+/// Creates a shrinking view with a vertical stack of `items`, returning the update counter of the stack.
+fn stack(view: &View<(), ()>, items: Vec<ui::CommonRef>) -> Rc<Cell<u32>> {
+    let updates = Rc::new(Cell::new(0));
+    let mut stack = Counted {
+        inner: VStack::new(),
+        updates: Rc::clone(&updates),
+    }
+    .into_node(None);
+    for item in items {
+        stack.push(item, None);
+    }
+    view.set_layout(stack);
+    view.set_layout_mode(ui::LayoutMode::Shrink);
+    updates
+}
+
+struct Tree {
+    ui: Headless<(), View<(), ()>>,
+    panel: ChildRef<View<(), ()>>,
+    /// Label in the root view, beside the panel.
+    outer: ChildRef<kit::Label<()>>,
+    /// Narrower of the two labels in the panel.
+    inner: ChildRef<kit::Label<()>>,
+    root_updates: Rc<Cell<u32>>,
+    panel_updates: Rc<Cell<u32>>,
+}
+
+impl Tree {
+    /// Root view stacking a panel (holding a 2 and a 4 character label) above a 3 character label.
+    fn new() -> Self {
+        let mut refs = None;
+        let ui = Headless::new(
+            |parent, aux| {
+                let mut view = View::new(parent, aux, ());
+                let mut inner = None;
+                let mut panel_updates = None;
+                let panel = view.child(
+                    |parent, aux| {
+                        let mut panel = View::new(parent, aux, ());
+                        let narrow = label(&mut panel, aux, "ab");
+                        let wide = label(&mut panel, aux, "abcd");
+                        let items = vec![
+                            panel.get(narrow).unwrap().common().clone(),
+                            panel.get(wide).unwrap().common().clone(),
+                        ];
+                        panel_updates = Some(stack(&panel, items));
+                        inner = Some(narrow);
+                        panel
+                    },
+                    aux,
+                );
+                let outer = label(&mut view, aux, "abc");
+                let items = vec![
+                    view.get(panel).unwrap().common().clone(),
+                    view.get(outer).unwrap().common().clone(),
+                ];
+                let root_updates = stack(&view, items);
+                refs = Some((
+                    panel,
+                    outer,
+                    inner.unwrap(),
+                    root_updates,
+                    panel_updates.unwrap(),
+                ));
+                view
+            },
+            (),
+            Box::new(TextTheme),
+            gfx::Size::new(200.0, 200.0),
+        );
+        let (panel, outer, inner, root_updates, panel_updates) = refs.unwrap();
+        Tree {
+            ui,
+            panel,
+            outer,
+            inner,
+            root_updates,
+            panel_updates,
+        }
+    }
+
+    /// Updates the UI, returning how often the root and panel layouts were updated.
+    fn update(&mut self) -> (u32, u32) {
+        self.root_updates.set(0);
+        self.panel_updates.set(0);
+        self.ui.update();
+        (self.root_updates.get(), self.panel_updates.get())
+    }
+
+    fn root_size(&self) -> gfx::Size {
+        self.ui.root().common().read(|x| x.size())
+    }
+}
+
+#[test]
+fn initial_layout() {
+    let mut tree = Tree::new();
+    assert_eq!(tree.root_size(), gfx::Size::new(40.0, 30.0));
+    let outer = tree
+        .ui
+        .root()
+        .get(tree.outer)
+        .unwrap()
+        .common()
+        .read(|x| x.rect());
+    assert_eq!(outer.origin, gfx::Point::new(0.0, 20.0));
+    assert_eq!(tree.update(), (0, 0), "nothing was invalidated");
+}
+
+#[test]
+fn resized_label_updates_its_parent_once() {
+    let mut tree = Tree::new();
+    let outer = tree.outer;
+    tree.ui
+        .root_mut()
+        .get_mut(outer)
+        .unwrap()
+        .set_text("abcdefgh".to_string());
+
+    assert_eq!(tree.update(), (1, 0), "the panel is unrelated to the label");
+    assert_eq!(tree.root_size(), gfx::Size::new(80.0, 30.0));
+    assert_eq!(tree.update(), (0, 0));
+}
+
+#[test]
+fn unchanged_size_stops_propagation() {
+    let mut tree = Tree::new();
+    let (panel, inner) = (tree.panel, tree.inner);
+    tree.ui
+        .root_mut()
+        .get_mut(panel)
+        .unwrap()
+        .get_mut(inner)
+        .unwrap()
+        .set_text("abc".to_string());
+
+    assert_eq!(
+        tree.update(),
+        (0, 1),
+        "the panel is as wide as its wider label either way"
+    );
+    assert_eq!(tree.root_size(), gfx::Size::new(40.0, 30.0));
+}
+
+#[test]
+fn grown_panel_updates_the_root() {
+    let mut tree = Tree::new();
+    let (panel, inner) = (tree.panel, tree.inner);
+    tree.ui
+        .root_mut()
+        .get_mut(panel)
+        .unwrap()
+        .get_mut(inner)
+        .unwrap()
+        .set_text("abcdef".to_string());
+
+    assert_eq!(tree.update(), (1, 1));
+    assert_eq!(tree.root_size(), gfx::Size::new(60.0, 30.0));
+}
+
+/// Items pushed into a layout after the initial pass (as in the todos example) are sized by their own layout, and the
+/// layouts they're pushed into are updated with that size.
+#[test]
+fn pushed_items_are_laid_out() {
+    let mut tree = Tree::new();
+    let (view, aux) = tree.ui.parts_mut();
+    let item = view.child(
+        |parent, aux| {
+            let mut item = View::new(parent, aux, ());
+            let text = label(&mut item, aux, "abcde");
+            let items = vec![item.get(text).unwrap().common().clone()];
+            stack(&item, items);
+            item
+        },
+        aux,
+    );
+    let common = view.get(item).unwrap().common().clone();
+    view.common().with(|x| {
+        x.layout_mut()
+            .unwrap()
+            .cast_mut::<Counted<VStack>>()
+            .unwrap()
+            .push(common.clone(), None)
+    });
+
+    assert_eq!(tree.update(), (1, 0));
+    assert_eq!(
+        common.read(|x| x.rect()),
+        gfx::Rect::new(gfx::Point::new(0.0, 30.0), gfx::Size::new(50.0, 10.0))
+    );
+    assert_eq!(tree.root_size(), gfx::Size::new(50.0, 40.0));
+}
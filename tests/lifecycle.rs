@@ -0,0 +1,78 @@
+//! Releasing the widgets removed from a view, along with the handlers for them.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{lifecycle::LeakCheck, view::View, CommonRef},
+    },
+    reclutch::display as gfx,
+};
+
+#[test]
+fn removed_items_are_released() {
+    let mut ui = Headless::new(
+        |parent, aux| View::new(parent, aux, ()),
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    );
+    let check = LeakCheck::new(&ui.aux().lifecycle);
+
+    let (view, aux) = ui.parts_mut();
+    let items: Vec<_> = (0..10)
+        .map(|_| {
+            let item = view.child(kit::Button::new, aux);
+            view.handle(item, |_, _, _: &kit::PressEvent| {});
+            item
+        })
+        .collect();
+    ui.update();
+    assert!(check.leaked().is_empty());
+    let handled = ui.root().listener().handled_ids().count();
+    assert_eq!(handled, items.len());
+
+    for &item in &items[..5] {
+        assert!(ui.root_mut().remove(item).is_some());
+    }
+    ui.update();
+
+    assert!(
+        check.leaked().is_empty(),
+        "removed items should be released"
+    );
+    assert_eq!(
+        ui.root().listener().handled_ids().count(),
+        handled - 5,
+        "handlers for removed items should be removed"
+    );
+}
+
+#[test]
+fn widgets_created_in_a_borrowed_parent_are_tracked() {
+    let mut ui = Headless::new(
+        |parent, aux| View::new(parent, aux, ()),
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    );
+    let check = LeakCheck::new(&ui.aux().lifecycle);
+
+    let root = ui.root().common().clone();
+    let child = root.with(|_| CommonRef::new(root.clone()));
+    assert!(child.read(|x| x.parent()) == Some(root));
+    ui.update();
+    assert!(check.leaked().is_empty());
+
+    child.with(|x| x.mark_for_detach());
+    ui.update();
+    assert_eq!(
+        check.leaked(),
+        vec![child.read(|x| x.id())],
+        "the child should share the lifecycle of the tree"
+    );
+}
@@ -0,0 +1,165 @@
+//! Pointer input routing over a grid of buttons.
+//!
+//! Runs headless with a theme which paints nothing; moves the cursor across the grid, checking that exactly one button
+//! is hovered at a time.
+//!
+//! The benchmark with a 100 by 100 grid is ignored by default, since it is slow without optimizations:
+//! `cargo test --release --test pointer_routing -- --ignored`
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{headless::Headless, kit, prelude::*, ui},
+    reclutch::display as gfx,
+    std::time::{Duration, Instant},
+};
+
+const BUTTON_WIDTH: f32 = 20.0;
+const BUTTON_HEIGHT: f32 = 10.0;
+
+struct Grid {
+    columns: usize,
+    buttons: Vec<kit::Button<()>>,
+    hovered: i32,
+
+    common: ui::CommonRef,
+    listener: ui::Listener<(ui::Write<Self>, ui::Write<ui::Aux<()>>)>,
+}
+
+impl Grid {
+    fn new(parent: ui::CommonRef, aux: &mut ui::Aux<()>, columns: usize, rows: usize) -> Self {
+        let common = ui::CommonRef::new(parent);
+        let mut listener: ui::Listener<(ui::Write<Self>, ui::Write<ui::Aux<()>>)> = aux.listen();
+        let mut buttons = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let button = kit::Button::new(common.clone(), aux);
+                button.set_rect(gfx::Rect::new(
+                    gfx::Point::new(column as f32 * BUTTON_WIDTH, row as f32 * BUTTON_HEIGHT),
+                    gfx::Size::new(BUTTON_WIDTH, BUTTON_HEIGHT),
+                ));
+
+                listener.on(button.id(), |(obj, _), _: &kit::BeginHoverEvent| {
+                    obj.hovered += 1
+                });
+                listener.on(button.id(), |(obj, _), _: &kit::EndHoverEvent| {
+                    obj.hovered -= 1
+                });
+                buttons.push(button);
+            }
+        }
+
+        Grid {
+            columns,
+            buttons,
+            hovered: 0,
+
+            common,
+            listener,
+        }
+    }
+}
+
+impl Element for Grid {
+    type Aux = ();
+
+    #[inline]
+    fn common(&self) -> &ui::CommonRef {
+        &self.common
+    }
+
+    fn update(&mut self, aux: &mut ui::Aux<()>) {
+        ui::dispatch((self, aux), |(x, _): (&mut Self, _)| &mut x.listener);
+    }
+}
+
+impl WidgetChildren<()> for Grid {
+    fn children(&self) -> Vec<&dyn WidgetChildren<()>> {
+        self.buttons
+            .iter()
+            .map(|x| x as &dyn WidgetChildren<()>)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn WidgetChildren<()>> {
+        self.buttons
+            .iter_mut()
+            .map(|x| x as &mut dyn WidgetChildren<()>)
+            .collect()
+    }
+
+    fn for_each_child(&self, f: &mut dyn FnMut(&dyn WidgetChildren<()>)) {
+        for button in &self.buttons {
+            f(button);
+        }
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn WidgetChildren<()>)) {
+        for button in &mut self.buttons {
+            f(button);
+        }
+    }
+}
+
+/// Most events emitted per mouse move: the move itself, to the global queue and to the buttons entered and left, and the
+/// hover events of those buttons. Any more would mean input is delivered to widgets it doesn't affect.
+const MAX_EVENTS_PER_MOVE: usize = 8;
+
+/// Moves the cursor across a `columns` by `rows` grid `moves` times, returning the average time per move.
+///
+/// Checks that the number of events emitted per move doesn't exceed [`MAX_EVENTS_PER_MOVE`], regardless of the size of the
+/// grid.
+fn route(columns: usize, rows: usize, moves: usize) -> Duration {
+    let size = gfx::Size::new(columns as f32 * BUTTON_WIDTH, rows as f32 * BUTTON_HEIGHT);
+    let mut ui = Headless::new(
+        |parent, aux| Grid::new(parent, aux, columns, rows),
+        (),
+        Box::new(NullTheme),
+        size,
+    );
+    // registers the buttons as pointer targets.
+    ui.update();
+    assert_eq!(ui.root().buttons.len(), ui.root().columns * rows);
+
+    ui.aux().stats.take();
+    let start = Instant::now();
+    for i in 0..moves {
+        // zig-zags through the grid, landing on a different button every move.
+        let column = (i * 7) % columns;
+        let row = (i * 13) % rows;
+        ui.mouse_move(gfx::Point::new(
+            (column as f32 + 0.5) * BUTTON_WIDTH,
+            (row as f32 + 0.5) * BUTTON_HEIGHT,
+        ));
+        assert_eq!(ui.root().hovered, 1, "exactly one button should be hovered");
+        let events = ui.aux().stats.take().events_emitted;
+        assert!(
+            events <= MAX_EVENTS_PER_MOVE,
+            "{} events were emitted for a single move",
+            events
+        );
+    }
+    let elapsed = start.elapsed();
+
+    ui.mouse_move(gfx::Point::new(-10.0, -10.0));
+    assert_eq!(ui.root().hovered, 0, "the last button should end its hover");
+
+    elapsed / moves as u32
+}
+
+#[test]
+fn hovers_one_button_at_a_time() {
+    route(10, 10, 100);
+}
+
+#[test]
+#[ignore]
+fn route_10k_buttons() {
+    let per_move = route(100, 100, 1000);
+    assert!(
+        per_move < Duration::from_millis(5),
+        "routing a mouse move over 10000 buttons took {:?}",
+        per_move
+    );
+}
@@ -0,0 +1,107 @@
+//! Replaying recorded input into a headless UI.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{
+            self,
+            record::{RecordedEntry, RecordedInput, Recording},
+            timer::Clock,
+            view::View,
+        },
+    },
+    reclutch::display as gfx,
+    std::time::Duration,
+};
+
+struct Tick;
+
+fn new_ui() -> Headless<(), View<(), ()>> {
+    Headless::new(
+        |parent, aux| {
+            let mut view = View::new(parent, aux, ());
+            view.child(kit::Button::new, aux);
+            view
+        },
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    )
+}
+
+fn entry(time: u64, frame: u64, input: ui::InputEvent) -> RecordedEntry {
+    RecordedEntry {
+        time: Duration::from_millis(time),
+        frame,
+        input: RecordedInput::Input(input),
+    }
+}
+
+#[test]
+fn updates_once_per_frame() {
+    let mut ui = new_ui();
+    let position = gfx::Point::new(10.0, 10.0);
+    let recording = Recording {
+        entries: vec![
+            entry(0, 3, ui::InputEvent::MouseMove(position)),
+            entry(
+                0,
+                3,
+                ui::InputEvent::MousePress(ui::MouseButton::Left, position),
+            ),
+            entry(
+                20,
+                4,
+                ui::InputEvent::MouseRelease(ui::MouseButton::Left, position),
+            ),
+        ],
+    };
+
+    let frame = ui.aux().trace.frame();
+    let start = ui.clock().now();
+    ui.replay(&recording);
+    assert_eq!(ui.aux().trace.frame(), frame + 2);
+    assert_eq!(ui.cursor(), position);
+    assert_eq!(ui.clock().now() - start, Duration::from_millis(20));
+}
+
+#[test]
+fn traces_input_and_timers() {
+    let mut ui = new_ui();
+    let id = ui.aux().id;
+    ui.aux_mut()
+        .set_timeout(&id, Duration::from_millis(10), Tick);
+    ui.aux_mut().trace.enable();
+
+    let position = gfx::Point::new(10.0, 10.0);
+    ui.replay(&Recording {
+        entries: vec![
+            entry(
+                0,
+                0,
+                ui::InputEvent::MousePress(ui::MouseButton::Left, position),
+            ),
+            entry(
+                10,
+                1,
+                ui::InputEvent::MouseRelease(ui::MouseButton::Left, position),
+            ),
+        ],
+    });
+
+    let types: Vec<_> = ui
+        .aux()
+        .trace
+        .entries()
+        .into_iter()
+        .map(|x| x.type_name)
+        .collect();
+    assert!(types.iter().any(|x| x.ends_with("MousePressEvent")));
+    assert!(types.iter().any(|x| x.ends_with("MouseReleaseEvent")));
+    assert!(types.iter().any(|x| x.ends_with("Tick")));
+}
@@ -0,0 +1,45 @@
+//! Stability of the text format of widget snapshots.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{snapshot::WidgetSnapshot, view::View},
+    },
+    reclutch::display as gfx,
+};
+
+fn capture() -> (WidgetSnapshot, String) {
+    let ui = Headless::new(
+        |parent, aux| {
+            let mut view = View::new(parent, aux, ());
+            let button = view.child(kit::Button::new, aux);
+            view.get(button).unwrap().set_name("save".to_string());
+            view.child(kit::Label::new, aux);
+            view
+        },
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    );
+    let snapshot = WidgetSnapshot::capture(ui.root(), ui.aux());
+    let text = snapshot.to_text();
+    (snapshot, text)
+}
+
+#[test]
+fn text_is_stable_across_captures() {
+    let (first, first_text) = capture();
+    let (second, second_text) = capture();
+
+    assert_ne!(first.id, second.id, "each capture has widgets of its own");
+    assert_eq!(first_text, second_text);
+    assert_eq!(first.diff(&second), None);
+
+    assert!(first_text.starts_with("View @0 "));
+    assert!(first_text.contains("Button @1 #save "));
+}
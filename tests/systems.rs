@@ -0,0 +1,20 @@
+//! The default order of the systems once the toolkit has registered its own.
+
+#![cfg(feature = "kit")]
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{kit, ui},
+};
+
+#[test]
+fn focus_runs_between_components_and_update() {
+    let mut aux = ui::Aux::new((), Box::new(NullTheme), ui::CommonRef::new(None));
+    kit::register_systems(&mut aux).unwrap();
+    assert_eq!(
+        aux.systems.names(),
+        ["components", "focus", "update", "layout"]
+    );
+}
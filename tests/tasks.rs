@@ -0,0 +1,203 @@
+//! Spawning tasks owned by widgets, on the manual clock of the headless UI.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{
+            self,
+            task::{self, TaskOutputEvent, TaskPanicEvent},
+            view::{ChildRef, View},
+        },
+    },
+    reclutch::display as gfx,
+    std::{cell::RefCell, rc::Rc, time::Duration},
+};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+struct Tasks {
+    ui: Headless<(), View<(), ()>>,
+    owner: ChildRef<kit::Label<()>>,
+    log: Log,
+}
+
+impl Tasks {
+    fn new() -> Self {
+        let log = Log::default();
+        let mut owner = None;
+        let ui = Headless::new(
+            |parent, aux| {
+                let mut view = View::new(parent, aux, ());
+                let label = view.child(kit::Label::new, aux);
+                let outputs = Rc::clone(&log);
+                view.handle(label, move |_, _, ev: &TaskOutputEvent<u32>| {
+                    outputs.borrow_mut().push(format!("output {}", ev.output))
+                });
+                let panics = Rc::clone(&log);
+                view.handle(label, move |_, _, ev: &TaskPanicEvent| {
+                    panics
+                        .borrow_mut()
+                        .push(format!("panic {}", ev.message.as_deref().unwrap_or("")))
+                });
+                owner = Some(label);
+                view
+            },
+            (),
+            Box::new(NullTheme),
+            gfx::Size::new(100.0, 100.0),
+        );
+        Tasks {
+            ui,
+            owner: owner.unwrap(),
+            log,
+        }
+    }
+
+    fn owner(&self) -> ui::CommonRef {
+        self.ui.root().get(self.owner).unwrap().common().clone()
+    }
+
+    fn spawn(
+        &mut self,
+        future: impl std::future::Future<Output = u32> + 'static,
+    ) -> task::TaskHandle {
+        let owner = self.owner();
+        self.ui.aux_mut().spawn(&owner, future)
+    }
+
+    fn take(&self) -> Vec<String> {
+        self.log.borrow_mut().drain(..).collect()
+    }
+}
+
+/// Sets its flag once dropped, to tell whether a task's future was dropped.
+struct DropFlag(Rc<RefCell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        *self.0.borrow_mut() = true;
+    }
+}
+
+fn fail() -> u32 {
+    panic!("task failed")
+}
+
+#[test]
+fn output_is_emitted_from_the_owner() {
+    let mut tasks = Tasks::new();
+    let handle = tasks.spawn(async { 1 });
+    assert!(tasks.ui.aux().tasks.is_active(handle));
+
+    tasks.ui.update();
+    assert_eq!(tasks.take(), ["output 1"]);
+    assert!(!tasks.ui.aux().tasks.is_active(handle));
+}
+
+#[test]
+fn delay_follows_the_manual_clock() {
+    let mut tasks = Tasks::new();
+    let start = tasks.ui.aux().timers.now();
+    let delay = tasks.ui.aux().delay(Duration::from_millis(100));
+    tasks.spawn(async move {
+        delay.await;
+        2
+    });
+
+    tasks.ui.update();
+    assert!(tasks.take().is_empty());
+    assert_eq!(
+        tasks.ui.aux().next_deadline(),
+        Some(start + Duration::from_millis(100))
+    );
+
+    tasks.ui.advance(Duration::from_millis(99));
+    assert!(tasks.take().is_empty());
+    tasks.ui.advance(Duration::from_millis(1));
+    assert_eq!(tasks.take(), ["output 2"]);
+    assert!(tasks.ui.aux().next_deadline().is_none());
+}
+
+#[test]
+fn channel_is_fed_from_another_thread() {
+    let mut tasks = Tasks::new();
+    let (sender, mut receiver) = task::channel::<u32>();
+    tasks.spawn(async move {
+        let mut sum = 0;
+        while let Some(value) = receiver.recv().await {
+            sum += value;
+        }
+        sum
+    });
+    tasks.ui.update();
+    assert!(!tasks.ui.aux().tasks.has_ready());
+
+    std::thread::spawn(move || {
+        for value in 1..=3 {
+            sender.send(value).unwrap();
+        }
+    })
+    .join()
+    .unwrap();
+    assert!(
+        tasks.ui.aux().tasks.has_ready(),
+        "sending should wake the task"
+    );
+
+    tasks.ui.update();
+    assert_eq!(tasks.take(), ["output 6"]);
+}
+
+#[test]
+fn detaching_the_owner_cancels() {
+    let mut tasks = Tasks::new();
+    let dropped = Rc::new(RefCell::new(false));
+    let flag = DropFlag(Rc::clone(&dropped));
+    let delay = tasks.ui.aux().delay(Duration::from_millis(100));
+    let handle = tasks.spawn(async move {
+        let _flag = flag;
+        delay.await;
+        3
+    });
+    tasks.ui.update();
+
+    let owner = tasks.owner;
+    assert!(tasks.ui.root_mut().remove(owner).is_some());
+    tasks.ui.advance(Duration::from_millis(100));
+    assert!(*dropped.borrow(), "the future should be dropped");
+    assert!(!tasks.ui.aux().tasks.is_active(handle));
+    assert!(tasks.ui.aux().next_deadline().is_none());
+    assert!(tasks.take().is_empty());
+}
+
+#[test]
+fn cancelled_by_handle() {
+    let mut tasks = Tasks::new();
+    let handle = tasks.spawn(async { 4 });
+    assert!(tasks.ui.aux_mut().cancel_task(handle));
+    assert!(!tasks.ui.aux_mut().cancel_task(handle));
+
+    tasks.ui.update();
+    assert!(tasks.take().is_empty());
+}
+
+#[test]
+fn panic_is_emitted_from_the_owner() {
+    let mut tasks = Tasks::new();
+    let handle = tasks.spawn(async { fail() });
+
+    tasks.ui.update();
+    assert_eq!(tasks.take(), ["panic task failed"]);
+    assert!(!tasks.ui.aux().tasks.is_active(handle));
+
+    tasks.ui.update();
+    assert!(
+        tasks.take().is_empty(),
+        "a panicked task isn't polled again"
+    );
+}
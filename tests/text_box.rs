@@ -0,0 +1,87 @@
+//! Input method compositions and commits in a focused text box.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{
+            self,
+            view::{ChildRef, View},
+        },
+    },
+    reclutch::display as gfx,
+};
+
+fn focused_text_box() -> (Headless<(), View<(), ()>>, ChildRef<kit::TextBox<()>>) {
+    let mut text_box = None;
+    let mut ui = Headless::new(
+        |parent, aux| {
+            let mut view = View::new(parent, aux, ());
+            text_box = Some(view.child(kit::TextBox::new, aux));
+            view
+        },
+        (),
+        Box::new(NullTheme),
+        gfx::Size::new(100.0, 100.0),
+    );
+    let text_box = text_box.unwrap();
+    let common = ui.root().get(text_box).unwrap().common().clone();
+    ui.aux_mut().grab_focus(common);
+    ui.update();
+    (ui, text_box)
+}
+
+fn preedit(text: &str, cursor: Option<(usize, usize)>) -> ui::InputEvent {
+    ui::InputEvent::Preedit(ui::Preedit {
+        text: text.to_string(),
+        cursor,
+    })
+}
+
+#[test]
+fn composition_is_displayed_at_the_cursor() {
+    let (mut ui, text_box) = focused_text_box();
+    ui.type_text("ab");
+    ui.key_press(ui::KeyInput::Left);
+
+    ui.input(preedit("xyz", Some((1, 2))));
+    let tb = ui.root().get(text_box).unwrap();
+    assert_eq!(tb.text(), "ab", "the composition isn't part of the text");
+    assert_eq!(tb.display_text(), "axyzb");
+    assert_eq!(tb.cursor(), 1);
+    assert_eq!(tb.caret(), 3, "the caret follows the input method");
+
+    ui.input(preedit("xyz", None));
+    assert_eq!(ui.root().get(text_box).unwrap().caret(), 1);
+}
+
+#[test]
+fn commit_inserts_at_the_cursor() {
+    let (mut ui, text_box) = focused_text_box();
+    ui.type_text("ab");
+    ui.key_press(ui::KeyInput::Left);
+    ui.input(preedit("xyz", Some((3, 3))));
+
+    ui.input(ui::InputEvent::Commit("xyz".to_string()));
+    let tb = ui.root().get(text_box).unwrap();
+    assert_eq!(tb.text(), "axyzb");
+    assert!(tb.preedit().is_empty());
+    assert_eq!(tb.cursor(), 4);
+    assert_eq!(tb.caret(), 4);
+}
+
+#[test]
+fn empty_composition_cancels() {
+    let (mut ui, text_box) = focused_text_box();
+    ui.type_text("ab");
+    ui.input(preedit("xyz", Some((3, 3))));
+
+    ui.input(preedit("", None));
+    let tb = ui.root().get(text_box).unwrap();
+    assert_eq!(tb.display_text(), "ab");
+    assert_eq!(tb.caret(), 2);
+}
@@ -0,0 +1,165 @@
+//! Scheduling, repeating and cancelling timers on the manual clock of the headless UI.
+
+mod common;
+
+use {
+    common::NullTheme,
+    otway::{
+        headless::Headless,
+        kit,
+        prelude::*,
+        ui::{
+            timer::Timers,
+            view::{ChildRef, View},
+        },
+    },
+    reclutch::display as gfx,
+    std::{cell::Cell, rc::Rc, time::Duration},
+};
+
+#[derive(Clone)]
+struct Tick;
+
+struct Ticks {
+    ui: Headless<(), View<(), ()>>,
+    target: ChildRef<kit::Label<()>>,
+    count: Rc<Cell<u32>>,
+}
+
+impl Ticks {
+    fn new() -> Self {
+        let count = Rc::new(Cell::new(0));
+        let mut target = None;
+        let ui = Headless::new(
+            |parent, aux| {
+                let mut view = View::new(parent, aux, ());
+                let label = view.child(kit::Label::new, aux);
+                let count = Rc::clone(&count);
+                view.handle(label, move |_, _, _: &Tick| count.set(count.get() + 1));
+                target = Some(label);
+                view
+            },
+            (),
+            Box::new(NullTheme),
+            gfx::Size::new(100.0, 100.0),
+        );
+        Ticks {
+            ui,
+            target: target.unwrap(),
+            count,
+        }
+    }
+
+    fn id(&self) -> u64 {
+        self.ui.root().get(self.target).unwrap().id()
+    }
+
+    fn advance_ms(&mut self, ms: u64) -> u32 {
+        self.ui.advance(Duration::from_millis(ms));
+        self.count.get()
+    }
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn timeout_fires_once() {
+    let mut ticks = Ticks::new();
+    let id = ticks.id();
+    let timer = ticks.ui.aux_mut().set_timeout(&id, ms(100), Tick);
+
+    assert_eq!(ticks.advance_ms(99), 0);
+    assert_eq!(ticks.advance_ms(1), 1);
+    assert!(!ticks.ui.aux().timers.is_active(timer));
+    assert_eq!(ticks.advance_ms(1000), 1);
+}
+
+#[test]
+fn interval_repeats_and_skips_missed_intervals() {
+    let mut ticks = Ticks::new();
+    let id = ticks.id();
+    let timer = ticks.ui.aux_mut().set_interval(&id, ms(100), Tick);
+
+    assert_eq!(ticks.advance_ms(100), 1);
+    assert_eq!(ticks.advance_ms(100), 2);
+    // three intervals elapse at once, but the timer only fires once and is rescheduled from now.
+    assert_eq!(ticks.advance_ms(350), 3);
+    assert_eq!(ticks.advance_ms(99), 3);
+    assert_eq!(ticks.advance_ms(1), 4);
+    assert!(ticks.ui.aux().timers.is_active(timer));
+}
+
+#[test]
+fn cancelled_by_handle() {
+    let mut ticks = Ticks::new();
+    let id = ticks.id();
+    let timeout = ticks.ui.aux_mut().set_timeout(&id, ms(100), Tick);
+    let interval = ticks.ui.aux_mut().set_interval(&id, ms(100), Tick);
+
+    assert!(ticks.ui.aux_mut().cancel_timer(timeout));
+    assert!(!ticks.ui.aux_mut().cancel_timer(timeout));
+    assert_eq!(ticks.advance_ms(100), 1);
+
+    assert!(ticks.ui.aux_mut().cancel_timer(interval));
+    assert_eq!(ticks.advance_ms(100), 1);
+    assert!(ticks.ui.aux().next_deadline().is_none());
+}
+
+#[test]
+fn cancelled_with_owner() {
+    let mut ticks = Ticks::new();
+    let id = ticks.id();
+    let timer = ticks.ui.aux_mut().set_interval(&id, ms(100), Tick);
+    let owner = ticks.ui.root().get(ticks.target).unwrap().common().clone();
+    assert!(ticks.ui.aux_mut().timers.set_owner(timer, &owner));
+    drop(owner);
+
+    assert_eq!(ticks.advance_ms(100), 1);
+    let target = ticks.target;
+    ticks.ui.root_mut().remove(target);
+    assert_eq!(ticks.advance_ms(100), 1);
+    assert!(!ticks.ui.aux().timers.is_active(timer));
+}
+
+#[test]
+fn interval_is_clamped() {
+    let mut ticks = Ticks::new();
+    let id = ticks.id();
+    let start = ticks.ui.aux().timers.now();
+    ticks
+        .ui
+        .aux_mut()
+        .set_interval(&id, Duration::from_secs(0), Tick);
+    assert_eq!(
+        ticks.ui.aux().next_deadline(),
+        Some(start + Timers::MIN_INTERVAL)
+    );
+
+    ticks.ui.advance(Timers::MIN_INTERVAL);
+    assert_eq!(ticks.count.get(), 1);
+    ticks.ui.update();
+    assert_eq!(
+        ticks.count.get(),
+        1,
+        "the timer shouldn't fire without the clock advancing"
+    );
+}
+
+#[test]
+fn next_deadline_is_the_earliest() {
+    let mut ticks = Ticks::new();
+    let id = ticks.id();
+    let start = ticks.ui.aux().timers.now();
+    assert!(ticks.ui.aux().next_deadline().is_none());
+
+    ticks.ui.aux_mut().set_timeout(&id, ms(50), Tick);
+    ticks.ui.aux_mut().set_timeout(&id, ms(20), Tick);
+    assert_eq!(ticks.ui.aux().next_deadline(), Some(start + ms(20)));
+
+    ticks.advance_ms(20);
+    assert_eq!(ticks.ui.aux().next_deadline(), Some(start + ms(50)));
+    ticks.advance_ms(30);
+    assert!(ticks.ui.aux().next_deadline().is_none());
+}